@@ -0,0 +1,188 @@
+//! Compares loading a `Database` by parsing the text navdata with reading
+//! it back from the binary cache written by the first load.
+//!
+//! Run with `cargo bench --bench cache`. The navdata is copied from the
+//! directory in the `OLDNAV_NAVDATA_DIR` environment variable, e.g. the
+//! `Resources/default data` directory of x-plane 11. Without it, synthetic
+//! navdata of about the same size as x-plane 11's is generated. Either way
+//! the navdata is loaded from a temporary directory, so that the cache is
+//! not written next to the original files.
+
+extern crate oldnav_lib;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::Random;
+use oldnav_lib::navdata::cache::{self, CacheKey, CACHE_FILE_NAME};
+use oldnav_lib::navdata::database::{Database, NavdataFormat};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant};
+
+/// Number of times to repeat each load
+static ITERATIONS: usize = 5;
+
+/// Regions the synthetic waypoints are spread over
+static REGIONS: [&'static str; 4] = ["YM", "K1", "EG", "DT"];
+
+/// Write 200000 fixes, 20000 NDBs and 100000 airway segments joining the
+/// fixes into `dir`.
+fn write_synthetic_navdata(dir: &Path) -> io::Result<()> {
+    let mut random = Random::new(12345);
+
+    let mut fixes = BufWriter::new(try!(File::create(dir.join("earth_fix.dat"))));
+    try!(writeln!(fixes, "I\n1101 Version - synthetic OldNav benchmark data\n"));
+    for i in 0..200000 {
+        let lat = (random.next() * 2.0 - 1.0).asin().to_degrees();
+        let lon = random.next() * 360.0 - 180.0;
+        try!(writeln!(
+            fixes,
+            "{:13.9} {:14.9}  F{} ENRT {} 2118994",
+            lat,
+            lon,
+            i,
+            REGIONS[i % REGIONS.len()]
+        ));
+    }
+    try!(writeln!(fixes, "99"));
+
+    let mut navaids = BufWriter::new(try!(File::create(dir.join("earth_nav.dat"))));
+    try!(writeln!(navaids, "I\n1150 Version - synthetic OldNav benchmark data\n"));
+    for i in 0..20000 {
+        let lat = (random.next() * 2.0 - 1.0).asin().to_degrees();
+        let lon = random.next() * 360.0 - 180.0;
+        try!(writeln!(
+            navaids,
+            " 2 {:12.8} {:13.8}      0   396  25    0.000  N{}  ENRT {} N{} NDB",
+            lat,
+            lon,
+            i,
+            REGIONS[i % REGIONS.len()],
+            i
+        ));
+    }
+    try!(writeln!(navaids, "99"));
+
+    let mut airways = BufWriter::new(try!(File::create(dir.join("earth_awy.dat"))));
+    try!(writeln!(airways, "I\n1100 Version - synthetic OldNav benchmark data\n"));
+    for i in 0..100000 {
+        try!(writeln!(
+            airways,
+            "F{} {} 11 F{} {} 11 N 2 180 450 A{}",
+            i,
+            REGIONS[i % REGIONS.len()],
+            i + 1,
+            REGIONS[(i + 1) % REGIONS.len()],
+            i / 20
+        ));
+    }
+    try!(writeln!(airways, "99"));
+
+    try!(fs::copy(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/xp11/cycle_info.txt"),
+        dir.join("cycle_info.txt"),
+    ));
+    return Ok(());
+}
+
+/// Copy the navdata files in `from`, which are read when loading a
+/// `Database`, into `dir`.
+fn copy_navdata(from: &Path, dir: &Path) -> io::Result<()> {
+    let format = try!(NavdataFormat::detect(from).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "no navdata found")
+    }));
+
+    let mut files = format.source_files(from);
+    files.push(from.join("cycle_info.txt"));
+    for file in files {
+        try!(fs::copy(&file, dir.join(file.file_name().unwrap())));
+    }
+
+    return Ok(());
+}
+
+/// A temporary directory containing the navdata to load.
+fn navdata_dir() -> PathBuf {
+    let dir = env::temp_dir().join(format!("oldnav-bench-cache-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    if let Ok(from) = env::var("OLDNAV_NAVDATA_DIR") {
+        copy_navdata(Path::new(&from), &dir).expect(
+            "unable to copy the navdata in OLDNAV_NAVDATA_DIR",
+        );
+        println!("navdata from {}", from);
+    } else {
+        write_synthetic_navdata(&dir).unwrap();
+        println!("synthetic navdata (set OLDNAV_NAVDATA_DIR for real navdata)");
+    }
+
+    return dir;
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+    return duration.as_secs() as f64 * 1e3 + duration.subsec_nanos() as f64 / 1e6;
+}
+
+fn main() {
+    let dir = navdata_dir();
+    let resources_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources");
+    let cache_path = dir.join(CACHE_FILE_NAME);
+
+    // parsing the text also writes the cache, which is timed separately
+    // below so that it can be taken out of the parse time.
+    let mut parse = 0.0;
+    let mut db = None;
+    for _ in 0..ITERATIONS {
+        let _ = fs::remove_file(&cache_path);
+        let start = Instant::now();
+        let parsed = Database::new(dir.clone(), resources_dir.clone()).unwrap();
+        parse += milliseconds(start.elapsed());
+        assert!(!parsed.loaded_from_cache);
+        db = Some(parsed);
+    }
+    let db = db.unwrap();
+
+    let mut source_files = db.format.source_files(&dir);
+    source_files.push(resources_dir.join("icao_countries.txt"));
+    let source_paths: Vec<&Path> = source_files.iter().map(|path| path.as_path()).collect();
+    let key = CacheKey::new(db.cycle_info.airac_cycle, db.format, db.load_mode, &source_paths)
+        .unwrap();
+
+    let mut write = 0.0;
+    let write_path = dir.join("write.cache");
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        cache::write_cache(&write_path, &key, &db).unwrap();
+        write += milliseconds(start.elapsed());
+    }
+
+    let mut read = 0.0;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        let cached = Database::new(dir.clone(), resources_dir.clone()).unwrap();
+        read += milliseconds(start.elapsed());
+        assert!(cached.loaded_from_cache);
+    }
+
+    let parse = (parse - write) / ITERATIONS as f64;
+    let write = write / ITERATIONS as f64;
+    let read = read / ITERATIONS as f64;
+
+    println!(
+        "{} fixes, {} navaids, {} airway segments",
+        db.fixes.len(),
+        db.navaids.len(),
+        db.airway_graph.segments().len()
+    );
+    println!("{:<16} {:>10.1} ms", "parse text", parse);
+    println!("{:<16} {:>10.1} ms", "write cache", write);
+    println!("{:<16} {:>10.1} ms", "read cache", read);
+    println!("{:<16} {:>10.1}x", "speedup", parse / read);
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,78 @@
+//! Compares the bit interleaving geohash `encode()` and `decode()` with the
+//! reference implementations which bisect the bounds one bit at a time.
+//!
+//! Run with `cargo bench --bench geohash`.
+
+extern crate nalgebra;
+extern crate oldnav_lib;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::Random;
+use nalgebra::Vector2;
+use oldnav_lib::navdata::geohash::*;
+use std::time::{Duration, Instant};
+
+/// Number of times to repeat each benchmark over the positions
+static ITERATIONS: usize = 20;
+
+/// Positions spread over the earth.
+fn scattered_positions(n: usize) -> Vec<Vector2<f64>> {
+    let mut random = Random::new(12345);
+    return (0..n)
+        .map(|_| Vector2::new(random.next() * 360.0 - 180.0, random.next() * 180.0 - 90.0))
+        .collect();
+}
+
+fn nanoseconds(duration: Duration) -> f64 {
+    return duration.as_secs() as f64 * 1e9 + duration.subsec_nanos() as f64;
+}
+
+/// Time `f` over each of the `items`, and print the average time per item.
+fn bench<T, F>(name: &str, items: &[T], mut f: F)
+where
+    F: FnMut(&T) -> u64,
+{
+    // a checksum of the results, so they aren't optimised away
+    let mut checksum: u64 = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for item in items {
+            checksum = checksum.wrapping_add(f(item));
+        }
+    }
+
+    let per_item = nanoseconds(start.elapsed()) / (ITERATIONS * items.len()) as f64;
+    println!("{:<32} {:>8.1} ns (checksum {:x})", name, per_item, checksum);
+}
+
+fn main() {
+    let positions = scattered_positions(100000);
+
+    for &precision in &[16, 32, PRECISION_MAX] {
+        println!("precision {}:", precision);
+
+        bench("encode", &positions, |p| {
+            encode(p, precision, &LATLON_BOUNDS).unwrap()
+        });
+        bench("encode_bisect", &positions, |p| {
+            encode_bisect(p, precision, &LATLON_BOUNDS).unwrap()
+        });
+
+        let hashes: Vec<u64> = positions
+            .iter()
+            .map(|p| encode(p, precision, &LATLON_BOUNDS).unwrap())
+            .collect();
+
+        bench("decode", &hashes, |gh| {
+            decode(*gh, &LATLON_BOUNDS).unwrap().x_min.to_bits()
+        });
+        bench("decode_precision_bisect", &hashes, |gh| {
+            decode_precision_bisect(*gh, precision, &LATLON_BOUNDS)
+                .unwrap()
+                .x_min
+                .to_bits()
+        });
+    }
+}
@@ -0,0 +1,128 @@
+//! Compares a `GeohashIndex` along the Z-order (geohash) and Hilbert
+//! curves: the number of key ranges each radius query is split into, the
+//! number of candidates scanned, and the time per query.
+//!
+//! Run with `cargo bench --bench spatial_keys`. The fixes are read from the
+//! navdata directory in the `OLDNAV_NAVDATA_DIR` environment variable, e.g.
+//! the `Resources/default data` directory of x-plane 11. Without it, a
+//! synthetic distribution of fixes clustered around airports is used.
+
+extern crate oldnav_lib;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::Random;
+use oldnav_lib::navdata::coord::{SphericalCoordinate, METERS_PER_NAUTICAL_MILE};
+use oldnav_lib::navdata::database::Database;
+use oldnav_lib::navdata::geohash::{self, PRECISION_MAX};
+use oldnav_lib::navdata::geohash_index::{GeohashIndex, GEOHASH_INDEX_COVER_HASHES, IndexCurve};
+use oldnav_lib::navdata::waypoint::Waypoint;
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Number of queries for each radius
+static QUERIES: usize = 2000;
+
+/// Fixes clustered around 2000 airports, with 100 fixes each within 3
+/// degrees, plus 50000 spread over the whole earth.
+fn synthetic_fixes() -> Vec<Arc<Waypoint>> {
+    let mut random = Random::new(12345);
+    let mut fixes = Vec::new();
+
+    let mut add = |lat: f64, lon: f64| {
+        let pos = SphericalCoordinate::from_geographic(0.0, lat.max(-90.0).min(90.0), lon);
+        let code = format!("F{}", fixes.len());
+        fixes.push(Arc::new(Waypoint::new(&code, &code, pos, None)));
+    };
+
+    for _ in 0..2000 {
+        let lat = (random.next() * 1.6 - 0.8).asin().to_degrees();
+        let lon = random.next() * 360.0 - 180.0;
+        for _ in 0..100 {
+            let dlat = (random.next() - 0.5) * 6.0;
+            let dlon = (random.next() - 0.5) * 6.0;
+            add(lat + dlat, (lon + dlon + 540.0) % 360.0 - 180.0);
+        }
+    }
+
+    for _ in 0..50000 {
+        let lat = (random.next() * 2.0 - 1.0).asin().to_degrees();
+        add(lat, random.next() * 360.0 - 180.0);
+    }
+
+    return fixes;
+}
+
+fn load_fixes() -> Vec<Arc<Waypoint>> {
+    if let Ok(dir) = env::var("OLDNAV_NAVDATA_DIR") {
+        let resources_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources");
+        let db = Database::new(PathBuf::from(&dir), resources_dir)
+            .expect("unable to load the navdata in OLDNAV_NAVDATA_DIR");
+        println!("{} fixes from {}", db.fixes.len(), dir);
+        return db.fixes.clone();
+    }
+
+    let fixes = synthetic_fixes();
+    println!("{} synthetic fixes (set OLDNAV_NAVDATA_DIR for real ones)", fixes.len());
+    return fixes;
+}
+
+fn nanoseconds(duration: Duration) -> f64 {
+    return duration.as_secs() as f64 * 1e9 + duration.subsec_nanos() as f64;
+}
+
+fn main() {
+    let fixes = load_fixes();
+
+    // queries centred on fixes, as most are around an aircraft's route
+    let mut random = Random::new(4321);
+    let centres: Vec<SphericalCoordinate> = (0..QUERIES)
+        .map(|_| fixes[(random.next() * fixes.len() as f64) as usize].pos)
+        .collect();
+
+    for &curve in &[IndexCurve::ZOrder, IndexCurve::Hilbert] {
+        let start = Instant::now();
+        let index = GeohashIndex::build_with_curve(fixes.clone(), curve).unwrap();
+        println!(
+            "{:?}: built in {:.1} ms",
+            curve,
+            nanoseconds(start.elapsed()) / 1e6
+        );
+
+        for &radius_nm in &[10.0, 50.0, 250.0] {
+            let radius = radius_nm * METERS_PER_NAUTICAL_MILE;
+            let mut ranges = 0;
+            let mut candidates = 0;
+            let mut found = 0;
+
+            for centre in &centres {
+                let cover = geohash::cover_cap(
+                    centre,
+                    radius,
+                    PRECISION_MAX,
+                    GEOHASH_INDEX_COVER_HASHES,
+                ).unwrap();
+                ranges += index.key_ranges(&cover).len();
+                candidates += index.scan(&cover).len();
+            }
+
+            let start = Instant::now();
+            for centre in &centres {
+                found += index.within_radius(centre, radius, |fix| fix.pos).len();
+            }
+            let per_query = nanoseconds(start.elapsed()) / centres.len() as f64;
+
+            println!(
+                "  {:>5} nm: {:>6.1} ranges, {:>8.1} candidates, {:>8.1} found, {:>8.1} µs",
+                radius_nm,
+                ranges as f64 / centres.len() as f64,
+                candidates as f64 / centres.len() as f64,
+                found as f64 / centres.len() as f64,
+                per_query / 1e3
+            );
+        }
+    }
+}
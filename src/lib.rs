@@ -6,7 +6,6 @@
 extern crate nalgebra;
 
 extern crate chrono;
-extern crate linked_list;
 
 #[macro_use]
 extern crate combine;
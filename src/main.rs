@@ -9,7 +9,10 @@ fn main() {
     let resources_dir = exe_dir.clone().join("resources");
     let navdata_dir = resources_dir.join("navdata");
 
-    let database = Database::new(navdata_dir, resources_dir);
+    match Database::new(navdata_dir, resources_dir) {
+        Ok(database) => println!("Loaded {}", database),
+        Err(error) => println!("Unable to load navdata: {}", error),
+    }
 
     println!("This is a test")
 }
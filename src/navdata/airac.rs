@@ -0,0 +1,141 @@
+//! The AIRAC calendar.
+//!
+//! Aeronautical information is updated on a fixed schedule of 28 day
+//! cycles, each of which is identified by the last two digits of the year
+//! it becomes effective in, and its number within that year (`YYNN`). Most
+//! years have 13 cycles, some have 14.
+
+use chrono::{DateTime, UTC, TimeZone, Duration, Datelike};
+use std::fmt;
+
+/// Length of an AIRAC cycle (in days)
+pub const CYCLE_DAYS: i64 = 28;
+
+/// The date cycle 1501 became effective, which is used as a reference for
+/// all other cycles.
+fn epoch() -> DateTime<UTC> {
+    return UTC.ymd(2015, 1, 8).and_hms(0, 0, 0);
+}
+
+/// An AIRAC cycle.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate chrono;
+/// # extern crate oldnav_lib;
+/// # use chrono::{UTC, TimeZone};
+/// # use oldnav_lib::navdata::airac::AiracCycle;
+/// # fn main() {
+/// let cycle = AiracCycle::from_date(UTC.ymd(2017, 9, 1).and_hms(12, 0, 0));
+///
+/// assert_eq!(1709, cycle.ident());
+/// assert_eq!(UTC.ymd(2017, 8, 17).and_hms(0, 0, 0), cycle.effective());
+/// assert_eq!(UTC.ymd(2017, 9, 14).and_hms(0, 0, 0), cycle.expires());
+/// assert_eq!(1710, cycle.next().ident());
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AiracCycle {
+    /// Number of cycles since cycle 1501
+    index: i64,
+}
+
+impl AiracCycle {
+    /// The cycle which is effective at a given time.
+    pub fn from_date(date: DateTime<UTC>) -> AiracCycle {
+        let seconds = date.signed_duration_since(epoch()).num_seconds();
+        let cycle_seconds = CYCLE_DAYS * 24 * 60 * 60;
+
+        // round down for dates before the epoch as well as after it
+        let mut index = seconds / cycle_seconds;
+        if seconds % cycle_seconds < 0 {
+            index -= 1;
+        }
+
+        return AiracCycle { index: index };
+    }
+
+    /// The cycle with the given identifier (`YYNN`), e.g. `1709`. Years
+    /// are taken to be in the 21st century.
+    ///
+    /// Returns `None` if the year doesn't have that many cycles.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::airac::AiracCycle;
+    /// assert_eq!(1709, AiracCycle::from_ident(1709).unwrap().ident());
+    /// assert_eq!(2014, AiracCycle::from_ident(2014).unwrap().ident());
+    /// assert_eq!(None, AiracCycle::from_ident(1714));
+    /// assert_eq!(None, AiracCycle::from_ident(1700));
+    /// ```
+    pub fn from_ident(ident: i32) -> Option<AiracCycle> {
+        let year = 2000 + ident / 100;
+        let number = ident % 100;
+        if ident < 0 || number < 1 {
+            return None;
+        }
+
+        // the first cycle of the year is the one effective on the 1st of
+        // January, unless that started the previous year.
+        let mut first = AiracCycle::from_date(UTC.ymd(year, 1, 1).and_hms(0, 0, 0));
+        if first.year() < year {
+            first = first.next();
+        }
+
+        let cycle = AiracCycle { index: first.index + (number - 1) as i64 };
+        if cycle.year() != year {
+            return None;
+        }
+
+        return Some(cycle);
+    }
+
+    /// The year this cycle becomes effective in.
+    pub fn year(&self) -> i32 {
+        return self.effective().year();
+    }
+
+    /// The number of this cycle within its year, starting at 1.
+    pub fn number(&self) -> u32 {
+        return self.effective().ordinal0() / CYCLE_DAYS as u32 + 1;
+    }
+
+    /// The identifier of this cycle (`YYNN`), e.g. `1709`.
+    pub fn ident(&self) -> i32 {
+        return (self.year() % 100) * 100 + self.number() as i32;
+    }
+
+    /// The time this cycle becomes effective.
+    pub fn effective(&self) -> DateTime<UTC> {
+        return epoch() + Duration::days(self.index * CYCLE_DAYS);
+    }
+
+    /// The time this cycle expires, which is when the next cycle becomes
+    /// effective.
+    pub fn expires(&self) -> DateTime<UTC> {
+        return self.next().effective();
+    }
+
+    /// The cycle after this one.
+    pub fn next(&self) -> AiracCycle {
+        return AiracCycle { index: self.index + 1 };
+    }
+
+    /// The cycle before this one.
+    pub fn previous(&self) -> AiracCycle {
+        return AiracCycle { index: self.index - 1 };
+    }
+
+    /// Whether this cycle is effective at a given time.
+    pub fn is_current(&self, at: DateTime<UTC>) -> bool {
+        return self.effective() <= at && at < self.expires();
+    }
+}
+
+impl fmt::Display for AiracCycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{:04}", self.ident());
+    }
+}
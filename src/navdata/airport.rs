@@ -1,25 +1,601 @@
 //! A module with methods for `Airport` and other associated functions.
+//!
+//! Airports are read from x-plane's `apt.dat`, where each airport starts
+//! with a header row (`1` for a land airport, `16` for a seaplane base and
+//! `17` for a heliport) followed by rows describing its runways,
+//! frequencies and metadata.
 
+use nalgebra::core::Vector3;
+use navdata::country::Country;
+use navdata::kind::WaypointKind;
 use navdata::waypoint::Waypoint;
 use navdata::waypoint::WaypointInterface;
 use navdata::coord::SphericalCoordinate;
-use navdata::country::Country;
 use std::fmt;
-use std::rc::Rc;
+use std::sync::Arc;
+
+/// The type of an `Airport`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AirportType {
+    /// A land airport (row code `1`)
+    Land,
+
+    /// A seaplane base (row code `16`)
+    Seaplane,
+
+    /// A heliport (row code `17`)
+    Heliport,
+}
+
+impl AirportType {
+    /// Get the `AirportType` for the header row code used in `apt.dat`.
+    pub fn from_row_code(row_code: u32) -> Option<AirportType> {
+        match row_code {
+            1 => Some(AirportType::Land),
+            16 => Some(AirportType::Seaplane),
+            17 => Some(AirportType::Heliport),
+            _ => None,
+        }
+    }
+}
+
+/// Surface type of a `Runway`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Surface {
+    /// Asphalt
+    Asphalt,
+
+    /// Concrete
+    Concrete,
+
+    /// Turf or grass
+    Grass,
+
+    /// Dirt
+    Dirt,
+
+    /// Gravel
+    Gravel,
+
+    /// Dry lakebed
+    DryLakebed,
+
+    /// Water
+    Water,
+
+    /// Snow or ice
+    SnowOrIce,
+
+    /// Transparent (a hard surface which is drawn by custom scenery)
+    Transparent,
+
+    /// A surface code which isn't recognised
+    Unknown(u32),
+}
+
+impl Surface {
+    /// Get the `Surface` for a surface code used in `apt.dat`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::airport::Surface;
+    /// assert_eq!(Surface::Asphalt, Surface::from_code(1));
+    /// // x-plane 12 asphalt and concrete shades
+    /// assert_eq!(Surface::Asphalt, Surface::from_code(21));
+    /// assert_eq!(Surface::Concrete, Surface::from_code(52));
+    /// ```
+    pub fn from_code(code: u32) -> Surface {
+        match code {
+            1 => Surface::Asphalt,
+            2 => Surface::Concrete,
+            3 => Surface::Grass,
+            4 => Surface::Dirt,
+            5 => Surface::Gravel,
+            12 => Surface::DryLakebed,
+            13 => Surface::Water,
+            14 => Surface::SnowOrIce,
+            15 => Surface::Transparent,
+            code if code >= 20 && code <= 38 => Surface::Asphalt,
+            code if code >= 50 && code <= 57 => Surface::Concrete,
+            _ => Surface::Unknown(code),
+        }
+    }
+}
+
+/// One end of a `Runway`.
+#[derive(Debug, Clone)]
+pub struct RunwayEnd {
+    /// Runway number, e.g. `16L`
+    pub number: String,
+
+    /// Position of the threshold, at the runway centreline
+    pub threshold: SphericalCoordinate,
+
+    /// True heading of the runway when landing on this end (in degrees)
+    pub heading: f64,
+
+    /// Length of the displaced threshold (in metres)
+    pub displaced_threshold: f64,
+
+    /// Length of the overrun/blast pad (in metres)
+    pub overrun: f64,
+
+    /// Runway markings code
+    pub markings: u32,
+
+    /// Approach lighting code, `0` if there is no approach lighting
+    pub approach_lighting: u32,
+
+    /// Whether there are touchdown zone lights
+    pub touchdown_zone_lighting: bool,
+
+    /// Runway end identifier lights code, `0` if there are none
+    pub reil: u32,
+}
+
+impl RunwayEnd {
+    /// Constructor for `RunwayEnd`
+    pub fn new<S: Into<String>>(number: S, threshold: SphericalCoordinate) -> RunwayEnd {
+        RunwayEnd {
+            number: number.into(),
+            threshold: threshold,
+            heading: 0.0,
+            displaced_threshold: 0.0,
+            overrun: 0.0,
+            markings: 0,
+            approach_lighting: 0,
+            touchdown_zone_lighting: false,
+            reil: 0,
+        }
+    }
+}
+
+/// The type of a `Runway`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunwayType {
+    /// A land runway (row code `100`)
+    Land,
+
+    /// A water runway (row code `101`)
+    Water,
+
+    /// A helipad (row code `102`)
+    Helipad,
+}
+
+/// A runway, water runway or helipad at an `Airport`.
+#[derive(Debug, Clone)]
+pub struct Runway {
+    /// The type of runway
+    pub runway_type: RunwayType,
+
+    /// Width (in metres)
+    pub width: f64,
+
+    /// Length between the two thresholds (in metres)
+    pub length: f64,
+
+    /// Surface of the runway
+    pub surface: Surface,
+
+    /// Whether the runway has centreline lights
+    pub centreline_lighting: bool,
+
+    /// Runway edge lighting code, `0` if there is no edge lighting
+    pub edge_lighting: u32,
+
+    /// The ends of the runway, there is only one for a helipad.
+    pub ends: Vec<RunwayEnd>,
+}
+
+impl Runway {
+    /// Read a land runway from the columns (after the row code) of a `100`
+    /// row in `apt.dat`.
+    ///
+    /// Returns `None` if the row is invalid.
+    pub fn from_land_row(columns: &[&str]) -> Option<Runway> {
+        if columns.len() < 25 {
+            return None;
+        }
+
+        let mut ends = Vec::new();
+        for end_columns in columns[7..25].chunks(9) {
+            let mut end = RunwayEnd::new(
+                end_columns[0],
+                SphericalCoordinate::from_geographic(
+                    0.0,
+                    parse_column(end_columns[1])?,
+                    parse_column(end_columns[2])?,
+                ),
+            );
+            end.displaced_threshold = parse_column(end_columns[3])?;
+            end.overrun = parse_column(end_columns[4])?;
+            end.markings = parse_column(end_columns[5])?;
+            end.approach_lighting = parse_column(end_columns[6])?;
+            end.touchdown_zone_lighting = parse_column::<u32>(end_columns[7])? == 1;
+            end.reil = parse_column(end_columns[8])?;
+            ends.push(end);
+        }
+
+        let mut runway = Runway {
+            runway_type: RunwayType::Land,
+            width: parse_column(columns[0])?,
+            length: 0.0,
+            surface: Surface::from_code(parse_column(columns[1])?),
+            centreline_lighting: parse_column::<u32>(columns[4])? == 1,
+            edge_lighting: parse_column(columns[5])?,
+            ends: ends,
+        };
+        runway.calculate_length_and_headings();
+
+        return Some(runway);
+    }
+
+    /// Read a water runway from the columns (after the row code) of a `101`
+    /// row in `apt.dat`.
+    ///
+    /// Returns `None` if the row is invalid.
+    pub fn from_water_row(columns: &[&str]) -> Option<Runway> {
+        if columns.len() < 8 {
+            return None;
+        }
+
+        let mut ends = Vec::new();
+        for end_columns in columns[2..8].chunks(3) {
+            ends.push(RunwayEnd::new(
+                end_columns[0],
+                SphericalCoordinate::from_geographic(
+                    0.0,
+                    parse_column(end_columns[1])?,
+                    parse_column(end_columns[2])?,
+                ),
+            ));
+        }
+
+        let mut runway = Runway {
+            runway_type: RunwayType::Water,
+            width: parse_column(columns[0])?,
+            length: 0.0,
+            surface: Surface::Water,
+            centreline_lighting: false,
+            edge_lighting: 0,
+            ends: ends,
+        };
+        runway.calculate_length_and_headings();
+
+        return Some(runway);
+    }
+
+    /// Read a helipad from the columns (after the row code) of a `102`
+    /// row in `apt.dat`.
+    ///
+    /// Returns `None` if the row is invalid.
+    pub fn from_helipad_row(columns: &[&str]) -> Option<Runway> {
+        if columns.len() < 11 {
+            return None;
+        }
+
+        let mut end = RunwayEnd::new(
+            columns[0],
+            SphericalCoordinate::from_geographic(
+                0.0,
+                parse_column(columns[1])?,
+                parse_column(columns[2])?,
+            ),
+        );
+        end.heading = parse_column(columns[3])?;
+        end.markings = parse_column(columns[7])?;
+
+        return Some(Runway {
+            runway_type: RunwayType::Helipad,
+            width: parse_column(columns[5])?,
+            length: parse_column(columns[4])?,
+            surface: Surface::from_code(parse_column(columns[6])?),
+            centreline_lighting: false,
+            edge_lighting: parse_column(columns[10])?,
+            ends: vec![end],
+        });
+    }
+
+    /// Calculate the length of the runway and the headings of its ends
+    /// from the positions of the thresholds.
+    fn calculate_length_and_headings(&mut self) {
+        if self.ends.len() != 2 {
+            return;
+        }
+
+        let threshold_0 = self.ends[0].threshold;
+        let threshold_1 = self.ends[1].threshold;
+
+        self.length = threshold_0.arc_distance(&threshold_1);
+        self.ends[0].heading = threshold_0.bearing_to(&threshold_1);
+        self.ends[1].heading = threshold_1.bearing_to(&threshold_0);
+    }
+}
+
+/// The service provided on an `AirportFrequency`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrequencyType {
+    /// Recorded weather and information (ATIS, AWOS or ASOS)
+    Recorded,
+
+    /// UNICOM or CTAF
+    Unicom,
+
+    /// Clearance delivery
+    Clearance,
+
+    /// Ground
+    Ground,
+
+    /// Tower
+    Tower,
+
+    /// Approach
+    Approach,
+
+    /// Departure
+    Departure,
+}
+
+impl FrequencyType {
+    /// Get the `FrequencyType` for a row code used in `apt.dat`, both the
+    /// 25 kHz (`50` to `56`) and 8.33 kHz (`1050` to `1056`) variants.
+    pub fn from_row_code(row_code: u32) -> Option<FrequencyType> {
+        match row_code {
+            50 | 1050 => Some(FrequencyType::Recorded),
+            51 | 1051 => Some(FrequencyType::Unicom),
+            52 | 1052 => Some(FrequencyType::Clearance),
+            53 | 1053 => Some(FrequencyType::Ground),
+            54 | 1054 => Some(FrequencyType::Tower),
+            55 | 1055 => Some(FrequencyType::Approach),
+            56 | 1056 => Some(FrequencyType::Departure),
+            _ => None,
+        }
+    }
+}
+
+/// A radio frequency used at an `Airport`.
+#[derive(Debug, Clone)]
+pub struct AirportFrequency {
+    /// The service provided on this frequency
+    pub frequency_type: FrequencyType,
+
+    /// Frequency (in kHz)
+    pub frequency: f64,
+
+    /// Name of the service, e.g. `SEATTLE TWR`
+    pub name: String,
+}
+
+impl AirportFrequency {
+    /// Read a frequency from a `50` to `56` or `1050` to `1056` row in
+    /// `apt.dat`.
+    ///
+    /// Returns `None` if the row is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::airport::{AirportFrequency, FrequencyType};
+    /// let frequency = AirportFrequency::from_row(54, &["11890", "SEATTLE", "TWR"]).unwrap();
+    /// assert_eq!(FrequencyType::Tower, frequency.frequency_type);
+    /// assert_eq!(118900.0, frequency.frequency);
+    /// assert_eq!("SEATTLE TWR", frequency.name);
+    ///
+    /// let frequency = AirportFrequency::from_row(1054, &["118905", "TWR"]).unwrap();
+    /// assert_eq!(118905.0, frequency.frequency);
+    /// ```
+    pub fn from_row(row_code: u32, columns: &[&str]) -> Option<AirportFrequency> {
+        if columns.len() < 1 {
+            return None;
+        }
+
+        let frequency_type = FrequencyType::from_row_code(row_code)?;
+        let frequency: f64 = parse_column(columns[0])?;
+
+        return Some(AirportFrequency {
+            frequency_type: frequency_type,
+            // the older rows are in units of 10 kHz
+            frequency: if row_code < 1000 {
+                frequency * 10.0
+            } else {
+                frequency
+            },
+            name: columns[1..].join(" "),
+        });
+    }
+}
 
 /// An airport on earth.
 pub struct Airport {
     /// This airport's waypoint.
     pub waypoint: Waypoint,
+
+    /// The type of airport
+    pub airport_type: AirportType,
+
+    /// Elevation above mean sea level (in feet)
+    pub elevation: f64,
+
+    /// Runways, water runways and helipads at this airport
+    pub runways: Vec<Runway>,
+
+    /// Radio frequencies used at this airport
+    pub frequencies: Vec<AirportFrequency>,
+
+    /// ICAO code, if the airport has one (the `code` of the waypoint
+    /// may be a local identifier).
+    pub icao_code: Option<String>,
+
+    /// IATA code
+    pub iata_code: Option<String>,
+
+    /// FAA code
+    pub faa_code: Option<String>,
+
+    /// Name of the city the airport serves
+    pub city: Option<String>,
+
+    /// Name of the country the airport is in
+    pub country_name: Option<String>,
+
+    /// Transition altitude (in feet)
+    pub transition_altitude: Option<u32>,
+
+    /// Transition level (in feet)
+    pub transition_level: Option<u32>,
 }
 
 impl Airport {
     /// Constructor for `Airport`.
     pub fn new<S: Into<String>>(code: S, name: S, pos: SphericalCoordinate) -> Airport {
-        return Airport { waypoint: Waypoint::new(code, name, pos, None) };
+        return Airport {
+            waypoint: Waypoint::new(code, name, pos, None),
+            airport_type: AirportType::Land,
+            elevation: 0.0,
+            runways: Vec::new(),
+            frequencies: Vec::new(),
+            icao_code: None,
+            iata_code: None,
+            faa_code: None,
+            city: None,
+            country_name: None,
+            transition_altitude: None,
+            transition_level: None,
+        };
+    }
+
+    /// Read an airport from the columns (after the row code) of a `1`, `16`
+    /// or `17` header row in `apt.dat`.
+    ///
+    /// The position of the airport is not part of the header, it is set from
+    /// the `datum_lat`/`datum_lon` metadata, or from the runways if there is
+    /// no datum (see `set_position_from_runways()`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::airport::{Airport, AirportType};
+    /// # use oldnav_lib::navdata::waypoint::WaypointInterface;
+    /// let row = "433 0 0 KSEA Seattle-Tacoma Intl";
+    /// let columns: Vec<&str> = row.split_whitespace().collect();
+    /// let airport = Airport::from_header_row(1, &columns).unwrap();
+    ///
+    /// assert_eq!("KSEA", airport.code());
+    /// assert_eq!("Seattle-Tacoma Intl", airport.name());
+    /// assert_eq!(AirportType::Land, airport.airport_type);
+    /// assert_eq!(433.0, airport.elevation);
+    /// ```
+    pub fn from_header_row(row_code: u32, columns: &[&str]) -> Option<Airport> {
+        if columns.len() < 4 {
+            return None;
+        }
+
+        let mut airport = Airport::new(
+            String::from(columns[3]),
+            columns[4..].join(" "),
+            SphericalCoordinate::from_geographic(0.0, 0.0, 0.0),
+        );
+        airport.airport_type = AirportType::from_row_code(row_code)?;
+        airport.elevation = parse_column(columns[0])?;
+
+        return Some(airport);
+    }
+
+    /// Apply a `1302` metadata row from `apt.dat` to this airport.
+    ///
+    /// Returns `None` if the row is invalid, unknown keys are ignored.
+    pub fn apply_metadata_row(&mut self, columns: &[&str]) -> Option<()> {
+        if columns.len() < 1 {
+            return None;
+        }
+
+        let key = columns[0];
+        let value = columns[1..].join(" ");
+
+        // metadata keys which x-plane leaves in the file without a value
+        if value.is_empty() {
+            return Some(());
+        }
+
+        match key {
+            "icao_code" => self.icao_code = Some(value),
+            "iata_code" => self.iata_code = Some(value),
+            "faa_code" => self.faa_code = Some(value),
+            "city" => self.city = Some(value),
+            "country" => self.country_name = Some(value),
+            "region_code" => self.waypoint.region = value,
+            "transition_alt" => self.transition_altitude = Some(parse_column(&value)?),
+            "transition_level" => self.transition_level = Some(parse_column(&value)?),
+            "datum_lat" => self.waypoint.pos.set_lat(parse_column(&value)?),
+            "datum_lon" => self.waypoint.pos.set_lon(parse_column(&value)?),
+            _ => {}
+        }
+
+        return Some(());
+    }
+
+    /// Set the position of the airport to the average of the positions of
+    /// its runway ends. Used for airports which have no datum in their
+    /// metadata.
+    ///
+    /// The positions are averaged as unit vectors, so that an airport whose
+    /// runways cross the antimeridian stays next to them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::airport::{Airport, Runway};
+    /// # use oldnav_lib::navdata::waypoint::WaypointInterface;
+    /// let columns: Vec<&str> = "0 0 0 NZZZ Dateline".split_whitespace().collect();
+    /// let mut airport = Airport::from_header_row(1, &columns).unwrap();
+    ///
+    /// let row = "45.00 1 0 0.25 0 2 1 09 -17.0 179.99 0.00 0.00 3 0 0 0 \
+    ///            27 -17.0 -179.99 0.00 0.00 3 0 0 0";
+    /// let columns: Vec<&str> = row.split_whitespace().collect();
+    /// airport.runways.push(Runway::from_land_row(&columns).unwrap());
+    ///
+    /// airport.set_position_from_runways();
+    /// assert!((airport.pos().lat() - -17.0).abs() < 0.0001);
+    /// assert!(airport.pos().lon().abs() > 179.99);
+    /// ```
+    pub fn set_position_from_runways(&mut self) {
+        let sum = self.runways
+            .iter()
+            .flat_map(|runway| runway.ends.iter())
+            .fold(Vector3::new(0.0, 0.0, 0.0), |sum, end| {
+                sum + end.threshold.r_cart_uv()
+            });
+
+        let length = (sum.x * sum.x + sum.y * sum.y + sum.z * sum.z).sqrt();
+        if length == 0.0 {
+            return;
+        }
+
+        // invert `SphericalCoordinate::r_cart_uv()`, where theta is the
+        // longitude + 180 degrees and phi is the latitude + 90 degrees
+        let lat = (-sum.z / length).asin().to_degrees();
+        let lon = (-sum.y).atan2(-sum.x).to_degrees();
+
+        self.waypoint.pos = SphericalCoordinate::from_geographic(0.0, lat, lon);
+    }
+
+    /// Find the runway end with the given number, e.g. `16L`.
+    pub fn runway_end(&self, number: &str) -> Option<&RunwayEnd> {
+        return self.runways
+            .iter()
+            .flat_map(|runway| runway.ends.iter())
+            .find(|end| end.number == number);
     }
 }
 
+/// Parse a column from a row in `apt.dat`.
+fn parse_column<T: ::std::str::FromStr>(column: &str) -> Option<T> {
+    return column.parse::<T>().ok();
+}
+
 
 impl WaypointInterface for Airport {
     fn code(&self) -> &str {
@@ -33,17 +609,30 @@ impl WaypointInterface for Airport {
     fn pos(&self) -> &SphericalCoordinate {
         return &self.waypoint.pos;
     }
+
+    fn region(&self) -> &str {
+        return &self.waypoint.region;
+    }
+
+    fn country(&self) -> Option<&Arc<Country>> {
+        return self.waypoint.country.as_ref();
+    }
+
+    fn kind(&self) -> WaypointKind {
+        return WaypointKind::Airport;
+    }
 }
 
 impl fmt::Debug for Airport {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         return write!(
             f,
-            "Airport: {{code: {}, name: {}, pos: [{}, {}]}}",
+            "Airport: {{code: {}, name: {}, pos: [{}, {}], n_runways: {}}}",
             self.waypoint.code,
             self.waypoint.name,
             self.pos().lat(),
-            self.pos().lon()
+            self.pos().lon(),
+            self.runways.len()
         );
 
     }
@@ -0,0 +1,578 @@
+//! Airways, and the graph of airway segments which connects the waypoints
+//! in the `Database`.
+
+use navdata::waypoint::WaypointInterface;
+use navdata::route::Route;
+use navdata::multihash::MultiHash;
+use std::collections::{HashMap, BinaryHeap, VecDeque};
+use std::cmp::Ordering;
+use std::sync::Arc;
+use std::fmt;
+
+/// The type of waypoint at the end of an airway segment, as listed in
+/// x-plane's `earth_awy.dat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AirwayNodeType {
+    /// A fix (row type 11)
+    Fix,
+
+    /// An NDB (row type 2)
+    Ndb,
+
+    /// A VHF navaid, such as a VOR (row type 3)
+    Vhf,
+}
+
+impl AirwayNodeType {
+    /// Get the `AirwayNodeType` for the type code used in `earth_awy.dat`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::airway::AirwayNodeType;
+    /// assert_eq!(Some(AirwayNodeType::Fix), AirwayNodeType::from_type_code(11));
+    /// assert_eq!(None, AirwayNodeType::from_type_code(1));
+    /// ```
+    pub fn from_type_code(type_code: u32) -> Option<AirwayNodeType> {
+        match type_code {
+            11 => Some(AirwayNodeType::Fix),
+            2 => Some(AirwayNodeType::Ndb),
+            3 => Some(AirwayNodeType::Vhf),
+            _ => None,
+        }
+    }
+}
+
+/// Uniquely identifies a waypoint at the end of an airway segment.
+///
+/// Waypoint codes are not unique, so they need to be qualified by their
+/// ICAO region and type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AirwayNodeKey {
+    /// Code of the waypoint
+    pub code: String,
+
+    /// ICAO region of the waypoint
+    pub region: String,
+
+    /// Type of the waypoint
+    pub node_type: AirwayNodeType,
+}
+
+impl AirwayNodeKey {
+    /// Constructor for `AirwayNodeKey`.
+    pub fn new<S: Into<String>>(code: S, region: S, node_type: AirwayNodeType) -> AirwayNodeKey {
+        AirwayNodeKey {
+            code: code.into(),
+            region: region.into(),
+            node_type: node_type,
+        }
+    }
+}
+
+/// The directions in which an airway segment may be flown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AirwayDirection {
+    /// The segment may be flown in both directions (`N`)
+    Both,
+
+    /// The segment may only be flown from its first to its second
+    /// waypoint (`F`)
+    Forward,
+
+    /// The segment may only be flown from its second to its first
+    /// waypoint (`B`)
+    Backward,
+}
+
+impl AirwayDirection {
+    /// Get the `AirwayDirection` for the direction restriction code used in
+    /// `earth_awy.dat`.
+    pub fn from_code(code: &str) -> Option<AirwayDirection> {
+        match code {
+            "N" => Some(AirwayDirection::Both),
+            "F" => Some(AirwayDirection::Forward),
+            "B" => Some(AirwayDirection::Backward),
+            _ => None,
+        }
+    }
+}
+
+/// Whether an airway is a high or low altitude airway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AirwayClass {
+    /// Low altitude airway (`1`), e.g. a victor airway
+    Low,
+
+    /// High altitude airway (`2`), e.g. a jet route
+    High,
+}
+
+impl AirwayClass {
+    /// Get the `AirwayClass` for the class code used in `earth_awy.dat`.
+    pub fn from_code(code: u32) -> Option<AirwayClass> {
+        match code {
+            1 => Some(AirwayClass::Low),
+            2 => Some(AirwayClass::High),
+            _ => None,
+        }
+    }
+}
+
+/// A segment of an airway, as it is read from the navdata files before the
+/// waypoints at either end have been found in the `Database`.
+#[derive(Debug, Clone)]
+pub struct UnlinkedAirwaySegment {
+    /// The waypoint at the start of the segment
+    pub from: AirwayNodeKey,
+
+    /// The waypoint at the end of the segment
+    pub to: AirwayNodeKey,
+
+    /// Directions in which the segment may be flown
+    pub direction: AirwayDirection,
+
+    /// Whether the segment is part of a high or low altitude airway
+    pub class: AirwayClass,
+
+    /// The base of the segment (flight level)
+    pub base: u32,
+
+    /// The top of the segment (flight level)
+    pub top: u32,
+
+    /// Names of the airways which this segment is a part of
+    pub names: Vec<String>,
+}
+
+/// A segment of an airway connecting two nodes in the `AirwayGraph`.
+pub struct AirwaySegment {
+    /// Name of the airway this segment is a part of
+    pub name: String,
+
+    /// Index of the node at the start of the segment
+    pub from: usize,
+
+    /// Index of the node at the end of the segment
+    pub to: usize,
+
+    /// Directions in which the segment may be flown
+    pub direction: AirwayDirection,
+
+    /// Whether the segment is part of a high or low altitude airway
+    pub class: AirwayClass,
+
+    /// The base of the segment (flight level)
+    pub base: u32,
+
+    /// The top of the segment (flight level)
+    pub top: u32,
+}
+
+impl AirwaySegment {
+    /// Whether this segment can be flown at the given flight level.
+    pub fn contains_level(&self, flight_level: u32) -> bool {
+        return flight_level >= self.base && flight_level <= self.top;
+    }
+}
+
+impl fmt::Debug for AirwaySegment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "AirwaySegment: {{name: {}, from: {}, to: {}, direction: {:?}, class: {:?}, \
+             base: {}, top: {}}}",
+            self.name,
+            self.from,
+            self.to,
+            self.direction,
+            self.class,
+            self.base,
+            self.top
+        );
+    }
+}
+
+/// A connection which can be flown out of a node in the `AirwayGraph`.
+#[derive(Debug, Clone)]
+pub struct AirwayEdge {
+    /// The segment being flown
+    pub segment: Arc<AirwaySegment>,
+
+    /// Index of the node at the other end of the segment
+    pub to: usize,
+}
+
+/// A named airway, made up of a connected sequence of segments.
+///
+/// Several airways in different parts of the world may share the same
+/// name, each of them is represented by its own `Airway`. An airway which
+/// branches is split into a separate `Airway` for each branch, between the
+/// nodes where it branches.
+pub struct Airway {
+    /// Name of the airway
+    pub name: String,
+
+    /// The segments of this airway, ordered from one end of the airway
+    /// to the other.
+    pub segments: Vec<Arc<AirwaySegment>>,
+
+    /// Indices of the nodes along this airway, in order, so that the
+    /// segment at each index joins the nodes at that index and the next.
+    pub nodes: Vec<usize>,
+}
+
+impl Airway {
+    /// Create a `Route` along the full length of this airway.
+    pub fn route(&self, graph: &AirwayGraph) -> Route {
+        let mut route = Route::new(Some(self.name.clone()));
+        for node in &self.nodes {
+            route.append_waypoint(graph.node(*node).clone());
+        }
+        return route;
+    }
+}
+
+impl fmt::Debug for Airway {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "Airway: {{name: {}, n_segments: {}}}",
+            self.name,
+            self.segments.len()
+        );
+    }
+}
+
+/// The graph formed by the airway segments connecting waypoints.
+///
+/// Each node is a waypoint which lies on an airway, and the edges out of
+/// each node are the segments which can be flown from it, taking into
+/// account the direction restrictions of the segments.
+pub struct AirwayGraph {
+    nodes: Vec<Arc<WaypointInterface>>,
+    node_keys: HashMap<AirwayNodeKey, usize>,
+    node_codes: MultiHash<String, usize>,
+    segments: Vec<Arc<AirwaySegment>>,
+    edges: Vec<Vec<AirwayEdge>>,
+}
+
+impl AirwayGraph {
+    /// Constructor for `AirwayGraph`
+    pub fn new() -> AirwayGraph {
+        AirwayGraph {
+            nodes: Vec::new(),
+            node_keys: HashMap::new(),
+            node_codes: MultiHash::new(),
+            segments: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Add a waypoint to the graph, if it is not already present, and
+    /// return the index of its node.
+    pub fn add_node(&mut self, key: AirwayNodeKey, waypoint: Arc<WaypointInterface>) -> usize {
+        if let Some(index) = self.node_keys.get(&key) {
+            return *index;
+        }
+
+        let index = self.nodes.len();
+        self.nodes.push(waypoint);
+        self.edges.push(Vec::new());
+        self.node_codes.insert(key.code.clone(), index);
+        self.node_keys.insert(key, index);
+        return index;
+    }
+
+    /// Add a segment between two nodes to the graph.
+    pub fn add_segment(&mut self, segment: AirwaySegment) -> Arc<AirwaySegment> {
+        let segment = Arc::new(segment);
+
+        if segment.direction != AirwayDirection::Backward {
+            self.edges[segment.from].push(AirwayEdge {
+                segment: segment.clone(),
+                to: segment.to,
+            });
+        }
+
+        if segment.direction != AirwayDirection::Forward {
+            self.edges[segment.to].push(AirwayEdge {
+                segment: segment.clone(),
+                to: segment.from,
+            });
+        }
+
+        self.segments.push(segment.clone());
+        return segment;
+    }
+
+    /// Get the waypoint for a node.
+    pub fn node(&self, index: usize) -> &Arc<WaypointInterface> {
+        return &self.nodes[index];
+    }
+
+    /// Get the index of the node with the given key.
+    pub fn node_index(&self, key: &AirwayNodeKey) -> Option<usize> {
+        return self.node_keys.get(key).cloned();
+    }
+
+    /// The keys of all the nodes in the graph, with the indices of their
+    /// nodes.
+    pub fn node_keys(&self) -> &HashMap<AirwayNodeKey, usize> {
+        return &self.node_keys;
+    }
+
+    /// Get the indices of all the nodes with the given waypoint code.
+    pub fn find_nodes(&self, code: &str) -> Vec<usize> {
+        return match self.node_codes.get(&String::from(code)) {
+            Some(indices) => indices.clone(),
+            None => Vec::new(),
+        };
+    }
+
+    /// The number of nodes in the graph.
+    pub fn n_nodes(&self) -> usize {
+        return self.nodes.len();
+    }
+
+    /// All the segments in the graph.
+    pub fn segments(&self) -> &[Arc<AirwaySegment>] {
+        return &self.segments;
+    }
+
+    /// The edges which can be flown out of a node.
+    pub fn edges(&self, node: usize) -> &[AirwayEdge] {
+        return &self.edges[node];
+    }
+
+    /// Group the segments into `Airway`s.
+    ///
+    /// Segments with the same name are joined together when they share a
+    /// node, regardless of the order they were added in. Each connected
+    /// group of segments is split into linear chains at the nodes where it
+    /// ends or branches, and each chain becomes its own `Airway`, so that
+    /// airways which share a name but are in different parts of the world
+    /// are kept apart, and every `Airway` can be flown from one end to the
+    /// other.
+    pub fn build_airways(&self) -> Vec<Airway> {
+        let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, segment) in self.segments.iter().enumerate() {
+            by_name
+                .entry(&segment.name)
+                .or_insert_with(Vec::new)
+                .push(index);
+        }
+
+        // sorted, so that the airways are always built in the same order
+        let mut names: Vec<&str> = by_name.keys().cloned().collect();
+        names.sort();
+
+        let mut airways: Vec<Airway> = Vec::new();
+        let mut visited: Vec<bool> = vec![false; self.segments.len()];
+
+        for name in names {
+            // undirected adjacency between the nodes of this airway
+            let mut adjacency: HashMap<usize, Vec<usize>> = HashMap::new();
+            for &index in &by_name[name] {
+                let segment = &self.segments[index];
+                adjacency.entry(segment.from).or_insert_with(Vec::new).push(index);
+                adjacency.entry(segment.to).or_insert_with(Vec::new).push(index);
+            }
+
+            // chains start at the ends and branches of the airway, nodes in
+            // the middle of a chain are only used to start closed loops
+            let mut starts: Vec<usize> = adjacency.keys().cloned().collect();
+            starts.sort_by_key(|node| (adjacency[node].len() == 2, *node));
+
+            for start in starts {
+                for &first in &adjacency[&start] {
+                    if visited[first] {
+                        continue;
+                    }
+
+                    let mut airway = Airway {
+                        name: String::from(name),
+                        segments: Vec::new(),
+                        nodes: vec![start],
+                    };
+
+                    let mut node = start;
+                    let mut index = first;
+
+                    loop {
+                        visited[index] = true;
+                        let segment = &self.segments[index];
+
+                        node = if segment.from == node {
+                            segment.to
+                        } else {
+                            segment.from
+                        };
+
+                        airway.segments.push(segment.clone());
+                        airway.nodes.push(node);
+
+                        if node == start || adjacency[&node].len() != 2 {
+                            break;
+                        }
+
+                        match adjacency[&node].iter().find(|&&next| !visited[next]) {
+                            Some(&next) => index = next,
+                            None => break,
+                        }
+                    }
+
+                    airways.push(airway);
+                }
+            }
+        }
+
+        return airways;
+    }
+
+    /// Create a `Route` along the airway with the given name, from the
+    /// waypoint with code `entry` to the waypoint with code `exit`.
+    ///
+    /// The direction restrictions of the airway's segments are respected.
+    /// Returns `None` if there is no way to fly from `entry` to `exit`
+    /// along the airway.
+    pub fn airway_route(&self, name: &str, entry: &str, exit: &str) -> Option<Route> {
+        for start in self.find_nodes(entry) {
+            let mut previous: HashMap<usize, usize> = HashMap::new();
+            let mut queue: VecDeque<usize> = VecDeque::new();
+            queue.push_back(start);
+            previous.insert(start, start);
+
+            while let Some(node) = queue.pop_front() {
+                if self.nodes[node].code() == exit && node != start {
+                    let mut route = Route::new(Some(String::from(name)));
+                    for node in self.trace_path(&previous, start, node) {
+                        route.append_waypoint(self.nodes[node].clone());
+                    }
+                    return Some(route);
+                }
+
+                for edge in &self.edges[node] {
+                    if edge.segment.name == name && !previous.contains_key(&edge.to) {
+                        previous.insert(edge.to, node);
+                        queue.push_back(edge.to);
+                    }
+                }
+            }
+        }
+
+        return None;
+    }
+
+    /// Find the shortest `Route` along the airways between two nodes.
+    ///
+    /// Only segments which can be flown at `flight_level` are used, if it
+    /// is specified. The direction restrictions of the segments are
+    /// respected. Returns `None` if the nodes are not connected, or either
+    /// is not a node of this graph.
+    pub fn find_route(&self, from: usize, to: usize, flight_level: Option<u32>) -> Option<Route> {
+        if from >= self.nodes.len() || to >= self.nodes.len() {
+            return None;
+        }
+
+        let mut distances: Vec<f64> = vec![::std::f64::INFINITY; self.nodes.len()];
+        let mut previous: HashMap<usize, usize> = HashMap::new();
+        let mut heap = BinaryHeap::new();
+
+        distances[from] = 0.0;
+        previous.insert(from, from);
+        heap.push(SearchState {
+            distance: 0.0,
+            node: from,
+        });
+
+        while let Some(SearchState { distance, node }) = heap.pop() {
+            if node == to {
+                let mut route = Route::new(None);
+                for node in self.trace_path(&previous, from, to) {
+                    route.append_waypoint(self.nodes[node].clone());
+                }
+                return Some(route);
+            }
+
+            if distance > distances[node] {
+                continue;
+            }
+
+            for edge in &self.edges[node] {
+                if let Some(flight_level) = flight_level {
+                    if !edge.segment.contains_level(flight_level) {
+                        continue;
+                    }
+                }
+
+                let next_distance = distance +
+                    self.nodes[node].pos().arc_distance(
+                        self.nodes[edge.to].pos(),
+                    );
+
+                if next_distance < distances[edge.to] {
+                    distances[edge.to] = next_distance;
+                    previous.insert(edge.to, node);
+                    heap.push(SearchState {
+                        distance: next_distance,
+                        node: edge.to,
+                    });
+                }
+            }
+        }
+
+        return None;
+    }
+
+    /// Follow the chain of previous nodes back from `end` to `start`,
+    /// returning the nodes in order from `start` to `end`.
+    fn trace_path(&self, previous: &HashMap<usize, usize>, start: usize, end: usize) -> Vec<usize> {
+        let mut path = vec![end];
+        let mut node = end;
+        while node != start {
+            node = previous[&node];
+            path.push(node);
+        }
+        path.reverse();
+        return path;
+    }
+}
+
+impl fmt::Debug for AirwayGraph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "AirwayGraph: {{n_nodes: {}, n_segments: {}}}",
+            self.nodes.len(),
+            self.segments.len()
+        );
+    }
+}
+
+/// A node waiting to be visited during the search in
+/// `AirwayGraph::find_route()`, ordered so that the closest node is popped
+/// first from a `BinaryHeap`.
+#[derive(PartialEq)]
+struct SearchState {
+    distance: f64,
+    node: usize,
+}
+
+impl Eq for SearchState {}
+
+impl Ord for SearchState {
+    fn cmp(&self, other: &SearchState) -> Ordering {
+        return other
+            .distance
+            .partial_cmp(&self.distance)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| self.node.cmp(&other.node));
+    }
+}
+
+impl PartialOrd for SearchState {
+    fn partial_cmp(&self, other: &SearchState) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
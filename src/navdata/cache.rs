@@ -0,0 +1,977 @@
+//! A binary cache of a parsed `Database`.
+//!
+//! Parsing the text navdata is slow, so after it has been loaded the whole
+//! `Database` is written to `oldnav.cache` in the navdata directory. The
+//! cache starts with a `CacheKey`, made up of the AIRAC cycle, the format
+//! and load mode, and a hash of each of the source files. The next time the
+//! same navdata is loaded the cache is used instead, as long as its key
+//! matches.
+//!
+//! Only the fixes, navaids, airports, countries, airway graph and load
+//! report are stored. The lookup tables (`waypoint_hash`, `airport_hash`
+//! and `airways`) are rebuilt from them when the cache is read.
+//!
+//! All numbers are stored little endian, strings as their length followed
+//! by their UTF-8 bytes, and references between objects (e.g. from a fix
+//! to its airport, or from an airway node to its waypoint) as indices.
+
+use navdata::address;
+use navdata::database::{Database, NavdataFormat, LoadMode};
+use navdata::waypoint::{Waypoint, WaypointInterface};
+use navdata::navaid::{Navaid, NavaidType};
+use navdata::airport::{Airport, AirportType, AirportFrequency, FrequencyType, Runway, RunwayEnd,
+                       RunwayType, Surface};
+use navdata::airway::{AirwaySegment, AirwayNodeKey, AirwayNodeType, AirwayDirection,
+                      AirwayClass};
+use navdata::country::Country;
+use navdata::coord::SphericalCoordinate;
+use navdata::report::{FileReport, SkippedLine, UnresolvedReference};
+use std::collections::HashMap;
+use std::io::{self, Read, Write, BufReader, BufWriter};
+use std::fs::{self, File, OpenOptions};
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Name of the cache file, in the navdata directory.
+pub const CACHE_FILE_NAME: &'static str = "oldnav.cache";
+
+/// Identifies the start of a cache file.
+const MAGIC: &'static [u8; 8] = b"OLDNAVDB";
+
+/// Version of the cache format, which needs to be incremented whenever the
+/// layout of the cache, or of the types stored in it, changes.
+const VERSION: u32 = 1;
+
+/// Hash the contents of a file with 64 bit FNV-1a.
+///
+/// This isn't a cryptographic hash, it is only used to notice that a file
+/// has changed since the cache was written.
+pub fn hash_file(path: &Path) -> io::Result<u64> {
+    let mut file = try!(File::open(path));
+    let mut buffer = [0u8; 64 * 1024];
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    loop {
+        let n = try!(file.read(&mut buffer));
+        if n == 0 {
+            break;
+        }
+
+        for byte in &buffer[..n] {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    return Ok(hash);
+}
+
+/// Everything the contents of a `Database` depend on. The cache is only
+/// used if its key is equal to the key of the navdata being loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheKey {
+    /// The AIRAC cycle number from `cycle_info.txt`
+    pub airac_cycle: i32,
+
+    /// The format of the navdata
+    pub format: NavdataFormat,
+
+    /// How invalid lines were handled while loading
+    pub load_mode: LoadMode,
+
+    /// The name and hash of each source file
+    pub files: Vec<(String, u64)>,
+}
+
+impl CacheKey {
+    /// Create the key for navdata made up of the given source files.
+    ///
+    /// Returns an error if one of the files can't be read.
+    pub fn new(
+        airac_cycle: i32,
+        format: NavdataFormat,
+        load_mode: LoadMode,
+        files: &[&Path],
+    ) -> io::Result<CacheKey> {
+        let mut hashes = Vec::new();
+        for path in files {
+            let name = path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            hashes.push((name, try!(hash_file(path))));
+        }
+
+        return Ok(CacheKey {
+            airac_cycle: airac_cycle,
+            format: format,
+            load_mode: load_mode,
+            files: hashes,
+        });
+    }
+}
+
+/// Number of cache files written by this process, to give each writer its
+/// own temporary file.
+static CACHE_WRITES: AtomicUsize = AtomicUsize::new(0);
+
+/// Write `db` to the cache file at `path`.
+///
+/// The cache is written to a temporary file of its own, which is then
+/// renamed, so a reader never sees a partially written cache, even while
+/// other threads or processes are writing the same cache.
+pub fn write_cache(path: &Path, key: &CacheKey, db: &Database) -> io::Result<()> {
+    let writer = CACHE_WRITES.fetch_add(1, Ordering::SeqCst);
+    let temp_path = path.with_extension(format!("tmp{}-{}", process::id(), writer));
+
+    // never open a file which another writer is still writing
+    let file = try!(OpenOptions::new().write(true).create_new(true).open(&temp_path));
+
+    let mut encoder = Encoder { writer: BufWriter::new(file) };
+    let result = encoder
+        .write_key(key)
+        .and_then(|_| encoder.write_database(db))
+        .and_then(|_| encoder.writer.flush());
+
+    if let Err(error) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(error);
+    }
+
+    return fs::rename(&temp_path, path);
+}
+
+/// Read the cache file at `path` into `db`, which should be empty.
+///
+/// Returns `false` if the cache doesn't exist or was written for a
+/// different `CacheKey`. If an error is returned `db` may have been
+/// partially filled in, so it should be discarded.
+pub fn read_cache(path: &Path, key: &CacheKey, db: &mut Database) -> io::Result<bool> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(error) => return Err(error),
+    };
+
+    let mut decoder = Decoder { reader: BufReader::new(file) };
+
+    let mut magic = [0u8; 8];
+    try!(decoder.reader.read_exact(&mut magic));
+    if &magic != MAGIC || try!(decoder.read_u32()) != VERSION {
+        return Ok(false);
+    }
+
+    if try!(decoder.read_key()) != *key {
+        return Ok(false);
+    }
+
+    try!(decoder.read_database(db));
+    return Ok(true);
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, message);
+}
+
+/// Kinds of waypoint an airway node can refer to.
+const NODE_FIX: u8 = 0;
+const NODE_NAVAID: u8 = 1;
+const NODE_AIRPORT: u8 = 2;
+
+struct Encoder<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Encoder<W> {
+    fn write_u8(&mut self, value: u8) -> io::Result<()> {
+        return self.writer.write_all(&[value]);
+    }
+
+    fn write_u32(&mut self, value: u32) -> io::Result<()> {
+        let bytes = [
+            value as u8,
+            (value >> 8) as u8,
+            (value >> 16) as u8,
+            (value >> 24) as u8,
+        ];
+        return self.writer.write_all(&bytes);
+    }
+
+    fn write_u64(&mut self, value: u64) -> io::Result<()> {
+        try!(self.write_u32(value as u32));
+        return self.write_u32((value >> 32) as u32);
+    }
+
+    fn write_i32(&mut self, value: i32) -> io::Result<()> {
+        return self.write_u32(value as u32);
+    }
+
+    fn write_f64(&mut self, value: f64) -> io::Result<()> {
+        return self.write_u64(value.to_bits());
+    }
+
+    fn write_bool(&mut self, value: bool) -> io::Result<()> {
+        return self.write_u8(value as u8);
+    }
+
+    fn write_len(&mut self, len: usize) -> io::Result<()> {
+        return self.write_u32(len as u32);
+    }
+
+    fn write_str(&mut self, value: &str) -> io::Result<()> {
+        try!(self.write_len(value.len()));
+        return self.writer.write_all(value.as_bytes());
+    }
+
+    fn write_option_str(&mut self, value: &Option<String>) -> io::Result<()> {
+        match *value {
+            Some(ref value) => {
+                try!(self.write_bool(true));
+                return self.write_str(value);
+            }
+            None => return self.write_bool(false),
+        }
+    }
+
+    fn write_option_u32(&mut self, value: Option<u32>) -> io::Result<()> {
+        match value {
+            Some(value) => {
+                try!(self.write_bool(true));
+                return self.write_u32(value);
+            }
+            None => return self.write_bool(false),
+        }
+    }
+
+    fn write_option_f64(&mut self, value: Option<f64>) -> io::Result<()> {
+        match value {
+            Some(value) => {
+                try!(self.write_bool(true));
+                return self.write_f64(value);
+            }
+            None => return self.write_bool(false),
+        }
+    }
+
+    fn write_pos(&mut self, pos: &SphericalCoordinate) -> io::Result<()> {
+        try!(self.write_f64(pos.r));
+        try!(self.write_f64(pos.theta));
+        return self.write_f64(pos.phi);
+    }
+
+    fn write_key(&mut self, key: &CacheKey) -> io::Result<()> {
+        try!(self.writer.write_all(MAGIC));
+        try!(self.write_u32(VERSION));
+        try!(self.write_i32(key.airac_cycle));
+        try!(self.write_u8(match key.format {
+            NavdataFormat::XPlane => 0,
+            NavdataFormat::Gns430 => 1,
+        }));
+        try!(self.write_u8(match key.load_mode {
+            LoadMode::Strict => 0,
+            LoadMode::Lenient => 1,
+        }));
+
+        try!(self.write_len(key.files.len()));
+        for &(ref name, hash) in &key.files {
+            try!(self.write_str(name));
+            try!(self.write_u64(hash));
+        }
+
+        return Ok(());
+    }
+
+    fn write_database(&mut self, db: &Database) -> io::Result<()> {
+        let mut countries: Vec<&Arc<Country>> = db.countries.values().collect();
+        countries.sort_by(|a, b| a.code.cmp(&b.code));
+        try!(self.write_len(countries.len()));
+        for country in countries {
+            try!(self.write_str(&country.code));
+            try!(self.write_str(&country.name));
+        }
+
+        let mut airport_indices = HashMap::new();
+        for (index, airport) in db.airports.iter().enumerate() {
+            airport_indices.insert(address(&**airport), index);
+        }
+
+        try!(self.write_len(db.airports.len()));
+        for airport in &db.airports {
+            try!(self.write_airport(airport, &airport_indices));
+        }
+
+        try!(self.write_len(db.fixes.len()));
+        for fix in &db.fixes {
+            try!(self.write_waypoint(fix, &airport_indices));
+        }
+
+        try!(self.write_len(db.navaids.len()));
+        for navaid in &db.navaids {
+            try!(self.write_navaid(navaid, &airport_indices));
+        }
+
+        try!(self.write_airway_graph(db, &airport_indices));
+
+        try!(self.write_len(db.load_report.files.len()));
+        for file in &db.load_report.files {
+            try!(self.write_file_report(file));
+        }
+
+        return Ok(());
+    }
+
+    fn write_waypoint(
+        &mut self,
+        waypoint: &Waypoint,
+        airport_indices: &HashMap<usize, usize>,
+    ) -> io::Result<()> {
+        try!(self.write_str(&waypoint.code));
+        try!(self.write_str(&waypoint.name));
+        try!(self.write_pos(&waypoint.pos));
+
+        let airport = waypoint.airport.as_ref().and_then(|airport| {
+            airport_indices.get(&address(&**airport)).map(|index| *index as u32)
+        });
+        try!(self.write_option_u32(airport));
+
+        try!(self.write_str(&waypoint.region));
+        return self.write_option_str(&waypoint.waypoint_type);
+    }
+
+    fn write_navaid(
+        &mut self,
+        navaid: &Navaid,
+        airport_indices: &HashMap<usize, usize>,
+    ) -> io::Result<()> {
+        try!(self.write_waypoint(&navaid.waypoint, airport_indices));
+        try!(self.write_u8(navaid_type_tag(navaid.navaid_type)));
+        try!(self.write_f64(navaid.elevation));
+        try!(self.write_option_f64(navaid.frequency));
+        try!(self.write_option_u32(navaid.channel));
+        try!(self.write_option_f64(navaid.range));
+        try!(self.write_option_f64(navaid.slaved_variation));
+        try!(self.write_option_f64(navaid.bearing));
+        try!(self.write_option_f64(navaid.glide_path_angle));
+        try!(self.write_option_f64(navaid.bias));
+        return self.write_option_str(&navaid.runway);
+    }
+
+    fn write_airport(
+        &mut self,
+        airport: &Airport,
+        airport_indices: &HashMap<usize, usize>,
+    ) -> io::Result<()> {
+        try!(self.write_waypoint(&airport.waypoint, airport_indices));
+        try!(self.write_u8(match airport.airport_type {
+            AirportType::Land => 0,
+            AirportType::Seaplane => 1,
+            AirportType::Heliport => 2,
+        }));
+        try!(self.write_f64(airport.elevation));
+
+        try!(self.write_len(airport.runways.len()));
+        for runway in &airport.runways {
+            try!(self.write_runway(runway));
+        }
+
+        try!(self.write_len(airport.frequencies.len()));
+        for frequency in &airport.frequencies {
+            try!(self.write_u8(frequency_type_tag(frequency.frequency_type)));
+            try!(self.write_f64(frequency.frequency));
+            try!(self.write_str(&frequency.name));
+        }
+
+        try!(self.write_option_str(&airport.icao_code));
+        try!(self.write_option_str(&airport.iata_code));
+        try!(self.write_option_str(&airport.faa_code));
+        try!(self.write_option_str(&airport.city));
+        try!(self.write_option_str(&airport.country_name));
+        try!(self.write_option_u32(airport.transition_altitude));
+        return self.write_option_u32(airport.transition_level);
+    }
+
+    fn write_runway(&mut self, runway: &Runway) -> io::Result<()> {
+        try!(self.write_u8(match runway.runway_type {
+            RunwayType::Land => 0,
+            RunwayType::Water => 1,
+            RunwayType::Helipad => 2,
+        }));
+        try!(self.write_f64(runway.width));
+        try!(self.write_f64(runway.length));
+
+        let (surface, unknown_code) = surface_tag(runway.surface);
+        try!(self.write_u8(surface));
+        try!(self.write_u32(unknown_code));
+
+        try!(self.write_bool(runway.centreline_lighting));
+        try!(self.write_u32(runway.edge_lighting));
+
+        try!(self.write_len(runway.ends.len()));
+        for end in &runway.ends {
+            try!(self.write_str(&end.number));
+            try!(self.write_pos(&end.threshold));
+            try!(self.write_f64(end.heading));
+            try!(self.write_f64(end.displaced_threshold));
+            try!(self.write_f64(end.overrun));
+            try!(self.write_u32(end.markings));
+            try!(self.write_u32(end.approach_lighting));
+            try!(self.write_bool(end.touchdown_zone_lighting));
+            try!(self.write_u32(end.reil));
+        }
+
+        return Ok(());
+    }
+
+    fn write_airway_graph(
+        &mut self,
+        db: &Database,
+        airport_indices: &HashMap<usize, usize>,
+    ) -> io::Result<()> {
+        let mut waypoints = HashMap::new();
+        for (index, fix) in db.fixes.iter().enumerate() {
+            waypoints.insert(address(&**fix), (NODE_FIX, index));
+        }
+        for (index, navaid) in db.navaids.iter().enumerate() {
+            waypoints.insert(address(&**navaid), (NODE_NAVAID, index));
+        }
+        for (&airport, &index) in airport_indices {
+            waypoints.insert(airport, (NODE_AIRPORT, index));
+        }
+
+        // nodes are written in index order, so that adding them to a new
+        // graph gives them the same indices as the segments refer to.
+        let graph = &db.airway_graph;
+        let mut keys: Vec<(&AirwayNodeKey, usize)> =
+            graph.node_keys().iter().map(|(key, index)| (key, *index)).collect();
+        keys.sort_by_key(|&(_, index)| index);
+
+        try!(self.write_len(keys.len()));
+        for (key, index) in keys {
+            let &(kind, waypoint) = try!(
+                waypoints
+                    .get(&address(&**graph.node(index)))
+                    .ok_or_else(|| invalid_data("Airway node is not in the database"))
+            );
+
+            try!(self.write_str(&key.code));
+            try!(self.write_str(&key.region));
+            try!(self.write_u8(match key.node_type {
+                AirwayNodeType::Fix => 0,
+                AirwayNodeType::Ndb => 1,
+                AirwayNodeType::Vhf => 2,
+            }));
+            try!(self.write_u8(kind));
+            try!(self.write_len(waypoint));
+        }
+
+        try!(self.write_len(graph.segments().len()));
+        for segment in graph.segments() {
+            try!(self.write_str(&segment.name));
+            try!(self.write_len(segment.from));
+            try!(self.write_len(segment.to));
+            try!(self.write_u8(match segment.direction {
+                AirwayDirection::Both => 0,
+                AirwayDirection::Forward => 1,
+                AirwayDirection::Backward => 2,
+            }));
+            try!(self.write_u8(match segment.class {
+                AirwayClass::Low => 0,
+                AirwayClass::High => 1,
+            }));
+            try!(self.write_u32(segment.base));
+            try!(self.write_u32(segment.top));
+        }
+
+        return Ok(());
+    }
+
+    fn write_file_report(&mut self, file: &FileReport) -> io::Result<()> {
+        try!(self.write_str(&file.path));
+        try!(self.write_len(file.loaded));
+
+        try!(self.write_len(file.skipped.len()));
+        for skipped in &file.skipped {
+            try!(self.write_len(skipped.line));
+            try!(self.write_str(&skipped.text));
+            try!(self.write_str(&skipped.reason));
+        }
+
+        try!(self.write_len(file.unresolved.len()));
+        for unresolved in &file.unresolved {
+            try!(self.write_len(unresolved.line));
+            try!(self.write_str(&unresolved.code));
+            try!(self.write_option_str(&unresolved.region));
+            try!(self.write_str(&unresolved.context));
+        }
+
+        return Ok(());
+    }
+}
+
+struct Decoder<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> Decoder<R> {
+    fn read_u8(&mut self) -> io::Result<u8> {
+        let mut bytes = [0u8; 1];
+        try!(self.reader.read_exact(&mut bytes));
+        return Ok(bytes[0]);
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32> {
+        let mut bytes = [0u8; 4];
+        try!(self.reader.read_exact(&mut bytes));
+        return Ok(
+            bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 |
+                (bytes[3] as u32) << 24,
+        );
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        let low = try!(self.read_u32()) as u64;
+        let high = try!(self.read_u32()) as u64;
+        return Ok(low | high << 32);
+    }
+
+    fn read_i32(&mut self) -> io::Result<i32> {
+        return Ok(try!(self.read_u32()) as i32);
+    }
+
+    fn read_f64(&mut self) -> io::Result<f64> {
+        return Ok(f64::from_bits(try!(self.read_u64())));
+    }
+
+    fn read_bool(&mut self) -> io::Result<bool> {
+        match try!(self.read_u8()) {
+            0 => return Ok(false),
+            1 => return Ok(true),
+            _ => return Err(invalid_data("Invalid bool")),
+        }
+    }
+
+    fn read_len(&mut self) -> io::Result<usize> {
+        return Ok(try!(self.read_u32()) as usize);
+    }
+
+    fn read_string(&mut self) -> io::Result<String> {
+        let len = try!(self.read_len());
+        let mut bytes = Vec::new();
+        try!((&mut self.reader).take(len as u64).read_to_end(&mut bytes));
+        if bytes.len() != len {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated string"));
+        }
+
+        return String::from_utf8(bytes).map_err(|_| invalid_data("Invalid UTF-8 string"));
+    }
+
+    fn read_option_string(&mut self) -> io::Result<Option<String>> {
+        if try!(self.read_bool()) {
+            return Ok(Some(try!(self.read_string())));
+        }
+        return Ok(None);
+    }
+
+    fn read_option_u32(&mut self) -> io::Result<Option<u32>> {
+        if try!(self.read_bool()) {
+            return Ok(Some(try!(self.read_u32())));
+        }
+        return Ok(None);
+    }
+
+    fn read_option_f64(&mut self) -> io::Result<Option<f64>> {
+        if try!(self.read_bool()) {
+            return Ok(Some(try!(self.read_f64())));
+        }
+        return Ok(None);
+    }
+
+    fn read_pos(&mut self) -> io::Result<SphericalCoordinate> {
+        // the fields are set directly, as they were already rectified when
+        // the position was first read.
+        let mut pos = SphericalCoordinate::new(0.0, 0.0, 0.0);
+        pos.r = try!(self.read_f64());
+        pos.theta = try!(self.read_f64());
+        pos.phi = try!(self.read_f64());
+        return Ok(pos);
+    }
+
+    fn read_key(&mut self) -> io::Result<CacheKey> {
+        let airac_cycle = try!(self.read_i32());
+        let format = match try!(self.read_u8()) {
+            0 => NavdataFormat::XPlane,
+            1 => NavdataFormat::Gns430,
+            _ => return Err(invalid_data("Invalid navdata format")),
+        };
+        let load_mode = match try!(self.read_u8()) {
+            0 => LoadMode::Strict,
+            1 => LoadMode::Lenient,
+            _ => return Err(invalid_data("Invalid load mode")),
+        };
+
+        let n_files = try!(self.read_len());
+        let mut files = Vec::new();
+        for _ in 0..n_files {
+            let name = try!(self.read_string());
+            files.push((name, try!(self.read_u64())));
+        }
+
+        return Ok(CacheKey {
+            airac_cycle: airac_cycle,
+            format: format,
+            load_mode: load_mode,
+            files: files,
+        });
+    }
+
+    fn read_database(&mut self, db: &mut Database) -> io::Result<()> {
+        let n_countries = try!(self.read_len());
+        for _ in 0..n_countries {
+            let code = try!(self.read_string());
+            let name = try!(self.read_string());
+            db.countries.insert(code.clone(), Arc::new(Country::new(code, name)));
+        }
+
+        let n_airports = try!(self.read_len());
+        for _ in 0..n_airports {
+            let airport = try!(self.read_airport(&db.airports));
+            db.insert_airport(airport);
+        }
+
+        let n_fixes = try!(self.read_len());
+        for _ in 0..n_fixes {
+            let fix = try!(self.read_waypoint(&db.airports));
+            db.insert_fix(fix);
+        }
+
+        let n_navaids = try!(self.read_len());
+        for _ in 0..n_navaids {
+            let navaid = try!(self.read_navaid(&db.airports));
+            db.insert_navaid(navaid);
+        }
+
+        try!(self.read_airway_graph(db));
+        for airway in db.airway_graph.build_airways() {
+            db.airways.insert(airway.name.clone(), Arc::new(airway));
+        }
+
+        let n_files = try!(self.read_len());
+        for _ in 0..n_files {
+            let file = try!(self.read_file_report());
+            db.load_report.files.push(file);
+        }
+
+        return Ok(());
+    }
+
+    fn read_waypoint(&mut self, airports: &[Arc<Airport>]) -> io::Result<Waypoint> {
+        let code = try!(self.read_string());
+        let name = try!(self.read_string());
+        let pos = try!(self.read_pos());
+
+        let airport = match try!(self.read_option_u32()) {
+            Some(index) => {
+                Some(try!(airports.get(index as usize).cloned().ok_or_else(|| {
+                    invalid_data("Invalid airport index")
+                })))
+            }
+            None => None,
+        };
+
+        let mut waypoint = Waypoint::new(code, name, pos, airport);
+        waypoint.region = try!(self.read_string());
+        waypoint.waypoint_type = try!(self.read_option_string());
+        return Ok(waypoint);
+    }
+
+    fn read_navaid(&mut self, airports: &[Arc<Airport>]) -> io::Result<Navaid> {
+        let waypoint = try!(self.read_waypoint(airports));
+        let navaid_type = try!(navaid_type_from_tag(try!(self.read_u8())));
+
+        return Ok(Navaid {
+            waypoint: waypoint,
+            navaid_type: navaid_type,
+            elevation: try!(self.read_f64()),
+            frequency: try!(self.read_option_f64()),
+            channel: try!(self.read_option_u32()),
+            range: try!(self.read_option_f64()),
+            slaved_variation: try!(self.read_option_f64()),
+            bearing: try!(self.read_option_f64()),
+            glide_path_angle: try!(self.read_option_f64()),
+            bias: try!(self.read_option_f64()),
+            runway: try!(self.read_option_string()),
+        });
+    }
+
+    fn read_airport(&mut self, airports: &[Arc<Airport>]) -> io::Result<Airport> {
+        let waypoint = try!(self.read_waypoint(airports));
+        let airport_type = match try!(self.read_u8()) {
+            0 => AirportType::Land,
+            1 => AirportType::Seaplane,
+            2 => AirportType::Heliport,
+            _ => return Err(invalid_data("Invalid airport type")),
+        };
+        let elevation = try!(self.read_f64());
+
+        let n_runways = try!(self.read_len());
+        let mut runways = Vec::new();
+        for _ in 0..n_runways {
+            runways.push(try!(self.read_runway()));
+        }
+
+        let n_frequencies = try!(self.read_len());
+        let mut frequencies = Vec::new();
+        for _ in 0..n_frequencies {
+            let frequency_type = try!(frequency_type_from_tag(try!(self.read_u8())));
+            frequencies.push(AirportFrequency {
+                frequency_type: frequency_type,
+                frequency: try!(self.read_f64()),
+                name: try!(self.read_string()),
+            });
+        }
+
+        return Ok(Airport {
+            waypoint: waypoint,
+            airport_type: airport_type,
+            elevation: elevation,
+            runways: runways,
+            frequencies: frequencies,
+            icao_code: try!(self.read_option_string()),
+            iata_code: try!(self.read_option_string()),
+            faa_code: try!(self.read_option_string()),
+            city: try!(self.read_option_string()),
+            country_name: try!(self.read_option_string()),
+            transition_altitude: try!(self.read_option_u32()),
+            transition_level: try!(self.read_option_u32()),
+        });
+    }
+
+    fn read_runway(&mut self) -> io::Result<Runway> {
+        let runway_type = match try!(self.read_u8()) {
+            0 => RunwayType::Land,
+            1 => RunwayType::Water,
+            2 => RunwayType::Helipad,
+            _ => return Err(invalid_data("Invalid runway type")),
+        };
+        let width = try!(self.read_f64());
+        let length = try!(self.read_f64());
+        let surface_tag = try!(self.read_u8());
+        let surface = try!(surface_from_tag(surface_tag, try!(self.read_u32())));
+        let centreline_lighting = try!(self.read_bool());
+        let edge_lighting = try!(self.read_u32());
+
+        let n_ends = try!(self.read_len());
+        let mut ends = Vec::new();
+        for _ in 0..n_ends {
+            let number = try!(self.read_string());
+            let mut end = RunwayEnd::new(number, try!(self.read_pos()));
+            end.heading = try!(self.read_f64());
+            end.displaced_threshold = try!(self.read_f64());
+            end.overrun = try!(self.read_f64());
+            end.markings = try!(self.read_u32());
+            end.approach_lighting = try!(self.read_u32());
+            end.touchdown_zone_lighting = try!(self.read_bool());
+            end.reil = try!(self.read_u32());
+            ends.push(end);
+        }
+
+        return Ok(Runway {
+            runway_type: runway_type,
+            width: width,
+            length: length,
+            surface: surface,
+            centreline_lighting: centreline_lighting,
+            edge_lighting: edge_lighting,
+            ends: ends,
+        });
+    }
+
+    fn read_airway_graph(&mut self, db: &mut Database) -> io::Result<()> {
+        let n_nodes = try!(self.read_len());
+        for expected_index in 0..n_nodes {
+            let code = try!(self.read_string());
+            let region = try!(self.read_string());
+            let node_type = match try!(self.read_u8()) {
+                0 => AirwayNodeType::Fix,
+                1 => AirwayNodeType::Ndb,
+                2 => AirwayNodeType::Vhf,
+                _ => return Err(invalid_data("Invalid airway node type")),
+            };
+
+            let kind = try!(self.read_u8());
+            let index = try!(self.read_len());
+            let waypoint = match kind {
+                NODE_FIX => waypoint_at(&db.fixes, index),
+                NODE_NAVAID => waypoint_at(&db.navaids, index),
+                NODE_AIRPORT => waypoint_at(&db.airports, index),
+                _ => None,
+            };
+            let waypoint = try!(waypoint.ok_or_else(|| invalid_data("Invalid airway node")));
+
+            let key = AirwayNodeKey::new(code, region, node_type);
+            if db.airway_graph.add_node(key, waypoint) != expected_index {
+                return Err(invalid_data("Duplicate airway node"));
+            }
+        }
+
+        let n_segments = try!(self.read_len());
+        for _ in 0..n_segments {
+            let name = try!(self.read_string());
+            let from = try!(self.read_len());
+            let to = try!(self.read_len());
+            if from >= n_nodes || to >= n_nodes {
+                return Err(invalid_data("Invalid airway segment node"));
+            }
+
+            let direction = match try!(self.read_u8()) {
+                0 => AirwayDirection::Both,
+                1 => AirwayDirection::Forward,
+                2 => AirwayDirection::Backward,
+                _ => return Err(invalid_data("Invalid airway direction")),
+            };
+            let class = match try!(self.read_u8()) {
+                0 => AirwayClass::Low,
+                1 => AirwayClass::High,
+                _ => return Err(invalid_data("Invalid airway class")),
+            };
+
+            db.airway_graph.add_segment(AirwaySegment {
+                name: name,
+                from: from,
+                to: to,
+                direction: direction,
+                class: class,
+                base: try!(self.read_u32()),
+                top: try!(self.read_u32()),
+            });
+        }
+
+        return Ok(());
+    }
+
+    fn read_file_report(&mut self) -> io::Result<FileReport> {
+        let mut file = FileReport::new(try!(self.read_string()));
+        file.loaded = try!(self.read_len());
+
+        let n_skipped = try!(self.read_len());
+        for _ in 0..n_skipped {
+            file.skipped.push(SkippedLine {
+                line: try!(self.read_len()),
+                text: try!(self.read_string()),
+                reason: try!(self.read_string()),
+            });
+        }
+
+        let n_unresolved = try!(self.read_len());
+        for _ in 0..n_unresolved {
+            file.unresolved.push(UnresolvedReference {
+                line: try!(self.read_len()),
+                code: try!(self.read_string()),
+                region: try!(self.read_option_string()),
+                context: try!(self.read_string()),
+            });
+        }
+
+        return Ok(file);
+    }
+}
+
+/// The waypoint at `index` in one of the collections of a `Database`.
+fn waypoint_at<T: WaypointInterface + 'static>(
+    waypoints: &[Arc<T>],
+    index: usize,
+) -> Option<Arc<WaypointInterface>> {
+    if let Some(waypoint) = waypoints.get(index) {
+        return Some(waypoint.clone());
+    }
+    return None;
+}
+
+const NAVAID_TYPES: [NavaidType; 15] = [
+    NavaidType::Ndb,
+    NavaidType::Vor,
+    NavaidType::VorDme,
+    NavaidType::Vortac,
+    NavaidType::Dme,
+    NavaidType::Tacan,
+    NavaidType::IlsLocalizer,
+    NavaidType::Localizer,
+    NavaidType::Glideslope,
+    NavaidType::OuterMarker,
+    NavaidType::MiddleMarker,
+    NavaidType::InnerMarker,
+    NavaidType::Fpap,
+    NavaidType::Gls,
+    NavaidType::Threshold,
+];
+
+fn navaid_type_tag(navaid_type: NavaidType) -> u8 {
+    return NAVAID_TYPES
+        .iter()
+        .position(|t| *t == navaid_type)
+        .unwrap() as u8;
+}
+
+fn navaid_type_from_tag(tag: u8) -> io::Result<NavaidType> {
+    return NAVAID_TYPES.get(tag as usize).cloned().ok_or_else(|| {
+        invalid_data("Invalid navaid type")
+    });
+}
+
+const FREQUENCY_TYPES: [FrequencyType; 7] = [
+    FrequencyType::Recorded,
+    FrequencyType::Unicom,
+    FrequencyType::Clearance,
+    FrequencyType::Ground,
+    FrequencyType::Tower,
+    FrequencyType::Approach,
+    FrequencyType::Departure,
+];
+
+fn frequency_type_tag(frequency_type: FrequencyType) -> u8 {
+    return FREQUENCY_TYPES
+        .iter()
+        .position(|t| *t == frequency_type)
+        .unwrap() as u8;
+}
+
+fn frequency_type_from_tag(tag: u8) -> io::Result<FrequencyType> {
+    return FREQUENCY_TYPES.get(tag as usize).cloned().ok_or_else(|| {
+        invalid_data("Invalid frequency type")
+    });
+}
+
+/// The tag for a `Surface`, and the code of an unknown surface.
+fn surface_tag(surface: Surface) -> (u8, u32) {
+    match surface {
+        Surface::Asphalt => (0, 0),
+        Surface::Concrete => (1, 0),
+        Surface::Grass => (2, 0),
+        Surface::Dirt => (3, 0),
+        Surface::Gravel => (4, 0),
+        Surface::DryLakebed => (5, 0),
+        Surface::Water => (6, 0),
+        Surface::SnowOrIce => (7, 0),
+        Surface::Transparent => (8, 0),
+        Surface::Unknown(code) => (9, code),
+    }
+}
+
+fn surface_from_tag(tag: u8, code: u32) -> io::Result<Surface> {
+    let surface = match tag {
+        0 => Surface::Asphalt,
+        1 => Surface::Concrete,
+        2 => Surface::Grass,
+        3 => Surface::Dirt,
+        4 => Surface::Gravel,
+        5 => Surface::DryLakebed,
+        6 => Surface::Water,
+        7 => Surface::SnowOrIce,
+        8 => Surface::Transparent,
+        9 => Surface::Unknown(code),
+        _ => return Err(invalid_data("Invalid surface")),
+    };
+
+    return Ok(surface);
+}
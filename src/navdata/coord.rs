@@ -6,6 +6,7 @@ use nalgebra::core::{Vector3, Vector2};
 use std::f64::consts::PI;
 use std::f64::*;
 use std::fmt;
+use navdata::geodesic::EarthModel;
 use navdata::geohash;
 
 static TWO_PI: f64 = PI * 2.0;
@@ -14,6 +15,9 @@ static HALF_PI: f64 = PI / 2.0;
 /// Mean sea level on earth
 pub static EARTH_MSL_RADIUS: f64 = 6371008.8;
 
+/// Length of a nautical mile in meters
+pub static METERS_PER_NAUTICAL_MILE: f64 = 1852.0;
+
 /// Represents a coordinate in the spherical coordinate system.
 ///
 /// The normal range for the variables is as follows:
@@ -306,11 +310,88 @@ impl SphericalCoordinate {
     }
 
     /// arc distance between two points along the surface of the sphere.
-    /// **warning: only tested to be accurate to within 5 meters at earth's surface**
+    /// **warning: only tested to be accurate to within 5 meters at earth's surface**,
+    /// see `distance_to()` for the distance over the WGS84 ellipsoid.
     pub fn arc_distance(&self, other: &SphericalCoordinate) -> f64 {
         // if greater accuracy is required, might be worth checking out the haversine formula
         // or this: https://goo.gl/Niyn91
-        return self.r * (self.r_cart_uv().dot(&other.r_cart_uv()).acos());
+        // rounding can push the dot product of two equal positions past 1
+        let cos_angle = self.r_cart_uv().dot(&other.r_cart_uv()).max(-1.0).min(1.0);
+        return self.r * cos_angle.acos();
+    }
+
+    /// Initial bearing (in degrees from true north, 0 -> 360) of the great circle
+    /// path from this position to another.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::coord::SphericalCoordinate;
+    /// let accuracy = 0.0001;
+    /// let pos1 = SphericalCoordinate::from_geographic(0.0, 0.0, 10.0);
+    /// let pos2 = SphericalCoordinate::from_geographic(0.0, 0.0, 11.0);
+    /// assert!((pos1.bearing_to(&pos2) - 90.0).abs() < accuracy);
+    /// assert!((pos2.bearing_to(&pos1) - 270.0).abs() < accuracy);
+    /// ```
+    pub fn bearing_to(&self, other: &SphericalCoordinate) -> f64 {
+        let lat1 = self.lat().to_radians();
+        let lat2 = other.lat().to_radians();
+        let dlon = (other.lon() - self.lon()).to_radians();
+
+        let y = dlon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+
+        return (y.atan2(x).to_degrees() + 360.0) % 360.0;
+    }
+
+    /// Distance (in metres) along the surface of the earth to another position, over the
+    /// geodesic of the `EarthModel`, ignoring the altitude of both.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::coord::SphericalCoordinate;
+    /// # use oldnav_lib::navdata::geodesic::EarthModel;
+    /// let pos1 = SphericalCoordinate::from_geographic(0.0, 38.0, 148.0);
+    /// let pos2 = SphericalCoordinate::from_geographic(0.0, 38.0, 149.0);
+    /// let d = pos1.distance_to(&pos2, EarthModel::Wgs84);
+    /// assert!((d - 87832.0).abs() < 1.0);
+    /// ```
+    pub fn distance_to(&self, other: &SphericalCoordinate, model: EarthModel) -> f64 {
+        return model.inverse(self, other).distance;
+    }
+
+    /// Initial azimuth (in degrees from true north, 0 -> 360) of the geodesic of the
+    /// `EarthModel` from this position to another, as `bearing_to()` for the great circle.
+    pub fn azimuth_to(&self, other: &SphericalCoordinate, model: EarthModel) -> f64 {
+        let azimuth = model.inverse(self, other).initial_azimuth;
+        return if azimuth < 0.0 { azimuth + 360.0 } else { azimuth };
+    }
+
+    /// The position at a `distance` (in metres) along the geodesic of the `EarthModel`
+    /// from this position, with the initial `azimuth` (in degrees from true north). The
+    /// altitude is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::coord::SphericalCoordinate;
+    /// # use oldnav_lib::navdata::geodesic::EarthModel;
+    /// let pos = SphericalCoordinate::from_geographic(1000.0, -37.67, 144.84);
+    /// let dest = pos.destination(45.0, 100000.0, EarthModel::Wgs84);
+    ///
+    /// assert!((dest.alt() - 1000.0).abs() < 0.0001);
+    /// assert!((pos.distance_to(&dest, EarthModel::Wgs84) - 100000.0).abs() < 0.0001);
+    /// assert!((pos.azimuth_to(&dest, EarthModel::Wgs84) - 45.0).abs() < 1e-9);
+    /// ```
+    pub fn destination(
+        &self,
+        azimuth: f64,
+        distance: f64,
+        model: EarthModel,
+    ) -> SphericalCoordinate {
+        let direct = model.direct(self, azimuth, distance);
+        return SphericalCoordinate::from_geographic(self.alt(), direct.lat, direct.lon);
     }
 
     /// Format the `SphericalCoordinate` as a Geographical point string (altitude,
@@ -358,13 +439,13 @@ impl fmt::Display for SphericalCoordinate {
 }
 
 impl geohash::Geohashable<SphericalCoordinate> for SphericalCoordinate {
-    fn integer_decode(geohash: u64) -> Result<SphericalCoordinate, String> {
+    fn integer_decode(geohash: u64) -> Result<SphericalCoordinate, geohash::GeohashError> {
         let bounds = try!(geohash::decode(geohash, &geohash::LATLON_BOUNDS));
         let pos = bounds.mid();
         let coord = SphericalCoordinate::from_geographic(0.0, pos.y, pos.x);
         return Ok(coord);
     }
-    fn integer_encode(&self, precision: u8) -> Result<u64, String> {
+    fn integer_encode(&self, precision: u8) -> Result<u64, geohash::GeohashError> {
         return geohash::encode(
             &Vector2::new(self.lon(), self.lat()),
             precision,
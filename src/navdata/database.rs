@@ -1,23 +1,44 @@
-//! The Navdata Database - Loaded from the x-plane GNS430 database.
-
-use navdata::waypoint::{Waypoint, UnlinkedWaypoint};
+//! The Navdata Database - Loaded from x-plane's navdata files.
+//!
+//! Two formats of navdata are supported, and the format is detected from
+//! the files present in the navdata directory:
+//!
+//! + The `earth_fix.dat`, `earth_nav.dat`, `earth_awy.dat` and `apt.dat`
+//!   files used by x-plane 11 onwards.
+//! + The comma separated `Waypoints.txt`, `Navaids.txt`, `ATS.txt` and
+//!   `Airports.txt` files of the GNS430 navdata used by x-plane 9 and 10.
+
+use navdata::waypoint::{Waypoint, UnlinkedWaypoint, WaypointInterface};
+use navdata::navaid::{Navaid, NavaidType};
+use navdata::airport::{Airport, AirportType, AirportFrequency, FrequencyType, Runway, RunwayEnd,
+                       RunwayType, Surface};
 use navdata::multihash::MultiHash;
+use navdata::spatial::SpatialIndex;
 use navdata::country::Country;
+use navdata::kind::{WaypointKind, WaypointKinds};
+use navdata::procedure::{self, AirportProcedures, ProcedureFix};
+use navdata::airac::AiracCycle;
+use navdata::cache;
+use navdata::loader::{LoadProgress, ProgressCallback};
+use navdata::report::{LoadReport, FileReport, SkippedLine, UnresolvedReference};
 use navdata::coord::SphericalCoordinate;
-use navdata::route::Route;
-use std::collections::HashMap;
-use std::io::{BufReader, BufRead};
+use navdata::airway::{Airway, AirwayGraph, AirwaySegment, UnlinkedAirwaySegment, AirwayNodeKey,
+                      AirwayNodeType, AirwayDirection, AirwayClass};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufReader, BufRead};
 use std::fs::File;
-use std::path::PathBuf;
-use std::rc::Rc;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::fmt;
+use std::cmp::Ordering;
+use std::error::Error;
 use chrono::{DateTime, UTC, TimeZone};
 use chrono::format::ParseResult;
-use std::mem;
-use combine::char::{newline, spaces, Spaces, char, alpha_num, digit, space};
-use combine::{many1, Parser, sep_by, StreamError, optional, one_of, count, Stream, any, many};
+use combine::char::{spaces, char, alpha_num, digit};
+use combine::{many1, Parser, StreamError, optional, count, Stream, any, many, satisfy};
 
 /// The result of a query for waypoint or waypoints in `Database`
+#[derive(Debug)]
 pub enum WaypointQueryResult<T> {
     /// `T` was found
     Found(T),
@@ -32,18 +53,18 @@ pub enum WaypointQueryResult<T> {
 impl<T> WaypointQueryResult<T> {
     /// Returns true if the result is `Found`.
     #[inline]
-    pub fn is_found(self) -> bool {
-        match self {
+    pub fn is_found(&self) -> bool {
+        match *self {
             WaypointQueryResult::Found(_) => true,
             WaypointQueryResult::TooFar(_) => false,
             WaypointQueryResult::NotFound => false,
         }
     }
 
-    /// Returns true if the result is `None`.
+    /// Returns true if the result is `NotFound`.
     #[inline]
-    pub fn is_none(self) -> bool {
-        match self {
+    pub fn is_none(&self) -> bool {
+        match *self {
             WaypointQueryResult::Found(_) => false,
             WaypointQueryResult::TooFar(_) => false,
             WaypointQueryResult::NotFound => true,
@@ -52,11 +73,11 @@ impl<T> WaypointQueryResult<T> {
 
     /// Returns true if the result is `TooFar`.
     #[inline]
-    pub fn is_too_far(self) -> bool {
-        match self {
+    pub fn is_too_far(&self) -> bool {
+        match *self {
             WaypointQueryResult::Found(_) => false,
-            WaypointQueryResult::TooFar(_) => false,
-            WaypointQueryResult::NotFound => true,
+            WaypointQueryResult::TooFar(_) => true,
+            WaypointQueryResult::NotFound => false,
         }
     }
 
@@ -83,29 +104,105 @@ impl<T> WaypointQueryResult<T> {
     }
 }
 
+/// One of the waypoints sharing an ident, as listed on an FMS "duplicate
+/// waypoints" page. See `Database::waypoint_candidates()`.
+pub struct WaypointCandidate {
+    /// The waypoint
+    pub waypoint: Arc<WaypointInterface>,
+
+    /// ICAO region code of the waypoint
+    pub region: String,
+
+    /// The kind of waypoint
+    pub kind: WaypointKind,
+
+    /// Distance (in meters) from the position the candidates were found
+    /// for
+    pub distance: f64,
+}
+
+impl fmt::Debug for WaypointCandidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "WaypointCandidate: {{code: {}, region: {}, kind: {:?}, distance: {}}}",
+            self.waypoint.code(),
+            self.region,
+            self.kind,
+            self.distance
+        );
+    }
+}
+
 
 /// A navigation database
+///
+/// Once loaded, a `Database` is only read from, and it is `Send` and `Sync`,
+/// so it can be shared between threads in an `Arc` without copying it.
+///
+/// ```rust,no_run
+/// # use oldnav_lib::navdata::database::Database;
+/// # use std::path::PathBuf;
+/// # use std::sync::Arc;
+/// # use std::thread;
+/// let db = Arc::new(Database::new(PathBuf::new(), PathBuf::new()).unwrap());
+///
+/// let worker_db = db.clone();
+/// let worker = thread::spawn(move || worker_db.airport_hash.get("KSEA").is_some());
+///
+/// println!("{} fixes", db.fixes.len());
+/// worker.join().unwrap();
+/// ```
 pub struct Database {
     /// Where all the fixes are stored in the database
-    pub fixes: Vec<Rc<Waypoint>>,
+    pub fixes: Vec<Arc<Waypoint>>,
 
-    /// hash of waypoints associated with their names
-    pub waypoint_hash: MultiHash<String, Rc<Waypoint>>,
+    /// Where all the navaids are stored in the database
+    pub navaids: Vec<Arc<Navaid>>,
+
+    /// Where all the airports are stored in the database
+    pub airports: Vec<Arc<Airport>>,
+
+    /// hash of airports associated with their codes
+    pub airport_hash: HashMap<String, Arc<Airport>>,
+
+    /// hash of waypoints (fixes, navaids and airports) associated with their codes
+    pub waypoint_hash: MultiHash<String, Arc<WaypointInterface>>,
+
+    /// Index of the waypoints (fixes, navaids and airports) by position, for
+    /// nearest, radius and bounding box queries
+    pub spatial_index: SpatialIndex,
 
-    /// note: if I want to make waypoint mutable, or country mutable,
-    /// I may need to put them in a Rc<RefCell<Waypoint>>.
-    ///
-    /// pub airports: Vec<Airport<'a>>,
-    /// pub waypoints: MultiHash<String, &'a WaypointInterface>,
     /// Where all the countries are stored in the database
-    pub countries: HashMap<String, Rc<Country>>,
+    pub countries: HashMap<String, Arc<Country>>,
 
-    /// Where all the airways are stored in the database
-    pub airways: HashMap<String, Rc<Route>>,
+    /// Where all the airways are stored in the database, associated with
+    /// their names. Airways in different parts of the world can share the
+    /// same name.
+    pub airways: MultiHash<String, Arc<Airway>>,
+
+    /// The graph of airway segments connecting the waypoints in the database
+    pub airway_graph: AirwayGraph,
 
     /// Information about the current AIRAC cycle loaded into this navigation
     /// database.
     pub cycle_info: CycleInfo,
+
+    /// The format of the navdata this database was loaded from
+    pub format: NavdataFormat,
+
+    /// The directory the navdata was loaded from
+    pub navdata_dir: PathBuf,
+
+    /// How invalid lines were handled while loading
+    pub load_mode: LoadMode,
+
+    /// Diagnostics for each of the files read while loading
+    pub load_report: LoadReport,
+
+    /// Whether the database was read from the binary cache rather than
+    /// parsed from the navdata files
+    pub loaded_from_cache: bool,
 }
 
 parser!{
@@ -114,7 +211,7 @@ parser!{
         [I: Stream<Item = char>,]
     {
         many1(digit().or(char('-').or(char('.'))))
-        .map(|string: String| string.parse::<f64>().unwrap())
+        .and_then(|string: String| string.parse::<f64>())
         .message("while parsing float")
     }
 }
@@ -127,7 +224,7 @@ parser!{
     {
         many1(alpha_num())
         .map(|string: String| string)
-        .message("while parsing float")
+        .message("while parsing icao code")
     }
 }
 
@@ -147,7 +244,18 @@ parser!{
 }
 
 parser!{
-    fn waypoint_p[I]()(I) -> UnlinkedWaypoint
+    fn unsigned_p[I]()(I) -> u32
+    where
+        [I: Stream<Item = char>,]
+    {
+        many1(digit())
+        .and_then(|string: String| string.parse::<u32>())
+        .message("while parsing unsigned integer")
+    }
+}
+
+parser!{
+    fn fix_p[I]()(I) -> UnlinkedWaypoint
     where
         [I: Stream<Item = char>,]
     {
@@ -155,345 +263,2152 @@ parser!{
             coord_p(),
             spaces().with(icao_code_p()),
             spaces().with(icao_code_p()),
-            many::<String, _>(char(' ').or(char('\t')).or(alpha_num()))
-        ).map(|(pos, code, apt_code, _) | {
-            if apt_code == "ENRT"
-            {
-                return UnlinkedWaypoint::new(code.clone(), code.clone(), pos, None);
-            }
-            else
-            {
-                return UnlinkedWaypoint::new(code.clone(), code.clone(), pos, Some(apt_code));
-            }
+            spaces().with(icao_code_p()),
+            spaces().with(optional(unsigned_p())),
+            spaces().with(many::<String, _>(any())),
+        ).map(|(pos, code, apt_code, region, type_code, spoken_name)| {
+            let name = match spoken_name.trim() {
+                "" => code.clone(),
+                spoken_name => String::from(spoken_name),
+            };
+
+            let airport = if apt_code == "ENRT" { None } else { Some(apt_code) };
+
+            let mut waypoint = UnlinkedWaypoint::new(code, name, pos, airport);
+            waypoint.region = region;
+            // a type of 0 is written for fixes without one
+            waypoint.waypoint_type = match type_code {
+                Some(0) | None => None,
+                Some(type_code) => Some(decode_waypoint_type(type_code)),
+            };
+            return waypoint;
         })
     }
 }
 
 parser!{
-    fn waypoints_p[I]()(I) -> Vec<UnlinkedWaypoint>
+    fn token_p[I]()(I) -> String
     where
         [I: Stream<Item = char>,]
     {
-        sep_by(waypoint_p(), newline())
+        many1(satisfy(|c: char| !c.is_whitespace()))
+        .message("while parsing token")
     }
 }
 
-impl Database {
-    /// Constructor for `Database`
-    pub fn new(navdata_dir: PathBuf, resources_dir: PathBuf) -> Database {
-        let countries_path = resources_dir.join("icao_countries.txt");
-        let countries_path = countries_path.to_str().unwrap();
+parser!{
+    fn navaid_p[I]()(I) -> Option<(Navaid, Option<String>)>
+    where
+        [I: Stream<Item = char>,]
+    {
+        (
+            unsigned_p(),
+            coord_p(),
+            spaces().with(float_p()),
+            spaces().with(unsigned_p()),
+            spaces().with(float_p()),
+            spaces().with(float_p()),
+            spaces().with(token_p()),
+            spaces().with(token_p()),
+            spaces().with(token_p()),
+            spaces().with(many::<String, _>(any())),
+        ).map(|(row_code, pos, elevation, frequency, range, type_value, code, apt_code,
+                region, rest)| {
+            let rest = rest.trim();
+
+            // navaids associated with a runway have the runway before the name
+            let (runway, name) = match rest.find(char::is_whitespace) {
+                Some(index) if row_code_has_runway(row_code) => {
+                    let (runway, name) = rest.split_at(index);
+                    (Some(String::from(runway)), name.trim())
+                }
+                _ if row_code_has_runway(row_code) => (Some(String::from(rest)), ""),
+                _ => (None, rest),
+            };
+
+            let navaid_type = match NavaidType::from_row_code(row_code, name) {
+                Some(navaid_type) => navaid_type,
+                None => return None,
+            };
+
+            let name = if name.is_empty() { code.clone() } else { String::from(name) };
+
+            let mut navaid = Navaid::new(code, name, pos, navaid_type);
+            navaid.waypoint.region = region;
+            navaid.elevation = elevation;
+            navaid.runway = runway;
+
+            match navaid_type {
+                NavaidType::Ndb => {
+                    navaid.frequency = Some(frequency as f64);
+                    navaid.range = Some(range);
+                }
+                NavaidType::Vor | NavaidType::VorDme | NavaidType::Vortac => {
+                    navaid.frequency = Some((frequency as f64) * 10.0);
+                    navaid.range = Some(range);
+                    navaid.slaved_variation = Some(type_value);
+                }
+                NavaidType::Dme | NavaidType::Tacan => {
+                    navaid.frequency = Some((frequency as f64) * 10.0);
+                    navaid.range = Some(range);
+                    navaid.bias = Some(type_value);
+                }
+                NavaidType::IlsLocalizer | NavaidType::Localizer => {
+                    navaid.frequency = Some((frequency as f64) * 10.0);
+                    navaid.range = Some(range);
+                    navaid.bearing = Some(type_value);
+                }
+                NavaidType::Glideslope => {
+                    navaid.frequency = Some((frequency as f64) * 10.0);
+                    navaid.range = Some(range);
+                    navaid.glide_path_angle = Some(glide_path_angle(type_value));
+                    navaid.bearing = Some(type_value % GLIDE_PATH_ANGLE_FACTOR);
+                }
+                NavaidType::OuterMarker | NavaidType::MiddleMarker | NavaidType::InnerMarker => {
+                    navaid.bearing = Some(type_value);
+                }
+                NavaidType::Fpap => {
+                    navaid.channel = Some(frequency);
+                    navaid.bearing = Some(type_value);
+                }
+                NavaidType::Gls | NavaidType::Threshold => {
+                    navaid.channel = Some(frequency);
+                    navaid.glide_path_angle = Some(glide_path_angle(type_value));
+                    navaid.bearing = Some(type_value % GLIDE_PATH_ANGLE_FACTOR);
+                }
+            }
 
-        let fixes_path = navdata_dir.join("earth_fix.dat");
-        let fixes_path = fixes_path.to_str().unwrap();
+            let airport = if apt_code == "ENRT" { None } else { Some(apt_code) };
 
-        let cycle_info_path = navdata_dir.join("cycle_info.txt");
-        let cycle_info_path = cycle_info_path.to_str().unwrap();
+            return Some((navaid, airport));
+        })
+    }
+}
 
-        let airways_path = navdata_dir.join("ats.txt");
-        let airways_path = airways_path.to_str().unwrap();
+parser!{
+    fn airway_node_p[I]()(I) -> Option<AirwayNodeKey>
+    where
+        [I: Stream<Item = char>,]
+    {
+        (
+            spaces().with(token_p()),
+            spaces().with(token_p()),
+            spaces().with(unsigned_p()),
+        ).map(|(code, region, type_code)| {
+            AirwayNodeType::from_type_code(type_code)
+                .map(|node_type| AirwayNodeKey::new(code, region, node_type))
+        })
+    }
+}
 
-        let mut db = Database {
-            countries: HashMap::new(),
-            airways: HashMap::new(),
-            fixes: Vec::new(),
-            waypoint_hash: MultiHash::new(),
-            cycle_info: read_cycle_info(cycle_info_path),
-        };
+parser!{
+    fn airway_segment_p[I]()(I) -> Option<UnlinkedAirwaySegment>
+    where
+        [I: Stream<Item = char>,]
+    {
+        (
+            airway_node_p(),
+            airway_node_p(),
+            spaces().with(token_p()),
+            spaces().with(unsigned_p()),
+            spaces().with(unsigned_p()),
+            spaces().with(unsigned_p()),
+            spaces().with(token_p()),
+        ).map(|(from, to, direction, class, base, top, names)| {
+            return Some(UnlinkedAirwaySegment {
+                from: match from { Some(from) => from, None => return None },
+                to: match to { Some(to) => to, None => return None },
+                direction: match AirwayDirection::from_code(&direction) {
+                    Some(direction) => direction,
+                    None => return None,
+                },
+                class: match AirwayClass::from_code(class) {
+                    Some(class) => class,
+                    None => return None,
+                },
+                base: base,
+                top: top,
+                names: names.split('-').map(String::from).collect(),
+            });
+        })
+    }
+}
 
-        db.read_countries(countries_path);
-        db.read_fixes(fixes_path);
-        //        db.read_airways(airways_path);
+/// The glide path angle and bearing are stored together in `earth_nav.dat`
+/// as `angle * 100000 + bearing`, e.g. `300180.343` for a 3 degree glide
+/// path with a bearing of 180.343 degrees. The bearing is the remainder
+/// after dividing by `GLIDE_PATH_ANGLE_FACTOR`.
+static GLIDE_PATH_ANGLE_FACTOR: f64 = 1000.0;
 
-        return db;
-    }
+/// Extract the glide path angle (in degrees) from the combined angle and
+/// bearing value used in `earth_nav.dat`.
+fn glide_path_angle(type_value: f64) -> f64 {
+    return (type_value / GLIDE_PATH_ANGLE_FACTOR).floor() / 100.0;
+}
 
-    /// Read Waypoints.txt file from x-plane's gns430 nav data to obtain fixes.
-    /// Needs to be called after read_countries in order to reference the countries.
-    fn read_fixes(&mut self, file_path: &str) {
-        let f = File::open(file_path).expect(&format!("Cannot open file {}", file_path));
+/// Whether the row of `earth_nav.dat` with this row code contains a runway
+/// column before the name.
+fn row_code_has_runway(row_code: u32) -> bool {
+    return (row_code >= 4 && row_code <= 9) || (row_code >= 14 && row_code <= 16);
+}
 
-        let bf = BufReader::new(&f);
+/// Decode the integer used by X-Plane to store the three ARINC 424
+/// waypoint type columns (section 5.42). The first column is stored
+/// in the least significant byte.
+fn decode_waypoint_type(type_code: u32) -> String {
+    return (0..3)
+        .map(|i| ((type_code >> (8 * i)) & 0xFF) as u8 as char)
+        .collect();
+}
 
-        let sample = "33.492513889    9.217400000  07EBA ENRT DT 2118994
- 16.000000000  -30.000000000  1630N ENRT GV 2115145
- 17.000000000  -30.000000000  1730N ENRT GV 2115145
- 18.000000000  -30.000000000  1830N ENRT GV 2115145
- 19.000000000  -30.000000000  1930N ENRT GV 2115145";
+/// Whether a waypoint's country has the given name.
+fn in_country(country: Option<&Arc<Country>>, name: &str) -> bool {
+    return country.map(|country| country.name == name).unwrap_or(false);
+}
 
+/// Versions of `earth_fix.dat` which can be read by `Database`.
+static FIX_VERSIONS: [u32; 3] = [1100, 1101, 1200];
 
-        let result: Result<(Vec<UnlinkedWaypoint>, &str), StreamError<&str>> = waypoints_p()
-            .parse(sample);
+/// Versions of `earth_nav.dat` which can be read by `Database`.
+static NAV_VERSIONS: [u32; 3] = [1100, 1150, 1200];
 
-        println!("{:?}", result.unwrap().0);
+/// Versions of `apt.dat` which can be read by `Database`.
+static APT_VERSIONS: [u32; 5] = [1000, 1050, 1100, 1130, 1200];
 
+/// Versions of `earth_awy.dat` which can be read by `Database`.
+static AWY_VERSIONS: [u32; 2] = [1100, 1200];
 
-        //        for line in bf.lines() {
-        //            let l = line.unwrap();
-        //            let split: Vec<&str> = l.split(",").collect();
-        //            let waypoint_code = split[0].to_string();
-        //            let lat: f64 = split[1].to_string().parse().unwrap();
-        //            let lon: f64 = split[2].to_string().parse().unwrap();
-        //            let pos = SphericalCoordinate::from_geographic(0.0, lat, lon);
-        //
-        //
-        //            // unwrap the option and get a counted reference to the country
-        //            let country = match self.countries.get(split[3]) {
-        //                None => None,
-        //                Some(v) => Some(v.clone()),
-        //            };
-        //
-        //            let waypoint =
-        //                Waypoint::new(waypoint_code.clone(), waypoint_code.clone(), pos, country);
-        //            self.insert_fix(waypoint)
-        //        }
+/// Maximum distance (in meters) between the position given for a waypoint
+/// in the GNS430 `ATS.txt` and the waypoint it refers to.
+const GNS430_MATCH_DISTANCE: f64 = 1000.0;
 
-    }
+/// Conversion factor for the runway dimensions in the GNS430 data.
+const FEET_TO_METERS: f64 = 0.3048;
 
-    /// Read countries in from a txt file.
-    ///
-    /// Basically it just maps ICAO codes to the country names,
-    /// see icao_countries.txt for an example file format.
-    fn read_countries(&mut self, file_path: &str) {
-        let f = File::open(file_path).expect(&format!("Cannot open file {}", file_path));
+/// The format of the navdata files in a navdata directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavdataFormat {
+    /// The `earth_*.dat` files used by x-plane 11 onwards
+    XPlane,
 
-        let bf = BufReader::new(&f);
+    /// The comma separated text files of the GNS430 navdata used by
+    /// x-plane 9 and 10
+    Gns430,
+}
 
-        for line in bf.lines() {
-            let l = line.unwrap();
-            let split: Vec<&str> = l.split("\t").collect();
-            let country_code = split[0].to_string();
-            let country_name = split[1].to_string();
+impl NavdataFormat {
+    /// Detect the format of the navdata in a directory from the files
+    /// present. Returns `None` if the directory contains neither format.
+    ///
+    /// If both formats are present, the x-plane 11 files are preferred.
+    pub fn detect(navdata_dir: &Path) -> Option<NavdataFormat> {
+        if navdata_dir.join("earth_fix.dat").exists() {
+            return Some(NavdataFormat::XPlane);
+        }
 
-            self.countries.insert(
-                country_code.clone(),
-                Rc::new(Country::new(country_code, country_name)),
-            );
+        if navdata_dir.join("Waypoints.txt").exists() {
+            return Some(NavdataFormat::Gns430);
         }
 
+        return None;
     }
 
-    /// Read the airways into the database.
-    /// Needs to be called after read_fixes()
-    fn read_airways(&mut self, file_path: &str) {
-        let f = File::open(file_path).expect(&format!("Cannot open file {}", file_path));
-
-        let bf = BufReader::new(&f);
-
-        let mut new_airway = Route::new(None);
-        let mut first_leg: bool = true; //first leg in airway
-        let mut valid_airway = true; // whether or not all the waypoints are valid
+    /// The navdata files of this format in a directory which are read
+    /// when loading a `Database`. The optional airports file is only
+    /// included if it is present.
+    pub fn source_files(&self, navdata_dir: &Path) -> Vec<PathBuf> {
+        let (names, airports) = match *self {
+            NavdataFormat::XPlane => {
+                (["earth_fix.dat", "earth_nav.dat", "earth_awy.dat"], "apt.dat")
+            }
+            NavdataFormat::Gns430 => {
+                (["Waypoints.txt", "Navaids.txt", "ATS.txt"], "Airports.txt")
+            }
+        };
 
-        for line in bf.lines() {
-            let l = line.unwrap();
+        let mut files: Vec<PathBuf> = names.iter().map(|name| navdata_dir.join(name)).collect();
+        if navdata_dir.join(airports).exists() {
+            files.push(navdata_dir.join(airports));
+        }
 
-            if l == "" {
-                if valid_airway {
-                    // allow us to insert this airway into airways,
-                    // and continue to create new airways without the new_airway
-                    // value getting moved.
-                    let a = Rc::new(mem::replace(&mut new_airway, Route::new(None)));
-                    let s = a.name.clone();
+        return files;
+    }
+}
 
-                    self.airways.insert(s.unwrap(), a);
-                }
+impl fmt::Display for NavdataFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NavdataFormat::XPlane => write!(f, "x-plane earth_*.dat"),
+            NavdataFormat::Gns430 => write!(f, "GNS430 text"),
+        }
+    }
+}
 
-            } else {
-                let split: Vec<&str> = l.split(",").collect();
-                let value_type = split[0].to_string();
+/// How invalid lines in the navdata files are handled while loading a
+/// `Database`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Stop loading, and return a `DatabaseError`
+    Strict,
 
-                if value_type == "A" {
-                    new_airway.name = Some(split[1].to_string());
-                    valid_airway = true;
-                    first_leg = true;
-                }
+    /// Skip the line (and any duplicate lines), and record it in the
+    /// `LoadReport`
+    Lenient,
+}
 
-                if value_type == "S" {
-                    // read first point in the leg, only for the first leg
-                    if first_leg {
-                        let w1_code = split[1].to_string();
-                        let lat1: f64 = split[2].to_string().parse().unwrap();
-                        let lon1: f64 = split[3].to_string().parse().unwrap();
-                        let p1 = SphericalCoordinate::from_geographic(0.0, lat1, lon1);
+/// An error encountered while loading navdata into a `Database`.
+#[derive(Debug)]
+pub enum DatabaseError {
+    /// A file could not be opened or read.
+    Io {
+        /// Path of the file
+        path: String,
+
+        /// The underlying I/O error
+        error: io::Error,
+    },
+
+    /// No navdata in a supported format was found in a directory.
+    NoNavdata {
+        /// Path of the directory
+        path: String,
+    },
+
+    /// A file is in a version of its format which can't be read.
+    UnsupportedVersion {
+        /// Path of the file
+        path: String,
+
+        /// Version of the file format
+        version: u32,
+    },
+
+    /// A line in a file could not be parsed.
+    Parse {
+        /// Path of the file
+        path: String,
+
+        /// Line number in the file (starting at 1)
+        line: usize,
+
+        /// Column in the line (starting at 1) where the error was found,
+        /// if it is known.
+        column: Option<usize>,
+
+        /// The text of the line
+        text: String,
+
+        /// What was wrong with the line
+        message: String,
+    },
 
-                        let result = self.match_waypoint_dist(&w1_code, &p1, 0.1);
-                        if result.is_some() {
-                            new_airway.append_waypoint(result.unwrap().clone());
-                        } else {
-                            valid_airway = false;
-                        }
-                    }
+    /// Loading was cancelled by the progress callback, or by
+    /// `DatabaseLoader::cancel()`.
+    Cancelled,
 
-                    // second point in the leg for every leg except the first one.
-                    let w2_code = split[4].to_string();
-                    let lat2: f64 = split[5].to_string().parse().unwrap();
-                    let lon2: f64 = split[6].to_string().parse().unwrap();
-                    let p2 = SphericalCoordinate::from_geographic(0.0, lat2, lon2);
-
-                    let result = self.match_waypoint_dist(&w2_code, &p2, 0.1);
-                    if result.is_some() {
-                        new_airway.append_waypoint(result.unwrap().clone());
-                    } else {
-                        valid_airway = false;
-                    }
+    /// The thread loading the database in the background panicked.
+    LoaderPanicked,
+}
 
-                    first_leg = false;
+impl fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DatabaseError::Io { ref path, ref error } => {
+                write!(f, "Cannot read file {}: {}", path, error)
+            }
+            DatabaseError::NoNavdata { ref path } => {
+                write!(f, "No navdata found in directory {}", path)
+            }
+            DatabaseError::UnsupportedVersion { ref path, version } => {
+                write!(f, "Unsupported version {} of file {}", version, path)
+            }
+            DatabaseError::Parse {
+                ref path,
+                line,
+                column,
+                ref text,
+                ref message,
+            } => {
+                try!(write!(f, "{}:{}", path, line));
+                if let Some(column) = column {
+                    try!(write!(f, ":{}", column));
                 }
+                write!(f, ": {} in line \"{}\"", message, text)
             }
+            DatabaseError::Cancelled => write!(f, "Loading the navdata was cancelled"),
+            DatabaseError::LoaderPanicked => {
+                write!(f, "The thread loading the navdata panicked")
+            }
+        }
+    }
+}
 
+impl Error for DatabaseError {
+    fn description(&self) -> &str {
+        match *self {
+            DatabaseError::Io { .. } => "cannot read navdata file",
+            DatabaseError::NoNavdata { .. } => "no navdata found",
+            DatabaseError::UnsupportedVersion { .. } => "unsupported navdata file version",
+            DatabaseError::Parse { .. } => "invalid line in navdata file",
+            DatabaseError::Cancelled => "loading navdata cancelled",
+            DatabaseError::LoaderPanicked => "navdata loader thread panicked",
         }
     }
 
-    /// Insert a fix waypoint into this database.
-    pub fn insert_fix(&mut self, waypoint: Waypoint) {
-        let waypoint_ref = Rc::new(waypoint);
-        self.waypoint_hash.insert(
-            waypoint_ref.name.clone(),
-            waypoint_ref.clone(),
-        );
-        self.fixes.push(waypoint_ref);
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            DatabaseError::Io { ref error, .. } => Some(error),
+            _ => None,
+        }
     }
+}
 
-    // TODO add an enumset for waypoint type.
+/// The header at the start of one of x-plane's `.dat` navdata files.
+#[derive(Debug)]
+pub struct DatHeader {
+    /// Version of the file format, e.g. 1100
+    pub version: u32,
 
-    /// Find a waypoint which most closely matches the supplied parameters.
-    /// + code: the icao code for the waypoint
-    /// + position and max_dist: max distance of the waypoint from the given position
-    pub fn match_waypoint_dist(
-        &self,
-        code: &str,
-        position: &SphericalCoordinate,
-        max_dist: f64,
-    ) -> Option<&Rc<Waypoint>> {
-        let matching_waypoints = self.waypoint_hash.get(&String::from(code));
+    /// The rest of the version line, containing the data cycle,
+    /// build and copyright information.
+    pub description: String,
+}
 
-        if matching_waypoints.is_none() {
-            //            println!("Warning: this waypoint does not exist in the
-            // database: {} [{},{}]",
-            //                     code,
-            //                     position.lat(),
-            //                     position.lon());
-            return None;
-        } else {
-            let matching_waypoints: &Vec<Rc<Waypoint>> = matching_waypoints.unwrap();
+/// Number of lines read between calls to the progress callback.
+const PROGRESS_INTERVAL: usize = 1000;
+
+/// A navdata file being read line by line, which keeps track of the line
+/// number so that errors can report where they were found, and collects
+/// the `FileReport` for the file.
+///
+/// If there is a progress callback, it is called when the file is opened,
+/// every `PROGRESS_INTERVAL` lines, and when it is finished with.
+struct NavdataFile<'a> {
+    path: String,
+    reader: BufReader<File>,
+    line_number: usize,
+    bytes_read: u64,
+    total_bytes: u64,
+    mode: LoadMode,
+    report: FileReport,
+    seen: HashSet<String>,
+    progress: Option<&'a mut ProgressCallback<'a>>,
+}
 
-            //            println!("matching waypoings to {}", code);
-            for waypoint in &*matching_waypoints {
-                //                println!("{:?}", waypoint);
+impl<'a> NavdataFile<'a> {
+    /// Open a navdata file for reading.
+    fn open(
+        path: &Path,
+        mode: LoadMode,
+        progress: Option<&'a mut ProgressCallback<'a>>,
+    ) -> Result<NavdataFile<'a>, DatabaseError> {
+        let io_error = |error| {
+            DatabaseError::Io {
+                path: path.display().to_string(),
+                error: error,
             }
+        };
+        let f = try!(File::open(path).map_err(&io_error));
+        let total_bytes = try!(f.metadata().map_err(&io_error)).len();
+
+        let mut file = NavdataFile {
+            path: path.display().to_string(),
+            reader: BufReader::new(f),
+            line_number: 0,
+            bytes_read: 0,
+            total_bytes: total_bytes,
+            mode: mode,
+            report: FileReport::new(path.display().to_string()),
+            seen: HashSet::new(),
+            progress: progress,
+        };
 
-            return Some(&matching_waypoints[0]);
-        }
+        try!(file.report_progress());
+        return Ok(file);
     }
-}
 
+    /// Finish reading the file, and return its `FileReport`. Any lines
+    /// after the end of the data (e.g. after the `99` line of a `.dat`
+    /// file) count as read.
+    fn finish(mut self) -> Result<FileReport, DatabaseError> {
+        self.bytes_read = self.total_bytes;
+        try!(self.report_progress());
+        return Ok(self.report);
+    }
 
-impl fmt::Debug for Database {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        return write!(
-            f,
-            "Database: {{n_fixes: {}, n_airports: {}, n_countries: {}, n_airways: {}}}",
-            self.fixes.len(),
-            0,
-            self.countries.len(),
-            self.airways.len()
-        );
+    /// Call the progress callback, if there is one. Returns an error if
+    /// the callback cancels loading.
+    fn report_progress(&mut self) -> Result<(), DatabaseError> {
+        let progress = LoadProgress {
+            path: self.path.clone(),
+            records: self.report.loaded,
+            bytes_read: self.bytes_read,
+            total_bytes: self.total_bytes,
+        };
+
+        if let Some(ref mut callback) = self.progress {
+            if !callback(&progress) {
+                return Err(DatabaseError::Cancelled);
+            }
+        }
 
+        return Ok(());
     }
-}
 
-/// AIRAC Cycle Info
-///
-/// # Examples
-/// ```rust,no_run
-/// # use std::path::PathBuf;
-/// # use oldnav_lib::navdata::database::{CycleInfo, Database};
-/// let db = Database::new(PathBuf::new(), PathBuf::new());
-/// let cycle_info = &db.cycle_info;
-///
-/// println!("airac cycle number: {}", cycle_info.airac_cycle);
-/// ```
-#[derive(Debug)]
-pub struct CycleInfo {
-    /// The cycle number
-    pub airac_cycle: i32,
-    /// Version of the cycle format?
-    pub version: i32,
-    /// The date this cycle comes into effect (and becomes valid)
-    pub valid_from: DateTime<UTC>,
-    /// The date after which this cycle expires and becomes invalid
-    pub valid_to: DateTime<UTC>,
-    /// Message embedded with the cycle data
-    pub message: String,
-}
+    /// Handle an invalid line. In strict mode the error is returned, and in
+    /// lenient mode the line is recorded as skipped so that loading can
+    /// carry on.
+    fn invalid_line(&mut self, error: DatabaseError) -> Result<(), DatabaseError> {
+        if self.mode == LoadMode::Strict {
+            return Err(error);
+        }
 
-impl CycleInfo {
-    /// Constructor for `CycleInfo`
-    pub fn new(
-        airac_cycle: i32,
-        version: i32,
-        valid_from: DateTime<UTC>,
-        valid_to: DateTime<UTC>,
-        message: String,
-    ) -> CycleInfo {
-        CycleInfo {
-            airac_cycle: airac_cycle,
-            version: version,
-            valid_from: valid_from,
-            valid_to: valid_to,
-            message: message,
+        match error {
+            DatabaseError::Parse { line, text, message, .. } => {
+                self.report.skipped.push(SkippedLine {
+                    line: line,
+                    text: text,
+                    reason: message,
+                });
+                return Ok(());
+            }
+            error => return Err(error),
         }
     }
-}
 
+    /// Handle a line which can't be parsed, see `invalid_line()`.
+    fn skip_line<S: Into<String>>(
+        &mut self,
+        text: &str,
+        column: Option<usize>,
+        message: S,
+    ) -> Result<(), DatabaseError> {
+        let error = self.parse_error(text, column, message);
+        return self.invalid_line(error);
+    }
 
-/// Read cycle info from GNS430 nav database
-fn read_cycle_info(file_path: &str) -> CycleInfo {
-    let f = File::open(file_path).expect(&format!("Cannot open file {}", file_path));
-    let bf = BufReader::new(&f);
-    let mut values: HashMap<String, String> = HashMap::new();
+    /// Whether a line is a duplicate of an earlier line in the file, which
+    /// should be skipped. Duplicates are only checked for in lenient mode.
+    fn is_duplicate(&mut self, text: &str) -> bool {
+        if self.mode == LoadMode::Strict {
+            return false;
+        }
 
-    let mut message: String = String::new();
+        if self.seen.insert(String::from(text.trim())) {
+            return false;
+        }
 
-    for line in bf.lines() {
-        let line_str = line.unwrap();
-        let split: Vec<&str> = line_str.split(":").collect();
+        self.report.skipped.push(SkippedLine {
+            line: self.line_number,
+            text: String::from(text),
+            reason: String::from("Duplicate line"),
+        });
+        return true;
+    }
 
-        if split.len() > 0 {
-            let lhs = split[0].trim();
+    /// Record a reference to a waypoint on the current line which could
+    /// not be found.
+    fn unresolved<S: Into<String>>(&mut self, code: &str, region: Option<&str>, context: S) {
+        self.report.unresolved.push(UnresolvedReference {
+            line: self.line_number,
+            code: String::from(code),
+            region: region.map(String::from),
+            context: context.into(),
+        });
+    }
 
-            if lhs == "AIRAC cycle" {
-                let rhs = split[1].trim();
-                values.insert(String::from("airac_cycle"), String::from(rhs));
-            } else if lhs == "Version" {
-                let rhs = split[1].trim();
-                values.insert(String::from("version"), String::from(rhs));
-            } else if lhs == "Valid (from/to)" {
-                let rhs = split[1];
+    /// Read the next line, returns `None` at the end of the file.
+    fn next_line(&mut self) -> Result<Option<String>, DatabaseError> {
+        let mut line = String::new();
+        let n = try!(self.reader.read_line(&mut line).map_err(|error| {
+            DatabaseError::Io {
+                path: self.path.clone(),
+                error: error,
+            }
+        }));
 
-                let split_dates: Vec<&str> = rhs.split("-").collect();
-                let from_date = split_dates[0].trim();
-                let to_date = split_dates[1].trim();
+        if n == 0 {
+            return Ok(None);
+        }
 
-                values.insert(String::from("from_date"), String::from(from_date));
-                values.insert(String::from("to_date"), String::from(to_date));
-            } else if split.len() == 1 {
-                message.push_str(split[0]);
-                message.push(' ');
-            }
+        self.line_number += 1;
+        self.bytes_read += n as u64;
+        if self.line_number % PROGRESS_INTERVAL == 0 {
+            try!(self.report_progress());
         }
+
+        let len = line.trim_right_matches(|c| c == '\n' || c == '\r').len();
+        line.truncate(len);
+        return Ok(Some(line));
+    }
+
+    /// Create an error for the current line.
+    fn parse_error<S: Into<String>>(
+        &self,
+        text: &str,
+        column: Option<usize>,
+        message: S,
+    ) -> DatabaseError {
+        return DatabaseError::Parse {
+            path: self.path.clone(),
+            line: self.line_number,
+            column: column,
+            text: String::from(text),
+            message: message.into(),
+        };
     }
 
-    let airac_cycle = values.get("airac_cycle").unwrap().parse::<i32>().unwrap();
-    let version = values.get("version").unwrap().parse::<i32>().unwrap();
+    /// Create an error for the current line from a failed parse of it (or
+    /// a slice of it) by one of the combine parsers.
+    fn combine_error(&self, text: &str, error: StreamError<&str>, message: &str) -> DatabaseError {
+        let column = error.position.translate_position(text) + 1;
+        let details: Vec<String> = error.errors.iter().map(|e| e.to_string()).collect();
 
-    let from_date_str = values.get("from_date").unwrap();
-    let from_date = parse_date_str(from_date_str).unwrap();
+        return self.parse_error(
+            text,
+            Some(column),
+            format!("{} ({})", message, details.join(", ")),
+        );
+    }
+
+    /// Read the header of an x-plane `.dat` file, and check that its
+    /// version is one of the supported `versions`.
+    ///
+    /// The first line is an `I` or `A` byte order marker, and the second
+    /// line starts with the file format version.
+    fn read_dat_header(&mut self, versions: &[u32]) -> Result<DatHeader, DatabaseError> {
+        let marker = try!(self.next_non_empty_line()).unwrap_or(String::new());
+        if marker.trim() != "I" && marker.trim() != "A" {
+            return Err(self.parse_error(
+                &marker,
+                Some(1),
+                "Expected I or A at the start of the file",
+            ));
+        }
+
+        let version_line = match try!(self.next_non_empty_line()) {
+            Some(line) => line,
+            None => return Err(self.parse_error("", None, "Missing version line")),
+        };
 
-    let to_date_str = values.get("from_date").unwrap();
-    let to_date = parse_date_str(to_date_str).unwrap();
+        let (version, description) = match version_line.trim().find(' ') {
+            Some(index) => version_line.trim().split_at(index),
+            None => (version_line.trim(), ""),
+        };
+
+        let version = try!(version.parse::<u32>().map_err(|_| {
+            self.parse_error(&version_line, Some(1), "Invalid version")
+        }));
+
+        if !versions.contains(&version) {
+            return Err(DatabaseError::UnsupportedVersion {
+                path: self.path.clone(),
+                version: version,
+            });
+        }
+
+        return Ok(DatHeader {
+            version: version,
+            description: String::from(description.trim()),
+        });
+    }
+
+    /// Read the next line which isn't blank.
+    fn next_non_empty_line(&mut self) -> Result<Option<String>, DatabaseError> {
+        while let Some(line) = try!(self.next_line()) {
+            if !line.trim().is_empty() {
+                return Ok(Some(line));
+            }
+        }
+        return Ok(None);
+    }
+}
+
+impl Database {
+    /// Constructor for `Database`
+    ///
+    /// Detects the format of the navdata in `navdata_dir`, and reads it
+    /// with the matching parser. The detected format is stored in `format`,
+    /// and the AIRAC cycle in `cycle_info`.
+    ///
+    /// Returns an error if a file is missing or can't be read, or a line
+    /// in one of the files can't be parsed. Use `load()` with
+    /// `LoadMode::Lenient` to skip invalid lines instead.
+    pub fn new(navdata_dir: PathBuf, resources_dir: PathBuf) -> Result<Database, DatabaseError> {
+        return Database::load(navdata_dir, resources_dir, LoadMode::Strict);
+    }
+
+    /// Load a `Database` from the navdata in `navdata_dir`, in the given
+    /// `LoadMode`.
+    ///
+    /// The diagnostics for each file read are stored in `load_report`.
+    /// Missing or unreadable files are an error in either mode.
+    ///
+    /// Once the navdata has been parsed it is written to a binary cache in
+    /// `navdata_dir` (see `navdata::cache`), which is used instead of the
+    /// text files as long as they and the AIRAC cycle haven't changed.
+    /// `loaded_from_cache` is set if the cache was used.
+    pub fn load(
+        navdata_dir: PathBuf,
+        resources_dir: PathBuf,
+        mode: LoadMode,
+    ) -> Result<Database, DatabaseError> {
+        return Database::load_with_progress(navdata_dir, resources_dir, mode, &mut |_| true);
+    }
+
+    /// Load a `Database` like `load()`, calling `progress` as each file is
+    /// read (see `LoadProgress`).
+    ///
+    /// Loading is cancelled, and `DatabaseError::Cancelled` returned, if
+    /// `progress` returns `false`. To load a database on another thread
+    /// use a `DatabaseLoader`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use std::path::PathBuf;
+    /// # use oldnav_lib::navdata::database::{Database, LoadMode};
+    /// let db = Database::load_with_progress(
+    ///     PathBuf::from("navdata"),
+    ///     PathBuf::from("resources"),
+    ///     LoadMode::Strict,
+    ///     &mut |progress| {
+    ///         println!("{}: {} records", progress.path, progress.records);
+    ///         true
+    ///     },
+    /// );
+    /// ```
+    pub fn load_with_progress(
+        navdata_dir: PathBuf,
+        resources_dir: PathBuf,
+        mode: LoadMode,
+        progress: &mut ProgressCallback,
+    ) -> Result<Database, DatabaseError> {
+        let format = try!(NavdataFormat::detect(&navdata_dir).ok_or_else(|| {
+            DatabaseError::NoNavdata { path: navdata_dir.display().to_string() }
+        }));
+        let cycle_info = try!(read_cycle_info(&navdata_dir.join("cycle_info.txt")));
+        let countries_path = resources_dir.join("icao_countries.txt");
+
+        // the cache is only an optimisation, so if anything goes wrong with
+        // it the navdata is parsed as if there were no cache.
+        let cache_path = navdata_dir.join(cache::CACHE_FILE_NAME);
+        let mut source_files = format.source_files(&navdata_dir);
+        source_files.push(countries_path.clone());
+        let source_paths: Vec<&Path> = source_files.iter().map(|path| path.as_path()).collect();
+        let key = cache::CacheKey::new(cycle_info.airac_cycle, format, mode, &source_paths).ok();
+
+        if let Some(ref key) = key {
+            let mut db = Database::empty(format, cycle_info.clone(), &navdata_dir, mode);
+            if let Ok(true) = cache::read_cache(&cache_path, key, &mut db) {
+                db.loaded_from_cache = true;
+
+                // the cache is read in one go, so progress is reported once
+                // it has been read.
+                let size = cache_path.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+                let cache_progress = LoadProgress {
+                    path: cache_path.display().to_string(),
+                    records: db.load_report.total_loaded(),
+                    bytes_read: size,
+                    total_bytes: size,
+                };
+                if !progress(&cache_progress) {
+                    return Err(DatabaseError::Cancelled);
+                }
+
+                return Ok(db);
+            }
+        }
+
+        let mut db = Database::empty(format, cycle_info, &navdata_dir, mode);
+        try!(db.read_countries(&countries_path, progress));
+
+        match format {
+            NavdataFormat::XPlane => try!(db.read_xplane(&navdata_dir, progress)),
+            NavdataFormat::Gns430 => try!(db.read_gns430(&navdata_dir, progress)),
+        }
+
+        if let Some(ref key) = key {
+            let _ = cache::write_cache(&cache_path, key, &db);
+        }
+
+        return Ok(db);
+    }
+
+    /// A `Database` with nothing loaded into it yet.
+    fn empty(
+        format: NavdataFormat,
+        cycle_info: CycleInfo,
+        navdata_dir: &Path,
+        mode: LoadMode,
+    ) -> Database {
+        return Database {
+            countries: HashMap::new(),
+            airways: MultiHash::new(),
+            airway_graph: AirwayGraph::new(),
+            fixes: Vec::new(),
+            navaids: Vec::new(),
+            airports: Vec::new(),
+            airport_hash: HashMap::new(),
+            waypoint_hash: MultiHash::new(),
+            spatial_index: SpatialIndex::new(),
+            cycle_info: cycle_info,
+            format: format,
+            navdata_dir: navdata_dir.to_path_buf(),
+            load_mode: mode,
+            load_report: LoadReport::new(),
+            loaded_from_cache: false,
+        };
+    }
+
+    /// Read the x-plane 11 `earth_*.dat` navdata.
+    fn read_xplane(
+        &mut self,
+        navdata_dir: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(), DatabaseError> {
+        let airports_path = navdata_dir.join("apt.dat");
+
+        // apt.dat is part of x-plane's scenery rather than its navdata,
+        // so it may not be present in the navdata directory.
+        if airports_path.exists() {
+            try!(self.read_airports(&airports_path, progress));
+        }
+
+        try!(self.read_fixes(&navdata_dir.join("earth_fix.dat"), progress));
+        try!(self.read_navaids(&navdata_dir.join("earth_nav.dat"), progress));
+        try!(self.read_airways(&navdata_dir.join("earth_awy.dat"), progress));
+        return Ok(());
+    }
+
+    /// Read the GNS430 text navdata.
+    fn read_gns430(
+        &mut self,
+        navdata_dir: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(), DatabaseError> {
+        let airports_path = navdata_dir.join("Airports.txt");
+
+        if airports_path.exists() {
+            try!(self.read_gns430_airports(&airports_path, progress));
+        }
+
+        try!(self.read_gns430_fixes(&navdata_dir.join("Waypoints.txt"), progress));
+        try!(self.read_gns430_navaids(&navdata_dir.join("Navaids.txt"), progress));
+        try!(self.read_gns430_airways(&navdata_dir.join("ATS.txt"), progress));
+        return Ok(());
+    }
+
+    /// Read x-plane's `earth_fix.dat` to obtain fixes.
+    ///
+    /// Supports the 1100, 1101 and 1200 versions of the file format,
+    /// which differ in whether they include the ARINC 424 waypoint type
+    /// and spoken name columns after the ICAO region.
+    fn read_fixes(
+        &mut self,
+        file_path: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(), DatabaseError> {
+        let (fixes, report) = try!(self.parse_fixes(file_path, progress));
+        for fix in fixes {
+            self.insert_fix(fix);
+        }
+
+        self.load_report.files.push(report);
+        return Ok(());
+    }
+
+    /// Read a file in the format of x-plane's `earth_fix.dat`, such as a
+    /// `user_fix.dat`, without inserting its fixes into this database.
+    /// Terminal fixes are linked to the airports in this database.
+    pub fn read_fix_file(
+        &self,
+        file_path: &Path,
+    ) -> Result<(Vec<Waypoint>, FileReport), DatabaseError> {
+        let (mut fixes, report) = try!(self.parse_fixes(file_path, &mut |_| true));
+        for fix in fixes.iter_mut() {
+            fix.country = self.find_country(&fix.region);
+        }
+
+        return Ok((fixes, report));
+    }
+
+    /// Parse the fixes in a file in the format of `earth_fix.dat`.
+    fn parse_fixes(
+        &self,
+        file_path: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(Vec<Waypoint>, FileReport), DatabaseError> {
+        let mut file = try!(NavdataFile::open(file_path, self.load_mode, Some(progress)));
+        try!(file.read_dat_header(&FIX_VERSIONS));
+        let mut fixes = Vec::new();
+
+        while let Some(l) = try!(file.next_line()) {
+            let trimmed = l.trim();
+
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            // end of file marker
+            if trimmed == "99" {
+                break;
+            }
+
+            let result: Result<(UnlinkedWaypoint, &str), StreamError<&str>> =
+                fix_p().parse(trimmed);
+
+            let fix = match result {
+                Ok((fix, _)) => fix,
+                Err(error) => {
+                    let error = file.combine_error(&l, error, "Unable to parse fix");
+                    try!(file.invalid_line(error));
+                    continue;
+                }
+            };
+
+            if file.is_duplicate(&l) {
+                continue;
+            }
+
+            let airport = fix.airport
+                .as_ref()
+                .and_then(|code| self.airport_hash.get(code))
+                .cloned();
+
+            if let Some(ref code) = fix.airport {
+                if airport.is_none() && !self.airports.is_empty() {
+                    file.unresolved(code, None, format!("terminal fix {}", fix.code));
+                }
+            }
+
+            fixes.push(fix.link(airport));
+            file.report.loaded += 1;
+        }
+
+        return Ok((fixes, try!(file.finish())));
+    }
+
+    /// Read x-plane's `earth_nav.dat` to obtain navaids.
+    ///
+    /// Supports the 1100, 1150 and 1200 versions of the file format.
+    /// The DME element of a VOR-DME, VORTAC or ILS is listed separately
+    /// from the VOR or localizer, and is stored as its own `Navaid` with
+    /// the same code.
+    fn read_navaids(
+        &mut self,
+        file_path: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(), DatabaseError> {
+        let (navaids, report) = try!(self.parse_navaids(file_path, progress));
+        for navaid in navaids {
+            self.insert_navaid(navaid);
+        }
+
+        self.load_report.files.push(report);
+        return Ok(());
+    }
+
+    /// Read a file in the format of x-plane's `earth_nav.dat`, such as a
+    /// `user_nav.dat`, without inserting its navaids into this database.
+    /// Navaids are linked to the airports in this database.
+    pub fn read_nav_file(
+        &self,
+        file_path: &Path,
+    ) -> Result<(Vec<Navaid>, FileReport), DatabaseError> {
+        let (mut navaids, report) = try!(self.parse_navaids(file_path, &mut |_| true));
+        for navaid in navaids.iter_mut() {
+            navaid.waypoint.country = self.find_country(&navaid.waypoint.region);
+        }
+
+        return Ok((navaids, report));
+    }
+
+    /// Parse the navaids in a file in the format of `earth_nav.dat`.
+    fn parse_navaids(
+        &self,
+        file_path: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(Vec<Navaid>, FileReport), DatabaseError> {
+        let mut file = try!(NavdataFile::open(file_path, self.load_mode, Some(progress)));
+        try!(file.read_dat_header(&NAV_VERSIONS));
+        let mut navaids = Vec::new();
+
+        while let Some(l) = try!(file.next_line()) {
+            let trimmed = l.trim();
+
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            // end of file marker
+            if trimmed == "99" {
+                break;
+            }
+
+            let result: Result<(Option<(Navaid, Option<String>)>, &str), StreamError<&str>> =
+                navaid_p().parse(trimmed);
+
+            let (mut navaid, airport_code) = match result {
+                Ok((Some(navaid), _)) => navaid,
+                Ok((None, _)) => {
+                    try!(file.skip_line(&l, Some(1), "Unknown navaid type"));
+                    continue;
+                }
+                Err(error) => {
+                    let error = file.combine_error(&l, error, "Unable to parse navaid");
+                    try!(file.invalid_line(error));
+                    continue;
+                }
+            };
+
+            if file.is_duplicate(&l) {
+                continue;
+            }
+
+            navaid.waypoint.airport = airport_code
+                .as_ref()
+                .and_then(|code| self.airport_hash.get(code))
+                .cloned();
+
+            if let Some(ref code) = airport_code {
+                if navaid.waypoint.airport.is_none() && !self.airports.is_empty() {
+                    file.unresolved(code, None, format!("navaid {}", navaid.waypoint.code));
+                }
+            }
+
+            navaids.push(navaid);
+            file.report.loaded += 1;
+        }
+
+        return Ok((navaids, try!(file.finish())));
+    }
+
+    /// Read x-plane's `apt.dat` to obtain the airports.
+    ///
+    /// Needs to be called before `read_fixes()` and `read_navaids()` so that
+    /// their terminal area `Airport`s can be linked. Only the airport header,
+    /// runway, frequency and metadata rows are read, the rest of the rows
+    /// (taxiways, signs, etc.) are ignored.
+    fn read_airports(
+        &mut self,
+        file_path: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(), DatabaseError> {
+        let mut file = try!(NavdataFile::open(file_path, self.load_mode, Some(progress)));
+        try!(file.read_dat_header(&APT_VERSIONS));
+
+        let mut airport: Option<Airport> = None;
+        let mut has_datum = false;
+
+        while let Some(l) = try!(file.next_line()) {
+            let columns: Vec<&str> = l.split_whitespace().collect();
+
+            if columns.is_empty() {
+                continue;
+            }
+
+            let row_code: u32 = match columns[0].parse() {
+                Ok(row_code) => row_code,
+                Err(_) => {
+                    try!(file.skip_line(&l, Some(1), "Invalid row code"));
+                    continue;
+                }
+            };
+
+            // the start of a new airport, or the end of the file
+            if AirportType::from_row_code(row_code).is_some() || row_code == 99 {
+                if let Some(finished) = airport.take() {
+                    self.finish_airport(finished, has_datum);
+                    file.report.loaded += 1;
+                }
+            }
+
+            if row_code == 99 {
+                break;
+            }
+
+            let parsed = match (row_code, airport.as_mut()) {
+                (1, _) | (16, _) | (17, _) => {
+                    airport = Airport::from_header_row(row_code, &columns[1..]);
+                    has_datum = false;
+                    airport.as_ref().map(|_| ())
+                }
+                // rows before the first airport header can be ignored
+                (_, None) => Some(()),
+                (100, Some(airport)) => {
+                    Runway::from_land_row(&columns[1..]).map(|runway| airport.runways.push(runway))
+                }
+                (101, Some(airport)) => {
+                    Runway::from_water_row(&columns[1..]).map(|runway| airport.runways.push(runway))
+                }
+                (102, Some(airport)) => {
+                    Runway::from_helipad_row(&columns[1..]).map(
+                        |runway| airport.runways.push(runway),
+                    )
+                }
+                (_, Some(airport)) if FrequencyType::from_row_code(row_code).is_some() => {
+                    AirportFrequency::from_row(row_code, &columns[1..]).map(|frequency| {
+                        airport.frequencies.push(frequency)
+                    })
+                }
+                (1302, Some(airport)) => {
+                    if columns.len() > 2 && columns[1].starts_with("datum_") {
+                        has_datum = true;
+                    }
+                    airport.apply_metadata_row(&columns[1..])
+                }
+                _ => Some(()),
+            };
+
+            if parsed.is_none() {
+                try!(file.skip_line(&l, None, "Unable to parse airport row"));
+            }
+        }
+
+        // the file may have been truncated before the `99` row
+        if let Some(finished) = airport.take() {
+            self.finish_airport(finished, has_datum);
+            file.report.loaded += 1;
+        }
+
+        self.load_report.files.push(try!(file.finish()));
+        return Ok(());
+    }
+
+    /// Insert an airport once all of its rows have been read from
+    /// `apt.dat`, positioning it between its runways if the file did not
+    /// specify its datum.
+    fn finish_airport(&mut self, mut airport: Airport, has_datum: bool) {
+        if !has_datum {
+            airport.set_position_from_runways();
+        }
+        self.insert_airport(airport);
+    }
+
+    /// Read countries in from a txt file.
+    ///
+    /// Basically it just maps ICAO codes to the country names,
+    /// see icao_countries.txt for an example file format.
+    fn read_countries(
+        &mut self,
+        file_path: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(), DatabaseError> {
+        let mut file = try!(NavdataFile::open(file_path, self.load_mode, Some(progress)));
+
+        while let Some(l) = try!(file.next_line()) {
+            let split: Vec<&str> = l.split("\t").collect();
+            if split.len() < 2 {
+                try!(file.skip_line(&l, None, "Expected a code and name separated by a tab"));
+                continue;
+            }
+
+            let country_code = split[0].to_string();
+            let country_name = split[1].to_string();
+
+            self.countries.insert(
+                country_code.clone(),
+                Arc::new(Country::new(country_code, country_name)),
+            );
+            file.report.loaded += 1;
+        }
+
+        self.load_report.files.push(try!(file.finish()));
+        return Ok(());
+    }
+
+    /// Read x-plane's `earth_awy.dat` to obtain the airways.
+    ///
+    /// Needs to be called after `read_fixes()` and `read_navaids()`, as
+    /// the waypoints at either end of each segment are found by their code,
+    /// ICAO region and type. Segments where either waypoint can't be found
+    /// are skipped, and the missing waypoints recorded in the `load_report`.
+    fn read_airways(
+        &mut self,
+        file_path: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(), DatabaseError> {
+        let mut file = try!(NavdataFile::open(file_path, self.load_mode, Some(progress)));
+        try!(file.read_dat_header(&AWY_VERSIONS));
+
+        let airway_nodes = self.airway_nodes();
+
+        while let Some(l) = try!(file.next_line()) {
+            let trimmed = l.trim();
+
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            // end of file marker
+            if trimmed == "99" {
+                break;
+            }
+
+            let result: Result<(Option<UnlinkedAirwaySegment>, &str), StreamError<&str>> =
+                airway_segment_p().parse(trimmed);
+
+            let segment = match result {
+                Ok((Some(segment), _)) => segment,
+                Ok((None, _)) => {
+                    try!(file.skip_line(&l, None, "Invalid airway segment"));
+                    continue;
+                }
+                Err(error) => {
+                    let error = file.combine_error(&l, error, "Unable to parse airway segment");
+                    try!(file.invalid_line(error));
+                    continue;
+                }
+            };
+
+            if file.is_duplicate(&l) {
+                continue;
+            }
+
+            let context = format!("airway {}", segment.names.join("-"));
+            let from = airway_nodes.get(&segment.from).cloned();
+            let to = airway_nodes.get(&segment.to).cloned();
+
+            if from.is_none() {
+                file.unresolved(
+                    &segment.from.code,
+                    Some(segment.from.region.as_str()),
+                    context.clone(),
+                );
+            }
+            if to.is_none() {
+                file.unresolved(&segment.to.code, Some(segment.to.region.as_str()), context);
+            }
+
+            let (from, to) = match (from, to) {
+                (Some(from), Some(to)) => (from, to),
+                _ => continue,
+            };
+
+            self.insert_airway_segment(segment, from, to);
+            file.report.loaded += 1;
+        }
+
+        for airway in self.airway_graph.build_airways() {
+            self.airways.insert(airway.name.clone(), Arc::new(airway));
+        }
+
+        self.load_report.files.push(try!(file.finish()));
+        return Ok(());
+    }
+
+    /// Read the GNS430 `Waypoints.txt` to obtain fixes.
+    ///
+    /// Each line is `code,latitude,longitude,region`.
+    fn read_gns430_fixes(
+        &mut self,
+        file_path: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(), DatabaseError> {
+        let mut file = try!(NavdataFile::open(file_path, self.load_mode, Some(progress)));
+
+        while let Some(l) = try!(file.next_line()) {
+            if l.trim().is_empty() {
+                continue;
+            }
+
+            let waypoint = match parse_gns430_fix(&l) {
+                Some(waypoint) => waypoint,
+                None => {
+                    try!(file.skip_line(&l, None, "Unable to parse fix"));
+                    continue;
+                }
+            };
+
+            if file.is_duplicate(&l) {
+                continue;
+            }
+
+            self.insert_fix(waypoint);
+            file.report.loaded += 1;
+        }
+
+        self.load_report.files.push(try!(file.finish()));
+        return Ok(());
+    }
+
+    /// Read the GNS430 `Navaids.txt` to obtain navaids.
+    ///
+    /// Each line is `code,name,frequency,vor,dme,range,latitude,longitude,
+    /// elevation,region,...`, where `vor` and `dme` are `1` if the navaid
+    /// has that element. Navaids with neither are NDBs.
+    fn read_gns430_navaids(
+        &mut self,
+        file_path: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(), DatabaseError> {
+        let mut file = try!(NavdataFile::open(file_path, self.load_mode, Some(progress)));
+
+        while let Some(l) = try!(file.next_line()) {
+            if l.trim().is_empty() {
+                continue;
+            }
+
+            let navaid = match parse_gns430_navaid(&l) {
+                Some(navaid) => navaid,
+                None => {
+                    try!(file.skip_line(&l, None, "Unable to parse navaid"));
+                    continue;
+                }
+            };
+
+            if file.is_duplicate(&l) {
+                continue;
+            }
+
+            self.insert_navaid(navaid);
+            file.report.loaded += 1;
+        }
+
+        self.load_report.files.push(try!(file.finish()));
+        return Ok(());
+    }
+
+    /// Read the GNS430 `Airports.txt` to obtain airports.
+    ///
+    /// Each airport starts with an `A` line (`A,code,name,latitude,
+    /// longitude,elevation,transition altitude,transition level,longest
+    /// runway`) followed by an `R` line for each runway end (`R,number,
+    /// heading,length,width,ils,ils frequency,ils heading,latitude,
+    /// longitude,elevation,glideslope angle,threshold crossing height,
+    /// surface,status`).
+    fn read_gns430_airports(
+        &mut self,
+        file_path: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(), DatabaseError> {
+        let mut file = try!(NavdataFile::open(file_path, self.load_mode, Some(progress)));
+
+        let mut airport: Option<Airport> = None;
+
+        while let Some(l) = try!(file.next_line()) {
+            let columns: Vec<&str> = l.split(',').map(|column| column.trim()).collect();
+
+            let parsed = match columns[0] {
+                "" => Some(()),
+                "A" => {
+                    if let Some(finished) = airport.take() {
+                        self.insert_airport(finished);
+                        file.report.loaded += 1;
+                    }
+                    airport = parse_gns430_airport(&columns);
+                    airport.as_ref().map(|_| ())
+                }
+                "R" => {
+                    match airport.as_mut() {
+                        Some(airport) => add_gns430_runway_end(airport, &columns),
+                        None => None,
+                    }
+                }
+                _ => None,
+            };
+
+            if parsed.is_none() {
+                try!(file.skip_line(&l, None, "Unable to parse airport row"));
+            }
+        }
+
+        if let Some(finished) = airport.take() {
+            self.insert_airport(finished);
+            file.report.loaded += 1;
+        }
+
+        self.load_report.files.push(try!(file.finish()));
+        return Ok(());
+    }
+
+    /// Read the GNS430 `ATS.txt` into the `airway_graph`.
+    /// Needs to be called after the fixes and navaids have been read.
+    ///
+    /// Each airway starts with an `A` line (`A,name,number of segments`),
+    /// followed by an `S` line for each segment (`S,from code,latitude,
+    /// longitude,to code,latitude,longitude,inbound course,outbound
+    /// course,distance`). The waypoints are matched by their code and
+    /// position, as the file does not include their regions.
+    fn read_gns430_airways(
+        &mut self,
+        file_path: &Path,
+        progress: &mut ProgressCallback,
+    ) -> Result<(), DatabaseError> {
+        let mut file = try!(NavdataFile::open(file_path, self.load_mode, Some(progress)));
+
+        let mut nodes_by_code: MultiHash<String, (AirwayNodeKey, Arc<WaypointInterface>)> =
+            MultiHash::new();
+        for (key, waypoint) in self.airway_nodes() {
+            nodes_by_code.insert(key.code.clone(), (key, waypoint));
+        }
+
+        let mut airway_name: Option<String> = None;
+
+        while let Some(l) = try!(file.next_line()) {
+            let columns: Vec<&str> = l.split(',').map(|column| column.trim()).collect();
+
+            let name = match (columns[0], airway_name.as_ref()) {
+                ("", _) => continue,
+                ("A", _) if columns.len() > 1 => {
+                    airway_name = Some(String::from(columns[1]));
+                    continue;
+                }
+                ("S", Some(name)) if columns.len() > 6 => name.clone(),
+                _ => {
+                    try!(file.skip_line(&l, None, "Unable to parse airway row"));
+                    continue;
+                }
+            };
+
+            let from_pos = parse_gns430_position(columns[2], columns[3]);
+            let to_pos = parse_gns430_position(columns[5], columns[6]);
+
+            let (from_pos, to_pos) = match (from_pos, to_pos) {
+                (Some(from_pos), Some(to_pos)) => (from_pos, to_pos),
+                _ => {
+                    try!(file.skip_line(&l, None, "Invalid waypoint position"));
+                    continue;
+                }
+            };
+
+            let from = closest_airway_node(&nodes_by_code, columns[1], &from_pos);
+            let to = closest_airway_node(&nodes_by_code, columns[4], &to_pos);
+
+            if from.is_none() {
+                file.unresolved(columns[1], None, format!("airway {}", name));
+            }
+            if to.is_none() {
+                file.unresolved(columns[4], None, format!("airway {}", name));
+            }
+
+            let ((from_key, from), (to_key, to)) = match (from, to) {
+                (Some(from), Some(to)) => (from, to),
+                _ => continue,
+            };
+
+            // the GNS430 data does not distinguish high and low airways,
+            // or give their altitude limits.
+            let segment = UnlinkedAirwaySegment {
+                from: from_key,
+                to: to_key,
+                direction: AirwayDirection::Both,
+                class: AirwayClass::Low,
+                base: 0,
+                top: 999,
+                names: vec![name],
+            };
+
+            self.insert_airway_segment(segment, from, to);
+            file.report.loaded += 1;
+        }
+
+        for airway in self.airway_graph.build_airways() {
+            self.airways.insert(airway.name.clone(), Arc::new(airway));
+        }
+
+        self.load_report.files.push(try!(file.finish()));
+        return Ok(());
+    }
+
+    /// Create a map of all the waypoints which can be at the end of an
+    /// airway segment, with the key used to refer to them in the airway
+    /// data.
+    fn airway_nodes(&self) -> HashMap<AirwayNodeKey, Arc<WaypointInterface>> {
+        let mut nodes: HashMap<AirwayNodeKey, Arc<WaypointInterface>> = HashMap::new();
+
+        for fix in &self.fixes {
+            nodes.insert(
+                AirwayNodeKey::new(fix.code.clone(), fix.region.clone(), AirwayNodeType::Fix),
+                fix.clone(),
+            );
+        }
+
+        // the DME element of a VOR-DME or VORTAC shares its code with the VOR,
+        // so insert the DMEs first to let the VOR take precedence.
+        let mut navaids: Vec<&Arc<Navaid>> = self.navaids.iter().collect();
+        navaids.sort_by_key(|navaid| match navaid.navaid_type {
+            NavaidType::Dme | NavaidType::Tacan => 0,
+            _ => 1,
+        });
+
+        for navaid in navaids {
+            let node_type = match navaid.navaid_type {
+                NavaidType::Ndb => AirwayNodeType::Ndb,
+                NavaidType::Vor | NavaidType::VorDme | NavaidType::Vortac | NavaidType::Dme |
+                NavaidType::Tacan => AirwayNodeType::Vhf,
+                _ => continue,
+            };
+
+            nodes.insert(
+                AirwayNodeKey::new(
+                    navaid.waypoint.code.clone(),
+                    navaid.waypoint.region.clone(),
+                    node_type,
+                ),
+                navaid.clone(),
+            );
+        }
+
+        return nodes;
+    }
+
+    /// Insert an airway segment between two waypoints into the
+    /// `airway_graph`. A segment which belongs to several airways is
+    /// inserted once for each of them.
+    fn insert_airway_segment(
+        &mut self,
+        segment: UnlinkedAirwaySegment,
+        from: Arc<WaypointInterface>,
+        to: Arc<WaypointInterface>,
+    ) {
+        let from = self.airway_graph.add_node(segment.from, from);
+        let to = self.airway_graph.add_node(segment.to, to);
+
+        for name in segment.names {
+            self.airway_graph.add_segment(AirwaySegment {
+                name: name,
+                from: from,
+                to: to,
+                direction: segment.direction,
+                class: segment.class,
+                base: segment.base,
+                top: segment.top,
+            });
+        }
+    }
+
+    /// Insert a fix waypoint into this database.
+    ///
+    /// The fix is linked to the `Country` of its ICAO region, so the
+    /// countries should be read before any fixes are inserted.
+    pub fn insert_fix(&mut self, mut waypoint: Waypoint) {
+        waypoint.country = self.find_country(&waypoint.region);
+        let waypoint_ref = Arc::new(waypoint);
+        self.waypoint_hash.insert(
+            waypoint_ref.code.clone(),
+            waypoint_ref.clone(),
+        );
+        self.spatial_index.insert(waypoint_ref.clone());
+        self.fixes.push(waypoint_ref);
+    }
+
+    /// Insert an airport into this database, linked to the `Country` of
+    /// its ICAO region.
+    pub fn insert_airport(&mut self, mut airport: Airport) {
+        airport.waypoint.country = self.find_country(&airport.waypoint.region);
+        let airport_ref = Arc::new(airport);
+        self.waypoint_hash.insert(
+            airport_ref.waypoint.code.clone(),
+            airport_ref.clone(),
+        );
+        self.airport_hash.insert(
+            airport_ref.waypoint.code.clone(),
+            airport_ref.clone(),
+        );
+        self.spatial_index.insert(airport_ref.clone());
+        self.airports.push(airport_ref);
+    }
+
+    /// Insert a navaid into this database, linked to the `Country` of its
+    /// ICAO region.
+    pub fn insert_navaid(&mut self, mut navaid: Navaid) {
+        navaid.waypoint.country = self.find_country(&navaid.waypoint.region);
+        let navaid_ref = Arc::new(navaid);
+        self.waypoint_hash.insert(
+            navaid_ref.waypoint.code.clone(),
+            navaid_ref.clone(),
+        );
+        self.spatial_index.insert(navaid_ref.clone());
+        self.navaids.push(navaid_ref);
+    }
+
+    /// Get the `Country` of an ICAO region code, such as `"YM"`.
+    pub fn find_country(&self, region: &str) -> Option<Arc<Country>> {
+        return self.countries.get(region).cloned();
+    }
+
+    /// Get the ICAO region codes of a country, by its name. Some countries
+    /// have more than one region, Australia has both `YB` and `YM`.
+    pub fn country_regions(&self, name: &str) -> Vec<&str> {
+        let mut regions: Vec<&str> = self.countries
+            .values()
+            .filter(|country| country.name == name)
+            .map(|country| country.code.as_str())
+            .collect();
+        regions.sort();
+        return regions;
+    }
+
+    /// Read the terminal procedures (SIDs, STARs and approaches) for an
+    /// airport from its CIFP file.
+    ///
+    /// There is one CIFP file per airport, so procedures are read on demand
+    /// rather than when the database is loaded. Returns `None` if there is
+    /// no CIFP file for the airport.
+    pub fn read_procedures(
+        &self,
+        airport_code: &str,
+    ) -> Result<Option<AirportProcedures>, DatabaseError> {
+        let procedures_path = self.navdata_dir.join("CIFP").join(
+            format!("{}.dat", airport_code),
+        );
+
+        if !procedures_path.exists() {
+            return Ok(None);
+        }
+
+        return procedure::read_procedures(airport_code, &procedures_path).map(Some);
+    }
+
+    /// Find the waypoint in this database referred to by a procedure fix.
+    ///
+    /// Airports are matched by their code, other fixes by their code and
+    /// ICAO region, and only if they are of one of the `kinds`. Returns
+    /// `None` for runway fixes (section `P`, subsection `G`), which are not
+    /// waypoints in the database, and for fixes which cannot be found.
+    pub fn resolve_procedure_fix(
+        &self,
+        fix: &ProcedureFix,
+        kinds: WaypointKinds,
+    ) -> Option<Arc<WaypointInterface>> {
+        match (fix.section.as_str(), fix.subsection.as_str()) {
+            ("P", "G") => return None,
+            ("P", "A") => {
+                if let Some(airport) = self.airport_hash.get(&fix.code) {
+                    if kinds.contains(WaypointKind::Airport) {
+                        return Some(airport.clone());
+                    }
+                }
+                return None;
+            }
+            _ => {}
+        }
+
+        return self.waypoint_hash
+            .get(&fix.code)?
+            .iter()
+            .find(|waypoint| waypoint.region() == fix.region && kinds.contains(waypoint.kind()))
+            .cloned();
+    }
+
+    /// Find the waypoints with the given code which are of one of the
+    /// `kinds`, e.g. only NDBs for an ADF tuning page.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use std::path::PathBuf;
+    /// # use oldnav_lib::navdata::database::Database;
+    /// # use oldnav_lib::navdata::kind::WaypointKind;
+    /// let db = Database::new(PathBuf::from("navdata"), PathBuf::from("resources")).unwrap();
+    /// for ndb in db.find_waypoints("APH", WaypointKind::Ndb.into()) {
+    ///     println!("{:?}", ndb);
+    /// }
+    /// ```
+    pub fn find_waypoints(&self, code: &str, kinds: WaypointKinds) -> Vec<Arc<WaypointInterface>> {
+        return match self.waypoint_hash.get(&String::from(code)) {
+            Some(waypoints) => {
+                waypoints
+                    .iter()
+                    .filter(|waypoint| kinds.contains(waypoint.kind()))
+                    .cloned()
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+    }
+
+    /// Get all the waypoints of one of the `kinds` in a country, by its
+    /// name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use std::path::PathBuf;
+    /// # use oldnav_lib::navdata::database::Database;
+    /// # use oldnav_lib::navdata::kind::WaypointKinds;
+    /// let db = Database::new(PathBuf::from("navdata"), PathBuf::from("resources")).unwrap();
+    /// for fix in db.waypoints_in_country("Australia", WaypointKinds::fixes()) {
+    ///     println!("{} ({})", fix.code(), fix.region());
+    /// }
+    /// ```
+    pub fn waypoints_in_country(
+        &self,
+        name: &str,
+        kinds: WaypointKinds,
+    ) -> Vec<Arc<WaypointInterface>> {
+        // only the waypoints which match are cloned
+        let mut waypoints: Vec<Arc<WaypointInterface>> = Vec::new();
+        for fix in &self.fixes {
+            if kinds.contains(fix.kind()) && in_country(fix.country(), name) {
+                waypoints.push(fix.clone());
+            }
+        }
+        for navaid in &self.navaids {
+            if kinds.contains(navaid.kind()) && in_country(navaid.country(), name) {
+                waypoints.push(navaid.clone());
+            }
+        }
+        for airport in &self.airports {
+            if kinds.contains(airport.kind()) && in_country(airport.country(), name) {
+                waypoints.push(airport.clone());
+            }
+        }
+
+        return waypoints;
+    }
+
+    /// Find all the waypoints with the given code which are of one of the
+    /// `kinds`, sorted by their distance from `position`, closest first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use std::path::PathBuf;
+    /// # use oldnav_lib::navdata::database::Database;
+    /// # use oldnav_lib::navdata::coord::{SphericalCoordinate, METERS_PER_NAUTICAL_MILE};
+    /// # use oldnav_lib::navdata::kind::WaypointKinds;
+    /// let db = Database::new(PathBuf::from("navdata"), PathBuf::from("resources")).unwrap();
+    /// let aircraft = SphericalCoordinate::from_geographic(0.0, -37.7, 144.8);
+    /// for candidate in db.waypoint_candidates("WENDY", &aircraft, WaypointKinds::all()) {
+    ///     println!(
+    ///         "{} {} {:?} {:.0}nm",
+    ///         candidate.waypoint.code(),
+    ///         candidate.region,
+    ///         candidate.kind,
+    ///         candidate.distance / METERS_PER_NAUTICAL_MILE
+    ///     );
+    /// }
+    /// ```
+    pub fn waypoint_candidates(
+        &self,
+        code: &str,
+        position: &SphericalCoordinate,
+        kinds: WaypointKinds,
+    ) -> Vec<WaypointCandidate> {
+        let mut candidates: Vec<WaypointCandidate> = self.find_waypoints(code, kinds)
+            .into_iter()
+            .map(|waypoint| {
+                WaypointCandidate {
+                    region: String::from(waypoint.region()),
+                    kind: waypoint.kind(),
+                    distance: waypoint.pos().arc_distance(position),
+                    waypoint: waypoint,
+                }
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| {
+            a.distance.partial_cmp(&b.distance).unwrap_or(Ordering::Equal)
+        });
+        return candidates;
+    }
+
+    /// Find a waypoint which most closely matches the supplied parameters.
+    /// + code: the icao code for the waypoint
+    /// + position and max_dist: max distance (in meters) of the waypoint from
+    ///   the given position
+    /// + kinds: the kinds of waypoint to consider
+    ///
+    /// The closest of the matching waypoints is returned, as `TooFar` if it
+    /// is further than `max_dist` from `position`.
+    pub fn match_waypoint_dist(
+        &self,
+        code: &str,
+        position: &SphericalCoordinate,
+        max_dist: f64,
+        kinds: WaypointKinds,
+    ) -> WaypointQueryResult<Arc<WaypointInterface>> {
+        return match self.waypoint_candidates(code, position, kinds).into_iter().next() {
+            Some(closest) => {
+                if closest.distance <= max_dist {
+                    WaypointQueryResult::Found(closest.waypoint)
+                } else {
+                    WaypointQueryResult::TooFar(closest.waypoint)
+                }
+            }
+            None => WaypointQueryResult::NotFound,
+        };
+    }
+}
+
+
+/// Parse a latitude and longitude column from the GNS430 navdata.
+fn parse_gns430_position(lat: &str, lon: &str) -> Option<SphericalCoordinate> {
+    return Some(SphericalCoordinate::from_geographic(
+        0.0,
+        lat.trim().parse().ok()?,
+        lon.trim().parse().ok()?,
+    ));
+}
+
+/// Parse a line of the GNS430 `Waypoints.txt`.
+fn parse_gns430_fix(line: &str) -> Option<Waypoint> {
+    let columns: Vec<&str> = line.split(',').map(|column| column.trim()).collect();
+    if columns.len() < 4 {
+        return None;
+    }
+
+    let pos = parse_gns430_position(columns[1], columns[2])?;
+    let mut waypoint = Waypoint::new(columns[0], columns[0], pos, None);
+    waypoint.region = String::from(columns[3]);
+    return Some(waypoint);
+}
+
+/// Parse a line of the GNS430 `Navaids.txt`.
+fn parse_gns430_navaid(line: &str) -> Option<Navaid> {
+    let columns: Vec<&str> = line.split(',').map(|column| column.trim()).collect();
+    if columns.len() < 10 {
+        return None;
+    }
+
+    let navaid_type = match (columns[3], columns[4]) {
+        ("0", "0") => NavaidType::Ndb,
+        ("1", "0") => NavaidType::Vor,
+        ("1", "1") => NavaidType::VorDme,
+        ("0", "1") => NavaidType::Dme,
+        _ => return None,
+    };
+
+    let pos = parse_gns430_position(columns[6], columns[7])?;
+    let mut navaid = Navaid::new(columns[0], columns[1], pos, navaid_type);
+
+    // VHF frequencies are in MHz, NDB frequencies in kHz
+    let frequency: f64 = columns[2].parse().ok()?;
+    navaid.frequency = Some(match navaid_type {
+        NavaidType::Ndb => frequency,
+        _ => frequency * 1000.0,
+    });
+    navaid.range = Some(columns[5].parse().ok()?);
+    navaid.elevation = columns[8].parse().ok()?;
+    navaid.waypoint.region = String::from(columns[9]);
+    return Some(navaid);
+}
+
+/// Parse an `A` line of the GNS430 `Airports.txt`.
+fn parse_gns430_airport(columns: &[&str]) -> Option<Airport> {
+    if columns.len() < 8 {
+        return None;
+    }
+
+    let pos = parse_gns430_position(columns[3], columns[4])?;
+    let mut airport = Airport::new(columns[1], columns[2], pos);
+    airport.elevation = columns[5].parse().ok()?;
+    airport.transition_altitude = columns[6].parse().ok().and_then(
+        |altitude| if altitude == 0 { None } else { Some(altitude) },
+    );
+    airport.transition_level = columns[7].parse().ok().and_then(
+        |level| if level == 0 { None } else { Some(level) },
+    );
+    return Some(airport);
+}
+
+/// Add a runway end from an `R` line of the GNS430 `Airports.txt` to an
+/// airport. Each end of a runway has its own line, so an end is added to
+/// the runway of its reciprocal if that has already been read.
+fn add_gns430_runway_end(airport: &mut Airport, columns: &[&str]) -> Option<()> {
+    if columns.len() < 11 {
+        return None;
+    }
+
+    let mut end = RunwayEnd::new(columns[1], parse_gns430_position(columns[8], columns[9])?);
+    end.heading = columns[2].parse().ok()?;
+
+    let reciprocal = reciprocal_runway_number(&end.number);
+    if let Some(runway) = airport.runways.iter_mut().find(|runway| {
+        runway.ends.len() == 1 && Some(&runway.ends[0].number) == reciprocal.as_ref()
+    })
+    {
+        runway.ends.push(end);
+        return Some(());
+    }
+
+    airport.runways.push(Runway {
+        runway_type: RunwayType::Land,
+        width: columns[4].parse::<f64>().ok()? * FEET_TO_METERS,
+        length: columns[3].parse::<f64>().ok()? * FEET_TO_METERS,
+        surface: Surface::Unknown(columns.get(13).and_then(|code| code.parse().ok()).unwrap_or(
+            0,
+        )),
+        centreline_lighting: false,
+        edge_lighting: 0,
+        ends: vec![end],
+    });
+    return Some(());
+}
+
+/// The runway number of the opposite end of a runway, e.g. `34R` for `16L`.
+fn reciprocal_runway_number(number: &str) -> Option<String> {
+    let digits: String = number.chars().take_while(|c| c.is_digit(10)).collect();
+    let heading: u32 = digits.parse().ok()?;
+    let reciprocal = (heading + 17) % 36 + 1;
+
+    let side = match &number[digits.len()..] {
+        "L" => "R",
+        "R" => "L",
+        side => side,
+    };
+
+    return Some(format!("{:02}{}", reciprocal, side));
+}
+
+/// Find the airway node with the given code closest to a position, if it
+/// is within `GNS430_MATCH_DISTANCE`.
+fn closest_airway_node(
+    nodes_by_code: &MultiHash<String, (AirwayNodeKey, Arc<WaypointInterface>)>,
+    code: &str,
+    pos: &SphericalCoordinate,
+) -> Option<(AirwayNodeKey, Arc<WaypointInterface>)> {
+    let mut closest: Option<(f64, &(AirwayNodeKey, Arc<WaypointInterface>))> = None;
+
+    for node in nodes_by_code.get(&String::from(code))? {
+        let distance = node.1.pos().arc_distance(pos);
+        if distance > GNS430_MATCH_DISTANCE {
+            continue;
+        }
+
+        closest = match closest {
+            Some((closest_distance, _)) if closest_distance <= distance => closest,
+            _ => Some((distance, node)),
+        };
+    }
+
+    return closest.map(|(_, node)| node.clone());
+}
+
+impl fmt::Debug for Database {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "Database: {{format: {:?}, airac_cycle: {}, n_fixes: {}, n_navaids: {}, \
+             n_airports: {}, n_countries: {}, n_airway_segments: {}}}",
+            self.format,
+            self.cycle_info.airac_cycle,
+            self.fixes.len(),
+            self.navaids.len(),
+            self.airports.len(),
+            self.countries.len(),
+            self.airway_graph.segments().len()
+        );
+
+    }
+}
+
+/// Describes the format and AIRAC cycle of the loaded navdata.
+impl fmt::Display for Database {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "{} navdata, AIRAC cycle {}",
+            self.format,
+            self.cycle_info.airac_cycle
+        );
+    }
+}
+
+/// AIRAC Cycle Info
+///
+/// # Examples
+/// ```rust,no_run
+/// # extern crate chrono;
+/// # extern crate oldnav_lib;
+/// # use std::path::PathBuf;
+/// # use chrono::UTC;
+/// # use oldnav_lib::navdata::database::{CycleInfo, Database};
+/// # fn main() {
+/// let db = Database::new(PathBuf::new(), PathBuf::new()).unwrap();
+/// let cycle_info = &db.cycle_info;
+///
+/// println!("airac cycle number: {}", cycle_info.airac_cycle);
+///
+/// if cycle_info.is_expired(UTC::now()) {
+///     println!("warning: the navdata has expired");
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CycleInfo {
+    /// The cycle number
+    pub airac_cycle: i32,
+    /// Version of the cycle format?
+    pub version: i32,
+    /// The date this cycle comes into effect (and becomes valid)
+    pub valid_from: DateTime<UTC>,
+    /// The date after which this cycle expires and becomes invalid
+    pub valid_to: DateTime<UTC>,
+    /// Message embedded with the cycle data
+    pub message: String,
+}
+
+impl CycleInfo {
+    /// Constructor for `CycleInfo`
+    pub fn new(
+        airac_cycle: i32,
+        version: i32,
+        valid_from: DateTime<UTC>,
+        valid_to: DateTime<UTC>,
+        message: String,
+    ) -> CycleInfo {
+        CycleInfo {
+            airac_cycle: airac_cycle,
+            version: version,
+            valid_from: valid_from,
+            valid_to: valid_to,
+            message: message,
+        }
+    }
+
+    /// The cycle in the AIRAC calendar with this cycle's number.
+    pub fn cycle(&self) -> Option<AiracCycle> {
+        return AiracCycle::from_ident(self.airac_cycle);
+    }
+
+    /// Whether this cycle is valid at a given time.
+    pub fn is_current(&self, at: DateTime<UTC>) -> bool {
+        return self.valid_from <= at && at < self.valid_to;
+    }
+
+    /// Whether this cycle has expired at a given time.
+    pub fn is_expired(&self, at: DateTime<UTC>) -> bool {
+        return at >= self.valid_to;
+    }
+
+    /// Number of whole days from a given time until this cycle expires,
+    /// negative if it has already expired.
+    pub fn days_remaining(&self, at: DateTime<UTC>) -> i64 {
+        return self.valid_to.signed_duration_since(at).num_days();
+    }
+
+    /// Check the cycle number and validity dates read from the file against
+    /// the AIRAC calendar, returning any differences.
+    pub fn check_calendar(&self) -> Vec<CalendarMismatch> {
+        let cycle = match self.cycle() {
+            Some(cycle) => cycle,
+            None => return vec![CalendarMismatch::UnknownCycle(self.airac_cycle)],
+        };
+
+        let mut mismatches = Vec::new();
+
+        if cycle.effective() != self.valid_from {
+            mismatches.push(CalendarMismatch::ValidFrom {
+                expected: cycle.effective(),
+                found: self.valid_from,
+            });
+        }
+
+        if cycle.expires() != self.valid_to {
+            mismatches.push(CalendarMismatch::ValidTo {
+                expected: cycle.expires(),
+                found: self.valid_to,
+            });
+        }
+
+        return mismatches;
+    }
+}
+
+/// A difference between a `CycleInfo` and the AIRAC calendar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalendarMismatch {
+    /// There is no cycle with this number in the calendar
+    UnknownCycle(i32),
+
+    /// The date the cycle becomes valid is not the calendar's effective date
+    ValidFrom {
+        /// Effective date from the calendar
+        expected: DateTime<UTC>,
+
+        /// Date read from the file
+        found: DateTime<UTC>,
+    },
+
+    /// The date the cycle becomes invalid is not the calendar's expiry date
+    ValidTo {
+        /// Expiry date from the calendar
+        expected: DateTime<UTC>,
+
+        /// Date read from the file
+        found: DateTime<UTC>,
+    },
+}
+
+
+/// Read cycle info from GNS430 nav database
+fn read_cycle_info(file_path: &Path) -> Result<CycleInfo, DatabaseError> {
+    let mut file = try!(NavdataFile::open(file_path, LoadMode::Strict, None));
+    let mut values: HashMap<String, String> = HashMap::new();
+
+    let mut message: String = String::new();
+
+    while let Some(line_str) = try!(file.next_line()) {
+        let split: Vec<&str> = line_str.split(":").collect();
+
+        if split.len() > 0 {
+            let lhs = split[0].trim();
+
+            if lhs == "AIRAC cycle" && split.len() > 1 {
+                let rhs = split[1].trim();
+                values.insert(String::from("airac_cycle"), String::from(rhs));
+            } else if lhs == "Version" && split.len() > 1 {
+                let rhs = split[1].trim();
+                values.insert(String::from("version"), String::from(rhs));
+            } else if lhs == "Valid (from/to)" && split.len() > 1 {
+                let rhs = split[1];
+
+                let split_dates: Vec<&str> = rhs.split("-").collect();
+                if split_dates.len() != 2 {
+                    return Err(file.parse_error(
+                        &line_str,
+                        None,
+                        "Expected two dates separated by -",
+                    ));
+                }
+
+                let from_date = split_dates[0].trim();
+                let to_date = split_dates[1].trim();
+
+                values.insert(String::from("from_date"), String::from(from_date));
+                values.insert(String::from("to_date"), String::from(to_date));
+            } else if split.len() == 1 {
+                message.push_str(split[0]);
+                message.push(' ');
+            }
+        }
+    }
 
-    return CycleInfo::new(airac_cycle, version, from_date, to_date, message);
+    let value = |key: &str, description: &str| -> Result<&String, DatabaseError> {
+        return values.get(key).ok_or_else(|| {
+            file.parse_error("", None, format!("Missing {}", description))
+        });
+    };
+
+    let invalid = |value: &str, description: &str| -> DatabaseError {
+        return file.parse_error(value, None, format!("Invalid {}", description));
+    };
+
+    let airac_cycle_str = try!(value("airac_cycle", "AIRAC cycle"));
+    let airac_cycle = try!(airac_cycle_str.parse::<i32>().map_err(|_| {
+        invalid(airac_cycle_str, "AIRAC cycle")
+    }));
+
+    let version_str = try!(value("version", "version"));
+    let version = try!(version_str.parse::<i32>().map_err(
+        |_| invalid(version_str, "version"),
+    ));
+
+    let from_date_str = try!(value("from_date", "valid from date"));
+    let from_date = try!(parse_date_str(from_date_str).map_err(|_| {
+        invalid(from_date_str, "valid from date")
+    }));
+
+    let to_date_str = try!(value("to_date", "valid to date"));
+    let to_date = try!(parse_date_str(to_date_str).map_err(|_| {
+        invalid(to_date_str, "valid to date")
+    }));
+
+    return Ok(CycleInfo::new(airac_cycle, version, from_date, to_date, message));
 }
 
 
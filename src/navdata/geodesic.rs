@@ -0,0 +1,1082 @@
+//! Geodesics on the WGS84 ellipsoid, or any other ellipsoid of revolution.
+//!
+//! `SphericalCoordinate::arc_distance()` treats the earth as a sphere, which
+//! is out by up to 0.5% of the distance, and loses precision over short
+//! distances. A `Geodesic` solves the inverse problem (the distance and the
+//! azimuths between two positions) and the direct problem (the position at a
+//! distance along an azimuth) on an ellipsoid, accurate to within
+//! nanometres, using the series of C. F. F. Karney, "Algorithms for
+//! geodesics", J. Geodesy 87, 43-55 (2013), as in GeographicLib.
+//!
+//! Positions are a latitude and longitude in degrees, and azimuths are in
+//! degrees clockwise from north, in the range (-180, 180]. See `EarthModel`
+//! to choose between the ellipsoid and the sphere for a `SphericalCoordinate`.
+
+use navdata::coord::{SphericalCoordinate, EARTH_MSL_RADIUS};
+use std::f64;
+use std::f64::consts::PI;
+
+/// Equatorial radius of the WGS84 ellipsoid (in metres)
+pub static WGS84_A: f64 = 6378137.0;
+
+/// Flattening of the WGS84 ellipsoid
+pub static WGS84_F: f64 = 1.0 / 298.257223563;
+
+/// Order of the series in the third flattening
+const ORDER: usize = 6;
+
+/// Number of coefficients of the C3 series, see `Geodesic::c3x`
+const C3_COEFFS: usize = 15;
+
+/// Iterations of Newton's method before falling back to bisection
+const MAXIT1: usize = 20;
+
+/// Total iterations, enough for bisection to converge to machine precision
+const MAXIT2: usize = MAXIT1 + 53 + 10;
+
+/// Which model of the earth to find the distance and azimuths over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EarthModel {
+    /// A sphere with a radius of `EARTH_MSL_RADIUS`
+    Sphere,
+
+    /// The WGS84 ellipsoid
+    Wgs84,
+}
+
+thread_local! {
+    /// The geodesics of each `EarthModel`, which are only built once as
+    /// finding their series coefficients takes as long as a solution.
+    static SPHERE_GEODESIC: Geodesic = Geodesic::new(EARTH_MSL_RADIUS, 0.0);
+    static WGS84_GEODESIC: Geodesic = Geodesic::wgs84();
+}
+
+impl EarthModel {
+    /// The `Geodesic` of this model of the earth.
+    pub fn geodesic(&self) -> Geodesic {
+        return self.with_geodesic(|geodesic| *geodesic);
+    }
+
+    /// Call `f` with the `Geodesic` of this model of the earth, without
+    /// building it again.
+    fn with_geodesic<T, F: FnOnce(&Geodesic) -> T>(&self, f: F) -> T {
+        return match *self {
+            EarthModel::Sphere => SPHERE_GEODESIC.with(f),
+            EarthModel::Wgs84 => WGS84_GEODESIC.with(f),
+        };
+    }
+
+    /// Solve the inverse problem between two positions along the surface,
+    /// ignoring their altitudes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::coord::SphericalCoordinate;
+    /// # use oldnav_lib::navdata::geodesic::EarthModel;
+    /// let jfk = SphericalCoordinate::from_geographic(0.0, 40.6, -73.8);
+    /// let lhr = SphericalCoordinate::from_geographic(0.0, 51.6, -0.5);
+    ///
+    /// let ellipsoid = EarthModel::Wgs84.inverse(&jfk, &lhr);
+    /// let sphere = EarthModel::Sphere.inverse(&jfk, &lhr);
+    /// assert!((ellipsoid.distance - 5551759.4003).abs() < 0.001);
+    /// assert!((sphere.distance - ellipsoid.distance).abs() < 20000.0);
+    /// ```
+    pub fn inverse(
+        &self,
+        from: &SphericalCoordinate,
+        to: &SphericalCoordinate,
+    ) -> GeodesicInverse {
+        return self.with_geodesic(|geodesic| {
+            geodesic.inverse(from.lat(), from.lon(), to.lat(), to.lon())
+        });
+    }
+
+    /// Solve the direct problem from a position along the surface, ignoring
+    /// its altitude.
+    pub fn direct(
+        &self,
+        from: &SphericalCoordinate,
+        azimuth: f64,
+        distance: f64,
+    ) -> GeodesicDirect {
+        return self.with_geodesic(|geodesic| {
+            geodesic.direct(from.lat(), from.lon(), azimuth, distance)
+        });
+    }
+}
+
+/// The solution of the inverse geodesic problem.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeodesicInverse {
+    /// length of the geodesic (in metres)
+    pub distance: f64,
+
+    /// azimuth of the geodesic at the first position (in degrees)
+    pub initial_azimuth: f64,
+
+    /// azimuth of the geodesic at the second position (in degrees)
+    pub final_azimuth: f64,
+}
+
+/// The solution of the direct geodesic problem.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeodesicDirect {
+    /// latitude of the second position (in degrees)
+    pub lat: f64,
+
+    /// longitude of the second position (in degrees)
+    pub lon: f64,
+
+    /// azimuth of the geodesic at the second position (in degrees)
+    pub final_azimuth: f64,
+}
+
+/// The geodesics of an ellipsoid of revolution.
+///
+/// # Examples
+///
+/// ```
+/// # use oldnav_lib::navdata::geodesic::Geodesic;
+/// let wgs84 = Geodesic::wgs84();
+///
+/// let inverse = wgs84.inverse(-37.67, 144.84, 51.47, -0.45);
+/// let direct = wgs84.direct(-37.67, 144.84, inverse.initial_azimuth, inverse.distance);
+/// assert!((direct.lat - 51.47).abs() < 1e-12);
+/// assert!((direct.lon + 0.45).abs() < 1e-12);
+/// assert!((direct.final_azimuth - inverse.final_azimuth).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Geodesic {
+    /// equatorial radius
+    a: f64,
+    /// flattening
+    f: f64,
+    /// 1 - f
+    f1: f64,
+    /// second eccentricity squared
+    ep2: f64,
+    /// third flattening
+    n: f64,
+    /// polar semi-axis
+    b: f64,
+    /// threshold for the short line approximation of `inverse_start()`
+    etol2: f64,
+    /// coefficients of the A3 series in epsilon
+    a3x: [f64; ORDER],
+    /// coefficients of the C3 series in epsilon
+    c3x: [f64; C3_COEFFS],
+}
+
+/// The state of the geodesic from a solution of `Geodesic::lambda12()`.
+struct Lambda12 {
+    lam12: f64,
+    salp2: f64,
+    calp2: f64,
+    sig12: f64,
+    ssig1: f64,
+    csig1: f64,
+    ssig2: f64,
+    csig2: f64,
+    eps: f64,
+    dlam12: f64,
+}
+
+impl Geodesic {
+    /// Constructor for `Geodesic`, of an ellipsoid with an equatorial radius
+    /// `a` (in metres) and a flattening `f`. A flattening of 0 is a sphere.
+    ///
+    /// Only oblate ellipsoids are supported, `f` must be in the range
+    /// [0, 1).
+    ///
+    /// # Panics
+    ///
+    /// If `a` isn't positive, or `f` is outside of the range [0, 1).
+    ///
+    /// ```should_panic
+    /// # use oldnav_lib::navdata::geodesic::Geodesic;
+    /// let prolate = Geodesic::new(6378137.0, -0.01);
+    /// ```
+    pub fn new(a: f64, f: f64) -> Geodesic {
+        assert!(a > 0.0, "equatorial radius must be positive");
+        assert!(f >= 0.0 && f < 1.0, "flattening must be in the range [0, 1)");
+
+        let f1 = 1.0 - f;
+        let e2 = f * (2.0 - f);
+        let n = f / (2.0 - f);
+        let tol2 = f64::EPSILON.sqrt();
+
+        let mut geodesic = Geodesic {
+            a: a,
+            f: f,
+            f1: f1,
+            ep2: e2 / sq(f1),
+            n: n,
+            b: a * f1,
+            etol2: 0.1 * tol2 / (f.max(0.001) * (1.0 - f / 2.0).min(1.0) / 2.0).sqrt(),
+            a3x: [0.0; ORDER],
+            c3x: [0.0; C3_COEFFS],
+        };
+        geodesic.a3_coeff();
+        geodesic.c3_coeff();
+        return geodesic;
+    }
+
+    /// The `Geodesic` of the WGS84 ellipsoid.
+    pub fn wgs84() -> Geodesic {
+        return Geodesic::new(WGS84_A, WGS84_F);
+    }
+
+    /// Equatorial radius of the ellipsoid (in metres)
+    pub fn equatorial_radius(&self) -> f64 {
+        return self.a;
+    }
+
+    /// Flattening of the ellipsoid
+    pub fn flattening(&self) -> f64 {
+        return self.f;
+    }
+
+    /// Solve the direct problem: the position at a `distance` (in metres)
+    /// from `lat1`, `lon1` along a geodesic with an initial `azimuth`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::geodesic::Geodesic;
+    /// let direct = Geodesic::wgs84().direct(40.0, 0.0, 30.0, 10000000.0);
+    /// assert!((direct.lat - 41.79331020506).abs() < 1e-11);
+    /// assert!((direct.lon - 137.84490004377).abs() < 1e-11);
+    /// assert!((direct.final_azimuth - 149.09016931807).abs() < 1e-11);
+    /// ```
+    pub fn direct(&self, lat1: f64, lon1: f64, azimuth: f64, distance: f64) -> GeodesicDirect {
+        let azi1 = ang_normalize(azimuth);
+        let (salp1, calp1) = sincosd(ang_round(azi1));
+
+        let (sbet1, cbet1) = sincosd(ang_round(lat_fix(lat1)));
+        let (sbet1, cbet1) = norm(sbet1 * self.f1, cbet1);
+        let cbet1 = cbet1.max(tiny());
+
+        let salp0 = salp1 * cbet1;
+        let calp0 = calp1.hypot(salp1 * sbet1);
+
+        let somg1 = salp0 * sbet1;
+        let comg1 = if sbet1 != 0.0 || calp1 != 0.0 {
+            cbet1 * calp1
+        } else {
+            1.0
+        };
+        let (ssig1, csig1) = norm(sbet1, comg1);
+
+        let k2 = sq(calp0) * self.ep2;
+        let eps = k2 / (2.0 * (1.0 + (1.0 + k2).sqrt()) + k2);
+
+        let a1m1 = a1m1f(eps);
+        let mut c1a = [0.0; ORDER + 1];
+        c1f(eps, &mut c1a);
+        let b11 = sin_cos_series(true, ssig1, csig1, &c1a);
+        let (s, c) = (b11.sin(), b11.cos());
+        let stau1 = ssig1 * c + csig1 * s;
+        let ctau1 = csig1 * c - ssig1 * s;
+
+        let mut c1pa = [0.0; ORDER + 1];
+        c1pf(eps, &mut c1pa);
+
+        let a3c = -self.f * salp0 * self.a3f(eps);
+        let mut c3a = [0.0; ORDER];
+        self.c3f(eps, &mut c3a);
+        let b31 = sin_cos_series(true, ssig1, csig1, &c3a);
+
+        // invert the distance along the auxiliary sphere, tau12, for sigma12
+        let tau12 = distance / (self.b * (1.0 + a1m1));
+        let (s, c) = (tau12.sin(), tau12.cos());
+        let b12 = -sin_cos_series(true, stau1 * c + ctau1 * s, ctau1 * c - stau1 * s, &c1pa);
+        let mut sig12 = tau12 - (b12 - b11);
+        let (mut ssig12, mut csig12) = (sig12.sin(), sig12.cos());
+
+        if self.f > 0.01 {
+            // the series inversion is inaccurate for large flattenings, so
+            // take one step of Newton's method
+            let ssig2 = ssig1 * csig12 + csig1 * ssig12;
+            let csig2 = csig1 * csig12 - ssig1 * ssig12;
+            let b12 = sin_cos_series(true, ssig2, csig2, &c1a);
+            let serr = (1.0 + a1m1) * (sig12 + (b12 - b11)) - distance / self.b;
+            sig12 = sig12 - serr / (1.0 + k2 * sq(ssig2)).sqrt();
+            ssig12 = sig12.sin();
+            csig12 = sig12.cos();
+        }
+
+        let ssig2 = ssig1 * csig12 + csig1 * ssig12;
+        let mut csig2 = csig1 * csig12 - ssig1 * ssig12;
+        let sbet2 = calp0 * ssig2;
+        let mut cbet2 = salp0.hypot(calp0 * csig2);
+        if cbet2 == 0.0 {
+            // the geodesic ends at a pole
+            cbet2 = tiny();
+            csig2 = tiny();
+        }
+        let salp2 = salp0;
+        let calp2 = calp0 * csig2;
+
+        let somg2 = salp0 * ssig2;
+        let comg2 = csig2;
+        let omg12 = (somg2 * comg1 - comg2 * somg1).atan2(comg2 * comg1 + somg2 * somg1);
+        let lam12 = omg12 + a3c * (sig12 + (sin_cos_series(true, ssig2, csig2, &c3a) - b31));
+        let lon12 = lam12.to_degrees();
+
+        return GeodesicDirect {
+            lat: atan2d(sbet2, self.f1 * cbet2),
+            lon: ang_normalize(ang_normalize(lon1) + ang_normalize(lon12)),
+            final_azimuth: atan2d(salp2, calp2),
+        };
+    }
+
+    /// Solve the inverse problem: the length and azimuths of the shortest
+    /// geodesic between `lat1`, `lon1` and `lat2`, `lon2`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::geodesic::Geodesic;
+    /// let inverse = Geodesic::wgs84().inverse(40.6, -73.8, 51.6, -0.5);
+    /// assert!((inverse.distance - 5551759.400319).abs() < 1e-6);
+    /// assert!((inverse.initial_azimuth - 51.198882845).abs() < 1e-9);
+    /// assert!((inverse.final_azimuth - 107.821776735).abs() < 1e-9);
+    /// ```
+    pub fn inverse(&self, lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> GeodesicInverse {
+        let (lon12, lon12s) = ang_diff(lon1, lon2);
+
+        // make the longitude difference positive
+        let mut lonsign = if lon12 >= 0.0 { 1.0 } else { -1.0 };
+        let lon12 = lonsign * ang_round(lon12);
+        let lon12s = ang_round((180.0 - lon12) - lonsign * lon12s);
+        let lam12 = lon12.to_radians();
+        let (slam12, clam12) = if lon12 > 90.0 {
+            let (s, c) = sincosd(lon12s);
+            (s, -c)
+        } else {
+            sincosd(lon12)
+        };
+
+        // swap the positions so that the first is furthest from the equator,
+        // and make its latitude negative
+        let mut lat1 = ang_round(lat_fix(lat1));
+        let mut lat2 = ang_round(lat_fix(lat2));
+        let swapp = if lat1.abs() < lat2.abs() { -1.0 } else { 1.0 };
+        if swapp < 0.0 {
+            lonsign = -lonsign;
+            let t = lat1;
+            lat1 = lat2;
+            lat2 = t;
+        }
+        let latsign = if lat1 < 0.0 { 1.0 } else { -1.0 };
+        lat1 *= latsign;
+        lat2 *= latsign;
+
+        let (sbet1, cbet1) = sincosd(lat1);
+        let (sbet1, cbet1) = norm(sbet1 * self.f1, cbet1);
+        let cbet1 = cbet1.max(tiny());
+        let (sbet2, cbet2) = sincosd(lat2);
+        let (mut sbet2, cbet2) = norm(sbet2 * self.f1, cbet2);
+        let mut cbet2 = cbet2.max(tiny());
+
+        // make the latitudes exactly equal in magnitude if they round to it
+        if cbet1 < -sbet1 {
+            if cbet2 == cbet1 {
+                sbet2 = if sbet2 < 0.0 { sbet1 } else { -sbet1 };
+            }
+        } else if sbet2.abs() == -sbet1 {
+            cbet2 = cbet1;
+        }
+
+        let dn1 = (1.0 + self.ep2 * sq(sbet1)).sqrt();
+        let dn2 = (1.0 + self.ep2 * sq(sbet2)).sqrt();
+
+        let (mut salp1, mut calp1, mut salp2, mut calp2) = (0.0, 0.0, 0.0, 0.0);
+        let mut s12x = 0.0;
+
+        let mut meridian = lat1 == -90.0 || slam12 == 0.0;
+        if meridian {
+            // the geodesic runs along a meridian, unless it is not the
+            // shortest path, when it passes around a pole
+            calp1 = clam12;
+            salp1 = slam12;
+            calp2 = 1.0;
+            salp2 = 0.0;
+
+            let ssig1 = sbet1;
+            let csig1 = calp1 * cbet1;
+            let ssig2 = sbet2;
+            let csig2 = calp2 * cbet2;
+            let sig12 = (csig1 * ssig2 - ssig1 * csig2)
+                .max(0.0)
+                .atan2(csig1 * csig2 + ssig1 * ssig2);
+
+            let (s12, m12) = lengths(self.n, sig12, (ssig1, csig1, dn1), (ssig2, csig2, dn2));
+            if sig12 < 1.0 || m12 >= 0.0 {
+                if sig12 < 3.0 * tiny() || (sig12 < f64::EPSILON && (s12 < 0.0 || m12 < 0.0)) {
+                    // the positions are coincident
+                    s12x = 0.0;
+                } else {
+                    s12x = s12 * self.b;
+                }
+            } else {
+                meridian = false;
+            }
+        }
+
+        if !meridian && sbet1 == 0.0 && lon12s >= self.f * 180.0 {
+            // the geodesic runs along the equator
+            calp1 = 0.0;
+            calp2 = 0.0;
+            salp1 = 1.0;
+            salp2 = 1.0;
+            s12x = self.a * lam12;
+        } else if !meridian {
+            let start = self.inverse_start(
+                (sbet1, cbet1),
+                (sbet2, cbet2),
+                lam12,
+                (slam12, clam12),
+            );
+            salp1 = start.salp1;
+            calp1 = start.calp1;
+
+            if start.sig12 >= 0.0 {
+                // a short line, solved by inverse_start()
+                salp2 = start.salp2;
+                calp2 = start.calp2;
+                s12x = start.sig12 * self.b * start.dnm;
+            } else {
+                // find the azimuth which reaches the longitude, by Newton's
+                // method within a bracket, falling back on bisection
+                let mut numit = 0;
+                let mut tripn = false;
+                let mut tripb = false;
+                let (mut salp1a, mut calp1a) = (tiny(), 1.0);
+                let (mut salp1b, mut calp1b) = (tiny(), -1.0);
+
+                let mut solution;
+                loop {
+                    solution = self.lambda12(
+                        (sbet1, cbet1, dn1),
+                        (sbet2, cbet2, dn2),
+                        (salp1, calp1),
+                        (slam12, clam12),
+                        numit < MAXIT1,
+                    );
+                    let v = solution.lam12;
+
+                    let tol = (if tripn { 8.0 } else { 1.0 }) * f64::EPSILON;
+                    if numit >= MAXIT2 || tripb || !(v.abs() >= tol) {
+                        break;
+                    }
+
+                    // update the bracket
+                    if v > 0.0 && (numit > MAXIT1 || calp1 / salp1 > calp1b / salp1b) {
+                        salp1b = salp1;
+                        calp1b = calp1;
+                    } else if v < 0.0 && (numit > MAXIT1 || calp1 / salp1 < calp1a / salp1a) {
+                        salp1a = salp1;
+                        calp1a = calp1;
+                    }
+
+                    numit += 1;
+                    let dv = solution.dlam12;
+                    if numit < MAXIT1 && dv > 0.0 {
+                        let dalp1 = -v / dv;
+                        if dalp1.abs() < PI {
+                            let (sdalp1, cdalp1) = (dalp1.sin(), dalp1.cos());
+                            let nsalp1 = salp1 * cdalp1 + calp1 * sdalp1;
+                            if nsalp1 > 0.0 {
+                                let (s, c) = norm(nsalp1, calp1 * cdalp1 - salp1 * sdalp1);
+                                salp1 = s;
+                                calp1 = c;
+                                tripn = v.abs() <= 16.0 * f64::EPSILON;
+                                continue;
+                            }
+                        }
+                    }
+
+                    // Newton's method overshot, so bisect the bracket
+                    let (s, c) = norm((salp1a + salp1b) / 2.0, (calp1a + calp1b) / 2.0);
+                    salp1 = s;
+                    calp1 = c;
+                    tripn = false;
+                    let tolb = f64::EPSILON * f64::EPSILON.sqrt();
+                    tripb = (salp1a - salp1).abs() + (calp1a - calp1) < tolb ||
+                        (salp1 - salp1b).abs() + (calp1 - calp1b) < tolb;
+                }
+
+                let (s12, _) = lengths(
+                    solution.eps,
+                    solution.sig12,
+                    (solution.ssig1, solution.csig1, dn1),
+                    (solution.ssig2, solution.csig2, dn2),
+                );
+                s12x = s12 * self.b;
+                salp2 = solution.salp2;
+                calp2 = solution.calp2;
+            }
+        }
+
+        // undo the swap and the sign changes
+        if swapp < 0.0 {
+            let (s, c) = (salp1, calp1);
+            salp1 = salp2;
+            calp1 = calp2;
+            salp2 = s;
+            calp2 = c;
+        }
+        salp1 *= swapp * lonsign;
+        calp1 *= swapp * latsign;
+        salp2 *= swapp * lonsign;
+        calp2 *= swapp * latsign;
+
+        return GeodesicInverse {
+            distance: 0.0 + s12x,
+            initial_azimuth: atan2d(salp1, calp1),
+            final_azimuth: atan2d(salp2, calp2),
+        };
+    }
+
+    /// Compute the coefficients of the A3 series, as polynomials in `n`.
+    fn a3_coeff(&mut self) {
+        static COEFF: [f64; 18] = [
+            -3.0, 128.0,
+            -2.0, -3.0, 64.0,
+            -1.0, -3.0, -1.0, 16.0,
+            3.0, -1.0, -2.0, 8.0,
+            1.0, -1.0, 2.0,
+            1.0, 1.0,
+        ];
+
+        let mut o = 0;
+        for (k, j) in (0..ORDER).rev().enumerate() {
+            let m = (ORDER - j - 1).min(j);
+            self.a3x[k] = polyval(m, &COEFF[o..], self.n) / COEFF[o + m + 1];
+            o += m + 2;
+        }
+    }
+
+    /// Compute the coefficients of the C3 series, as polynomials in `n`.
+    fn c3_coeff(&mut self) {
+        static COEFF: [f64; 45] = [
+            3.0, 128.0,
+            2.0, 5.0, 128.0,
+            -1.0, 3.0, 3.0, 64.0,
+            -1.0, 0.0, 1.0, 8.0,
+            -1.0, 1.0, 4.0,
+            5.0, 256.0,
+            1.0, 3.0, 128.0,
+            -3.0, -2.0, 3.0, 64.0,
+            1.0, -3.0, 2.0, 32.0,
+            7.0, 512.0,
+            -10.0, 9.0, 384.0,
+            5.0, -9.0, 5.0, 192.0,
+            7.0, 512.0,
+            -14.0, 7.0, 512.0,
+            21.0, 2560.0,
+        ];
+
+        let mut o = 0;
+        let mut k = 0;
+        for l in 1..ORDER {
+            for j in (l..ORDER).rev() {
+                let m = (ORDER - j - 1).min(j);
+                self.c3x[k] = polyval(m, &COEFF[o..], self.n) / COEFF[o + m + 1];
+                k += 1;
+                o += m + 2;
+            }
+        }
+    }
+
+    /// The A3 series, scaling the longitude difference along a geodesic.
+    fn a3f(&self, eps: f64) -> f64 {
+        return polyval(ORDER - 1, &self.a3x, eps);
+    }
+
+    /// The coefficients of the C3 series, `c[1]` to `c[ORDER - 1]`.
+    fn c3f(&self, eps: f64, c: &mut [f64; ORDER]) {
+        let mut mult = 1.0;
+        let mut o = 0;
+        for l in 1..ORDER {
+            let m = ORDER - l - 1;
+            mult *= eps;
+            c[l] = mult * polyval(m, &self.c3x[o..], eps);
+            o += m + 1;
+        }
+    }
+
+    /// A first guess of the initial azimuth of the inverse problem, which
+    /// solves it outright for short lines.
+    fn inverse_start(
+        &self,
+        beta1: (f64, f64),
+        beta2: (f64, f64),
+        lam12: f64,
+        slam12: (f64, f64),
+    ) -> InverseStart {
+        let (sbet1, cbet1) = beta1;
+        let (sbet2, cbet2) = beta2;
+        let (slam12, clam12) = slam12;
+
+        let mut start = InverseStart {
+            sig12: -1.0,
+            salp1: 0.0,
+            calp1: 0.0,
+            salp2: f64::NAN,
+            calp2: f64::NAN,
+            dnm: f64::NAN,
+        };
+
+        let sbet12 = sbet2 * cbet1 - cbet2 * sbet1;
+        let cbet12 = cbet2 * cbet1 + sbet2 * sbet1;
+        let sbet12a = sbet2 * cbet1 + cbet2 * sbet1;
+
+        let shortline = cbet12 >= 0.0 && sbet12 < 0.5 && cbet2 * lam12 < 0.5;
+        let (mut somg12, mut comg12);
+        if shortline {
+            let mut sbetm2 = sq(sbet1 + sbet2);
+            sbetm2 /= sbetm2 + sq(cbet1 + cbet2);
+            start.dnm = (1.0 + self.ep2 * sbetm2).sqrt();
+            let omg12 = lam12 / (self.f1 * start.dnm);
+            somg12 = omg12.sin();
+            comg12 = omg12.cos();
+        } else {
+            somg12 = slam12;
+            comg12 = clam12;
+        }
+
+        start.salp1 = cbet2 * somg12;
+        start.calp1 = if comg12 >= 0.0 {
+            sbet12 + cbet2 * sbet1 * sq(somg12) / (1.0 + comg12)
+        } else {
+            sbet12a - cbet2 * sbet1 * sq(somg12) / (1.0 - comg12)
+        };
+
+        let ssig12 = start.salp1.hypot(start.calp1);
+        let csig12 = sbet1 * sbet2 + cbet1 * cbet2 * comg12;
+
+        if shortline && ssig12 < self.etol2 {
+            // really short lines
+            let salp2 = cbet1 * somg12;
+            let calp2 = sbet12 -
+                cbet1 * sbet2 *
+                    (if comg12 >= 0.0 {
+                        sq(somg12) / (1.0 + comg12)
+                    } else {
+                        1.0 - comg12
+                    });
+            let (s, c) = norm(salp2, calp2);
+            start.salp2 = s;
+            start.calp2 = c;
+            start.sig12 = ssig12.atan2(csig12);
+        } else if self.n.abs() >= 0.1 || csig12 >= 0.0 ||
+                   ssig12 >= 6.0 * self.n.abs() * PI * sq(cbet1)
+        {
+            // the guess from the auxiliary sphere is good enough
+        } else {
+            // nearly antipodal positions, so solve the astroid problem
+            let lam12x = (-slam12).atan2(-clam12);
+            let k2 = sq(sbet1) * self.ep2;
+            let eps = k2 / (2.0 * (1.0 + (1.0 + k2).sqrt()) + k2);
+            let lamscale = self.f * cbet1 * self.a3f(eps) * PI;
+            let betscale = lamscale * cbet1;
+            let x = lam12x / lamscale;
+            let y = sbet12a / betscale;
+
+            if y > -200.0 * f64::EPSILON && x > -1.0 - 1000.0 * f64::EPSILON.sqrt() {
+                start.salp1 = (-x).min(1.0);
+                start.calp1 = -(1.0 - sq(start.salp1)).sqrt();
+            } else {
+                let k = astroid(x, y);
+                let omg12a = lamscale * (-x * k / (1.0 + k));
+                somg12 = omg12a.sin();
+                comg12 = -omg12a.cos();
+                start.salp1 = cbet2 * somg12;
+                start.calp1 = sbet12a - cbet2 * sbet1 * sq(somg12) / (1.0 - comg12);
+            }
+        }
+
+        if start.salp1 > 0.0 {
+            let (s, c) = norm(start.salp1, start.calp1);
+            start.salp1 = s;
+            start.calp1 = c;
+        } else {
+            start.salp1 = 1.0;
+            start.calp1 = 0.0;
+        }
+
+        return start;
+    }
+
+    /// The longitude difference reached by the geodesic with the initial
+    /// azimuth `alpha1`, less the longitude difference `lam120` to reach,
+    /// and its derivative with respect to the azimuth if `diffp`.
+    fn lambda12(
+        &self,
+        beta1: (f64, f64, f64),
+        beta2: (f64, f64, f64),
+        alpha1: (f64, f64),
+        lam120: (f64, f64),
+        diffp: bool,
+    ) -> Lambda12 {
+        let (sbet1, cbet1, dn1) = beta1;
+        let (sbet2, cbet2, dn2) = beta2;
+        let (salp1, mut calp1) = alpha1;
+        let (slam120, clam120) = lam120;
+
+        if sbet1 == 0.0 && calp1 == 0.0 {
+            // break the degeneracy of equatorial lines
+            calp1 = -tiny();
+        }
+
+        let salp0 = salp1 * cbet1;
+        let calp0 = calp1.hypot(salp1 * sbet1);
+
+        let somg1 = salp0 * sbet1;
+        let comg1 = calp1 * cbet1;
+        let (ssig1, csig1) = norm(sbet1, comg1);
+
+        let salp2 = if cbet2 != cbet1 { salp0 / cbet2 } else { salp1 };
+        let calp2 = if cbet2 != cbet1 || sbet2.abs() != -sbet1 {
+            let t = if cbet1 < -sbet1 {
+                (cbet2 - cbet1) * (cbet1 + cbet2)
+            } else {
+                (sbet1 - sbet2) * (sbet1 + sbet2)
+            };
+            (sq(calp1 * cbet1) + t).sqrt() / cbet2
+        } else {
+            calp1.abs()
+        };
+
+        let somg2 = salp0 * sbet2;
+        let comg2 = calp2 * cbet2;
+        let (ssig2, csig2) = norm(sbet2, comg2);
+
+        let sig12 = (csig1 * ssig2 - ssig1 * csig2)
+            .max(0.0)
+            .atan2(csig1 * csig2 + ssig1 * ssig2);
+        let somg12 = (comg1 * somg2 - somg1 * comg2).max(0.0);
+        let comg12 = comg1 * comg2 + somg1 * somg2;
+        let eta = (somg12 * clam120 - comg12 * slam120)
+            .atan2(comg12 * clam120 + somg12 * slam120);
+
+        let k2 = sq(calp0) * self.ep2;
+        let eps = k2 / (2.0 * (1.0 + (1.0 + k2).sqrt()) + k2);
+        let mut c3a = [0.0; ORDER];
+        self.c3f(eps, &mut c3a);
+        let b312 = sin_cos_series(true, ssig2, csig2, &c3a) -
+            sin_cos_series(true, ssig1, csig1, &c3a);
+        let domg12 = -self.f * self.a3f(eps) * salp0 * (sig12 + b312);
+
+        let dlam12 = if !diffp {
+            f64::NAN
+        } else if calp2 == 0.0 {
+            -2.0 * self.f1 * dn1 / sbet1
+        } else {
+            let (_, m12) = lengths(eps, sig12, (ssig1, csig1, dn1), (ssig2, csig2, dn2));
+            m12 * self.f1 / (calp2 * cbet2)
+        };
+
+        return Lambda12 {
+            lam12: eta + domg12,
+            salp2: salp2,
+            calp2: calp2,
+            sig12: sig12,
+            ssig1: ssig1,
+            csig1: csig1,
+            ssig2: ssig2,
+            csig2: csig2,
+            eps: eps,
+            dlam12: dlam12,
+        };
+    }
+}
+
+/// The first guess of `Geodesic::inverse_start()`.
+struct InverseStart {
+    /// the arc length on the auxiliary sphere of a short line, or -1
+    sig12: f64,
+    salp1: f64,
+    calp1: f64,
+    salp2: f64,
+    calp2: f64,
+    dnm: f64,
+}
+
+/// The distance and reduced length of a geodesic, scaled to a polar
+/// semi-axis of 1, between the positions `sig1` and `sig2` (the sine and
+/// cosine of their arc lengths, and `dn`) on the auxiliary sphere.
+fn lengths(
+    eps: f64,
+    sig12: f64,
+    sig1: (f64, f64, f64),
+    sig2: (f64, f64, f64),
+) -> (f64, f64) {
+    let (ssig1, csig1, dn1) = sig1;
+    let (ssig2, csig2, dn2) = sig2;
+
+    let mut c1a = [0.0; ORDER + 1];
+    let mut c2a = [0.0; ORDER + 1];
+    let a1 = 1.0 + a1m1f(eps);
+    c1f(eps, &mut c1a);
+    let a2m1 = a2m1f(eps);
+    c2f(eps, &mut c2a);
+    let m0x = a1 - 1.0 - a2m1;
+    let a2 = 1.0 + a2m1;
+
+    let b1 = sin_cos_series(true, ssig2, csig2, &c1a) - sin_cos_series(true, ssig1, csig1, &c1a);
+    let b2 = sin_cos_series(true, ssig2, csig2, &c2a) - sin_cos_series(true, ssig1, csig1, &c2a);
+    let s12b = a1 * (sig12 + b1);
+    let j12 = m0x * sig12 + (a1 * b1 - a2 * b2);
+    let m12b = dn2 * (csig1 * ssig2) - dn1 * (ssig1 * csig2) - csig1 * csig2 * j12;
+
+    return (s12b, m12b);
+}
+
+/// Solve the astroid problem, for the initial azimuth of a nearly antipodal
+/// geodesic: the positive root `k` of `k^4 + 2k^3 - (x^2 + y^2 - 1)k^2 -
+/// 2y^2 k - y^2 = 0`.
+fn astroid(x: f64, y: f64) -> f64 {
+    let p = sq(x);
+    let q = sq(y);
+    let r = (p + q - 1.0) / 6.0;
+
+    if q == 0.0 && r <= 0.0 {
+        return 0.0;
+    }
+
+    let s = p * q / 4.0;
+    let r2 = sq(r);
+    let r3 = r * r2;
+    let disc = s * (s + 2.0 * r3);
+    let mut u = r;
+    if disc >= 0.0 {
+        let mut t3 = s + r3;
+        t3 += if t3 < 0.0 { -disc.sqrt() } else { disc.sqrt() };
+        let t = t3.cbrt();
+        u += t + if t != 0.0 { r2 / t } else { 0.0 };
+    } else {
+        let ang = (-disc).sqrt().atan2(-(s + r3));
+        u += 2.0 * r * (ang / 3.0).cos();
+    }
+
+    let v = (sq(u) + q).sqrt();
+    let uv = if u < 0.0 { q / (v - u) } else { u + v };
+    let w = (uv - q) / (2.0 * v);
+    return uv / ((uv + sq(w)).sqrt() + w);
+}
+
+/// The A1 series less 1, scaling the distance along a geodesic.
+fn a1m1f(eps: f64) -> f64 {
+    static COEFF: [f64; 5] = [1.0, 4.0, 64.0, 0.0, 256.0];
+    let m = ORDER / 2;
+    let t = polyval(m, &COEFF, sq(eps)) / COEFF[m + 1];
+    return (t + eps) / (1.0 - eps);
+}
+
+/// The coefficients of the C1 series, `c[1]` to `c[ORDER]`.
+fn c1f(eps: f64, c: &mut [f64; ORDER + 1]) {
+    static COEFF: [f64; 18] = [
+        -1.0, 6.0, -16.0, 32.0,
+        -9.0, 64.0, -128.0, 2048.0,
+        9.0, -16.0, 768.0,
+        3.0, -5.0, 512.0,
+        -7.0, 1280.0,
+        -7.0, 2048.0,
+    ];
+    series_coeffs(eps, &COEFF, c);
+}
+
+/// The coefficients of the C1' series, the inverse of the C1 series.
+fn c1pf(eps: f64, c: &mut [f64; ORDER + 1]) {
+    static COEFF: [f64; 18] = [
+        205.0, -432.0, 768.0, 1536.0,
+        4005.0, -4736.0, 3840.0, 12288.0,
+        -225.0, 116.0, 384.0,
+        -7173.0, 2695.0, 7680.0,
+        3467.0, 7680.0,
+        38081.0, 61440.0,
+    ];
+    series_coeffs(eps, &COEFF, c);
+}
+
+/// The A2 series less 1, scaling the reduced length of a geodesic.
+fn a2m1f(eps: f64) -> f64 {
+    static COEFF: [f64; 5] = [25.0, 36.0, 64.0, 0.0, 256.0];
+    let m = ORDER / 2;
+    let t = polyval(m, &COEFF, sq(eps)) / COEFF[m + 1];
+    return t * (1.0 - eps) - eps;
+}
+
+/// The coefficients of the C2 series, `c[1]` to `c[ORDER]`.
+fn c2f(eps: f64, c: &mut [f64; ORDER + 1]) {
+    static COEFF: [f64; 18] = [
+        1.0, 2.0, 16.0, 32.0,
+        35.0, 64.0, 384.0, 2048.0,
+        15.0, 80.0, 768.0,
+        7.0, 35.0, 512.0,
+        63.0, 1280.0,
+        77.0, 2048.0,
+    ];
+    series_coeffs(eps, &COEFF, c);
+}
+
+/// Evaluate the coefficients of a C1, C1' or C2 series, where `c[l]` is
+/// `eps^l` times a polynomial in `eps^2`, from their packed `coeff`.
+fn series_coeffs(eps: f64, coeff: &[f64], c: &mut [f64; ORDER + 1]) {
+    let eps2 = sq(eps);
+    let mut d = eps;
+    let mut o = 0;
+    for l in 1..(ORDER + 1) {
+        let m = (ORDER - l) / 2;
+        c[l] = d * polyval(m, &coeff[o..], eps2) / coeff[o + m + 1];
+        o += m + 2;
+        d *= eps;
+    }
+}
+
+/// Evaluate the sum of `c[l] * sin(2lx)` (if `sinp`) or `c[l] * cos((2l +
+/// 1)x)` by Clenshaw summation, given the sine and cosine of `x`.
+fn sin_cos_series(sinp: bool, sinx: f64, cosx: f64, c: &[f64]) -> f64 {
+    let mut k = c.len();
+    let mut n = k - if sinp { 1 } else { 0 };
+    let ar = 2.0 * (cosx - sinx) * (cosx + sinx);
+
+    let mut y0 = 0.0;
+    let mut y1 = 0.0;
+    if n & 1 == 1 {
+        k -= 1;
+        y0 = c[k];
+    }
+
+    n /= 2;
+    while n > 0 {
+        n -= 1;
+        k -= 1;
+        y1 = ar * y0 - y1 + c[k];
+        k -= 1;
+        y0 = ar * y1 - y0 + c[k];
+    }
+
+    return if sinp {
+        2.0 * sinx * cosx * y0
+    } else {
+        cosx * (y0 - y1)
+    };
+}
+
+/// Evaluate the polynomial of order `n`, with the coefficients `p` from the
+/// highest power, at `x`.
+fn polyval(n: usize, p: &[f64], x: f64) -> f64 {
+    let mut y = p[0];
+    for i in 1..(n + 1) {
+        y = y * x + p[i];
+    }
+    return y;
+}
+
+/// The smallest value which is squared without underflow.
+fn tiny() -> f64 {
+    return f64::MIN_POSITIVE.sqrt();
+}
+
+fn sq(x: f64) -> f64 {
+    return x * x;
+}
+
+/// Normalise the sine and cosine of an angle.
+fn norm(sinx: f64, cosx: f64) -> (f64, f64) {
+    let r = sinx.hypot(cosx);
+    return (sinx / r, cosx / r);
+}
+
+/// The sum of two values, and the rounding error of their sum.
+fn sum(u: f64, v: f64) -> (f64, f64) {
+    let s = u + v;
+    let up = s - v;
+    let vpp = s - up;
+    return (s, -((up - u) + (vpp - v)));
+}
+
+/// Round an angle so that small values are exact multiples of 2^-57,
+/// avoiding problems with angles close to 0.
+fn ang_round(x: f64) -> f64 {
+    let z = 1.0 / 16.0;
+    if x == 0.0 {
+        return 0.0;
+    }
+
+    let y = x.abs();
+    let y = if y < z { z - (z - y) } else { y };
+    return if x < 0.0 { -y } else { y };
+}
+
+/// Normalise an angle (in degrees) into the range (-180, 180].
+fn ang_normalize(x: f64) -> f64 {
+    let mut y = x % 360.0;
+    if y <= -180.0 {
+        y += 360.0;
+    } else if y > 180.0 {
+        y -= 360.0;
+    }
+    return y;
+}
+
+/// Latitudes beyond the poles are not a number.
+fn lat_fix(x: f64) -> f64 {
+    return if x.abs() > 90.0 { f64::NAN } else { x };
+}
+
+/// The exact difference `y - x` of two angles (in degrees), normalised
+/// into the range (-180, 180], and its rounding error.
+fn ang_diff(x: f64, y: f64) -> (f64, f64) {
+    let (d, t) = sum(ang_normalize(-x), ang_normalize(y));
+    let d = ang_normalize(d);
+    return sum(if d == 180.0 && t > 0.0 { -180.0 } else { d }, t);
+}
+
+/// The sine and cosine of an angle in degrees, exact for multiples of 90.
+fn sincosd(x: f64) -> (f64, f64) {
+    let r = x % 360.0;
+    let q = (r / 90.0).round();
+    let r = (r - 90.0 * q).to_radians();
+    let (s, c) = (r.sin(), r.cos());
+
+    let (s, c) = match (q as i64 % 4 + 4) % 4 {
+        1 => (c, -s),
+        2 => (-s, -c),
+        3 => (-c, s),
+        _ => (s, c),
+    };
+
+    // keep the sign of a zero angle
+    return (if x == 0.0 { x } else { s }, 0.0 + c);
+}
+
+/// The angle (in degrees) of `y` and `x`, as `atan2()`, exact for
+/// multiples of 45.
+fn atan2d(y: f64, x: f64) -> f64 {
+    let (mut x, mut y) = (x, y);
+    let mut q = 0;
+    if y.abs() > x.abs() {
+        q = 2;
+        let t = x;
+        x = y;
+        y = t;
+    }
+    if x < 0.0 {
+        q += 1;
+        x = -x;
+    }
+
+    let ang = y.atan2(x).to_degrees();
+    return match q {
+        1 => if y >= 0.0 { 180.0 - ang } else { -180.0 - ang },
+        2 => 90.0 - ang,
+        3 => -90.0 + ang,
+        _ => ang,
+    };
+}
@@ -12,6 +12,15 @@
 // to-use-geohash-for-proximity-searches
 
 use nalgebra::Vector2;
+use navdata::coord::{SphericalCoordinate, EARTH_MSL_RADIUS};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::f64::{INFINITY, NEG_INFINITY};
+use std::f64::consts::PI;
 
 // first 4 bits is currently reserved for the precision with a range of 1 to 16
 
@@ -26,13 +35,166 @@ pub static PRECISION_BITS: u8 = 6;
 
 static PRECISION_MASK: u64 = 63; //mask 111111 for the 6 precision bits
 
+/// number of bits in each character of a base32 geohash string
+pub static BASE32_BITS: u8 = 5;
+
+/// the characters of a base32 geohash string, by value
+static BASE32_ALPHABET: &'static [u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// An error from encoding, decoding or working with a geohash.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeohashError {
+    /// A precision is outside of the range which can be used.
+    PrecisionOutOfRange {
+        /// The precision (in bits)
+        precision: u8,
+
+        /// Minimum precision which can be used
+        min: u8,
+
+        /// Maximum precision which can be used
+        max: u8,
+    },
+
+    /// A precision must be a multiple of some number of bits, such as the 5
+    /// bits of each character of a base32 string.
+    PrecisionNotMultiple {
+        /// The precision (in bits)
+        precision: u8,
+
+        /// The number of bits it must be a multiple of
+        multiple: u8,
+    },
+
+    /// A hash was encoded within different bounds than it is being used
+    /// with.
+    BoundsMismatch {
+        /// The bounds being used
+        expected: Bounds,
+
+        /// The bounds of the hash
+        found: Bounds,
+    },
+
+    /// A string is not a valid geohash.
+    InvalidString {
+        /// The string
+        string: String,
+
+        /// What was wrong with it
+        message: String,
+    },
+
+    /// An integer has bits set past the precision stored in its precision
+    /// bits, so it isn't a valid geohash.
+    InvalidBits {
+        /// The integer
+        geohash: u64,
+    },
+
+    /// A neighbor is outside of the bounds, and wrapping around them is
+    /// disabled.
+    OutOfBounds {
+        /// The hash of the cell the neighbor is of
+        geohash: u64,
+
+        /// Direction of the neighbor
+        dir: (i8, i8),
+    },
+}
+
+impl fmt::Display for GeohashError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GeohashError::PrecisionOutOfRange { precision, min, max } => {
+                write!(
+                    f,
+                    "Precision {} is outside of the range of {} to {}",
+                    precision,
+                    min,
+                    max
+                )
+            }
+            GeohashError::PrecisionNotMultiple { precision, multiple } => {
+                write!(f, "Precision {} is not a multiple of {}", precision, multiple)
+            }
+            GeohashError::BoundsMismatch { ref expected, ref found } => {
+                write!(
+                    f,
+                    "A geohash of bounds {:?} can't be used with bounds {:?}",
+                    found,
+                    expected
+                )
+            }
+            GeohashError::InvalidString { ref string, ref message } => {
+                write!(f, "Invalid geohash \"{}\": {}", string, message)
+            }
+            GeohashError::InvalidBits { geohash } => {
+                write!(
+                    f,
+                    "Invalid geohash {:#x}: bits are set past its precision",
+                    geohash
+                )
+            }
+            GeohashError::OutOfBounds { geohash, dir } => {
+                write!(
+                    f,
+                    "The neighbor ({}, {}) of geohash {:#x} is outside of the bounds",
+                    dir.0,
+                    dir.1,
+                    geohash
+                )
+            }
+        }
+    }
+}
+
+impl Error for GeohashError {
+    fn description(&self) -> &str {
+        match *self {
+            GeohashError::PrecisionOutOfRange { .. } => "geohash precision out of range",
+            GeohashError::PrecisionNotMultiple { .. } => "geohash precision not a multiple",
+            GeohashError::BoundsMismatch { .. } => "geohash bounds mismatch",
+            GeohashError::InvalidString { .. } => "invalid geohash string",
+            GeohashError::InvalidBits { .. } => "invalid geohash bits",
+            GeohashError::OutOfBounds { .. } => "geohash neighbor out of bounds",
+        }
+    }
+}
+
+/// Check that a precision is in the range of `min` to `max`.
+fn check_precision(precision: u8, min: u8, max: u8) -> Result<(), GeohashError> {
+    if precision < min || precision > max {
+        return Err(GeohashError::PrecisionOutOfRange {
+            precision: precision,
+            min: min,
+            max: max,
+        });
+    }
+
+    return Ok(());
+}
+
 /// An object which is able to be geohashed.
 pub trait Geohashable<T> {
     /// decode this object of type `T`from an integer geohash
-    fn integer_decode(geohash: u64) -> Result<T, String>;
+    fn integer_decode(geohash: u64) -> Result<T, GeohashError>;
 
     /// encode this object of type `T` into an unsigned integer geohash
-    fn integer_encode(&self, precision: u8) -> Result<u64, String>;
+    fn integer_encode(&self, precision: u8) -> Result<u64, GeohashError>;
+
+    /// decode this object of type `T` from a base32 geohash string, see
+    /// `hash_from_base32()`
+    fn string_decode(string: &str) -> Result<T, GeohashError> {
+        return Self::integer_decode(try!(hash_from_base32(string)));
+    }
+
+    /// encode this object of type `T` into a base32 geohash string of
+    /// `length` characters, see `hash_to_base32()`
+    fn string_encode(&self, length: u8) -> Result<String, GeohashError> {
+        let hash = try!(self.integer_encode(length.saturating_mul(BASE32_BITS)));
+        return hash_to_base32(hash);
+    }
 }
 
 /// A trait for a vector type object that can be rectified to fit
@@ -72,24 +234,14 @@ impl Rectifiable for Vector2<f64> {
         let x_range = range.x_range();
         let y_range = range.y_range();
 
-        println!("x_range = {:#?}", x_range);
-        println!("y_range = {:#?}", y_range);
-
-        println!("self.y 0 = {:?}", self.y);
-
         while self.y < range.y_min - y_range {
             self.y += y_range;
         }
 
-        println!("self.y 1 = {:#?}", self.y);
-
         while self.y > range.y_max + y_range {
             self.y -= y_range;
         }
 
-        println!("self.y 2 = {:#?}", self.y);
-        println!("self.x 1 = {:#?}", self.x);
-
         if self.y > range.y_max {
             self.y = y_range - self.y;
             self.x += x_range / 2.0;
@@ -104,18 +256,14 @@ impl Rectifiable for Vector2<f64> {
             self.x -= x_range;
         }
 
-        println!("self.x 3 = {:#?}", self.x);
-
         while self.x < range.x_min {
             self.x += x_range;
         }
-
-        println!("self.x 4 = {:#?}", self.x);
     }
 }
 
 /// A rectangular boundary
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, PartialEq)]
 pub struct Bounds {
     /// x coordinate minimum
     pub x_min: f64,
@@ -214,6 +362,13 @@ pub static LATLON_BOUNDS: Bounds = Bounds {
 /// encode a `Vector2` position into an unsigned integer geohash.
 /// wraps the position around the boundaries, to keep it within them.
 ///
+/// The position is quantised to the index of its cell along x and y,
+/// whose bits are then interleaved, rather than bisecting the bounds once
+/// for each bit. Positions on the boundary between two cells are in the
+/// lower one, the same as for `encode_bisect()`, and the hashes are the same
+/// whenever the midpoints of the bounds are exactly representable, as they
+/// are for `LATLON_BOUNDS`.
+///
 /// # Example
 ///
 /// ```
@@ -228,14 +383,41 @@ pub static LATLON_BOUNDS: Bounds = Bounds {
 /// assert_eq!(hash_to_string(gh).unwrap(), "11100110");
 /// # }
 /// ```
-pub fn encode(position: &Vector2<f64>, precision: u8, range: &Bounds) -> Result<u64, String> {
-    if precision > PRECISION_MAX || precision < PRECISION_MIN {
-        return Err(format!(
-            "Precision must be in the range of {} to {}",
-            PRECISION_MIN,
-            PRECISION_MAX
-        ));
-    }
+pub fn encode(
+    position: &Vector2<f64>,
+    precision: u8,
+    range: &Bounds,
+) -> Result<u64, GeohashError> {
+    try!(check_precision(precision, PRECISION_MIN, PRECISION_MAX));
+
+    let x = quantise(position.x, range.x_min, range.x_max, (precision + 1) / 2);
+    let y = quantise(position.y, range.y_min, range.y_max, precision / 2);
+    return Ok(interleave(x, y, precision));
+}
+
+/// encode a `Vector2` position into an unsigned integer geohash by
+/// bisecting the bounds one bit at a time. This gives the same hashes as
+/// `encode()`, which is faster, and is kept as a reference for it.
+///
+/// # Example
+///
+/// ```
+/// # extern crate nalgebra;
+/// # extern crate oldnav_lib;
+/// # use oldnav_lib::navdata::geohash::*;
+/// use nalgebra::Vector2;
+/// # fn main() {
+/// let p = Vector2::new(121.473, 31.23);
+/// let gh = encode_bisect(&p, 8, &LATLON_BOUNDS).unwrap();
+/// assert_eq!(encode(&p, 8, &LATLON_BOUNDS).unwrap(), gh);
+/// # }
+/// ```
+pub fn encode_bisect(
+    position: &Vector2<f64>,
+    precision: u8,
+    range: &Bounds,
+) -> Result<u64, GeohashError> {
+    try!(check_precision(precision, PRECISION_MIN, PRECISION_MAX));
 
     let mut current_range: Bounds = range.clone();
 
@@ -288,12 +470,10 @@ pub fn encode(position: &Vector2<f64>, precision: u8, range: &Bounds) -> Result<
 /// assert_eq!("1111", hash_to_string(gh).unwrap());
 /// ```
 ///
-pub fn hash_to_string(geohash: u64) -> Result<String, String> {
+pub fn hash_to_string(geohash: u64) -> Result<String, GeohashError> {
     let precision: u8 = hash_precision(geohash);
     let mut string = String::with_capacity(precision as usize);
 
-    println!("Precision {}", precision);
-
     let mut i = PRECISION_BITS;
     let end = PRECISION_BITS + precision;
     while i < end {
@@ -315,15 +495,25 @@ pub fn hash_to_string(geohash: u64) -> Result<String, String> {
 /// assert_eq!(4, hash_precision(gh));
 /// assert_eq!("1111", hash_to_string(gh).unwrap());
 /// ```
-pub fn hash_from_string(string: &str) -> Result<u64, String> {
+pub fn hash_from_string(string: &str) -> Result<u64, GeohashError> {
     let precision = string.len();
 
     if precision > (PRECISION_MAX as usize) || precision < (PRECISION_MIN as usize) {
-        return Err(format!(
-            "String too long, its length must be in the range of {} to {}",
-            PRECISION_MIN,
-            PRECISION_MAX
-        ));
+        return Err(GeohashError::InvalidString {
+            string: String::from(string),
+            message: format!(
+                "its length must be in the range of {} to {}",
+                PRECISION_MIN,
+                PRECISION_MAX
+            ),
+        });
+    }
+
+    if let Some(character) = string.chars().find(|c| *c != '0' && *c != '1') {
+        return Err(GeohashError::InvalidString {
+            string: String::from(string),
+            message: format!("invalid character '{}'", character),
+        });
     }
 
     let mut hash: u64 = (precision as u64) - 1;
@@ -340,6 +530,105 @@ pub fn hash_from_string(string: &str) -> Result<u64, String> {
     return Ok(hash);
 }
 
+/// Convert an integer geohash to a standard base32 geohash string, as used
+/// by geohash.org and most other geohash libraries. Each character holds 5
+/// bits, so the precision of the hash must be a multiple of 5. The string
+/// is only meaningful for a hash encoded within `LATLON_BOUNDS`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate nalgebra;
+/// # extern crate oldnav_lib;
+/// # use oldnav_lib::navdata::geohash::*;
+/// use nalgebra::Vector2;
+/// # fn main() {
+/// let gh = encode(&Vector2::new(116.3906, 39.92324), 40, &LATLON_BOUNDS).unwrap();
+/// assert_eq!("wx4g0ec1", hash_to_base32(gh).unwrap());
+///
+/// let gh = encode(&Vector2::new(116.3906, 39.92324), 8, &LATLON_BOUNDS).unwrap();
+/// assert!(hash_to_base32(gh).is_err());
+/// # }
+/// ```
+pub fn hash_to_base32(geohash: u64) -> Result<String, GeohashError> {
+    let precision = hash_precision(geohash);
+    if precision % BASE32_BITS != 0 {
+        return Err(GeohashError::PrecisionNotMultiple {
+            precision: precision,
+            multiple: BASE32_BITS,
+        });
+    }
+
+    let length = precision / BASE32_BITS;
+    let mut string = String::with_capacity(length as usize);
+
+    for c in 0..length {
+        // the first bit of each character is its most significant
+        let mut value = 0;
+        for i in 0..BASE32_BITS {
+            let bit = (geohash >> (PRECISION_BITS + c * BASE32_BITS + i)) & 1;
+            value = value << 1 | bit as usize;
+        }
+        string.push(BASE32_ALPHABET[value] as char);
+    }
+
+    return Ok(string);
+}
+
+/// Create an integer geohash from a standard base32 geohash string, see
+/// `hash_to_base32()`. Upper case characters are accepted, and the string
+/// may be up to 11 characters long.
+///
+/// # Example
+///
+/// ```
+/// # extern crate nalgebra;
+/// # extern crate oldnav_lib;
+/// # use oldnav_lib::navdata::geohash::*;
+/// use nalgebra::Vector2;
+/// # fn main() {
+/// let gh = hash_from_base32("ezs42").unwrap();
+/// assert_eq!(25, hash_precision(gh));
+/// let b = decode(gh, &LATLON_BOUNDS).unwrap();
+/// assert!(b.contains(&Vector2::new(-5.6, 42.6)));
+///
+/// assert!(hash_from_base32("ezs4a").is_err());
+/// # }
+/// ```
+pub fn hash_from_base32(string: &str) -> Result<u64, GeohashError> {
+    let max_length = PRECISION_MAX / BASE32_BITS;
+    let length = string.chars().count();
+    if length < 1 || length > max_length as usize {
+        return Err(GeohashError::InvalidString {
+            string: String::from(string),
+            message: format!("its length must be in the range of 1 to {}", max_length),
+        });
+    }
+
+    let precision = length as u8 * BASE32_BITS;
+    let mut hash: u64 = (precision as u64) - 1;
+
+    for (c, character) in string.chars().enumerate() {
+        let lower = character.to_ascii_lowercase();
+        let value = match BASE32_ALPHABET.iter().position(|a| *a as char == lower) {
+            Some(value) => value as u64,
+            None => {
+                return Err(GeohashError::InvalidString {
+                    string: String::from(string),
+                    message: format!("invalid base32 character '{}'", character),
+                })
+            }
+        };
+
+        for i in 0..BASE32_BITS {
+            let bit = (value >> (BASE32_BITS - 1 - i)) & 1;
+            hash |= bit << (PRECISION_BITS + c as u8 * BASE32_BITS + i);
+        }
+    }
+
+    return Ok(hash);
+}
+
 /// Decode integer geohash into a `Bounds`
 ///
 /// # Example
@@ -356,33 +645,49 @@ pub fn hash_from_string(string: &str) -> Result<u64, String> {
 /// assert_eq!(true, b.contains(&p));
 /// # }
 /// ```
-pub fn decode(geohash: u64, range: &Bounds) -> Result<Bounds, String> {
+pub fn decode(geohash: u64, range: &Bounds) -> Result<Bounds, GeohashError> {
     let precision: u8 = hash_precision(geohash);
     return decode_precision_nocheck(geohash, precision, range);
 }
 
 /// decode an integer geohash with the specified precision.
-pub fn decode_precision(geohash: u64, precision: u8, range: &Bounds) -> Result<Bounds, String> {
-    let hp: u8 = hash_precision(geohash);
-    if precision > hp {
-        return Err(format!(
-            "Selected precision ({}) is greater than the max precision of the \
-                            hash: ({})",
-            precision,
-            hp
-        ));
-    }
+pub fn decode_precision(
+    geohash: u64,
+    precision: u8,
+    range: &Bounds,
+) -> Result<Bounds, GeohashError> {
+    try!(check_precision(precision, 0, hash_precision(geohash)));
 
     return decode_precision_nocheck(geohash, precision, range);
 }
 
 /// decode an integer geohash with specified precision, without checking
 /// whether or not the precision is less than the hash's internal precision.
+///
+/// The bits of the hash are deinterleaved into the index of its cell along
+/// x and y, see `encode()`.
 pub fn decode_precision_nocheck(
     geohash: u64,
     precision: u8,
     range: &Bounds,
-) -> Result<Bounds, String> {
+) -> Result<Bounds, GeohashError> {
+    let x_bits = (precision + 1) / 2;
+    let y_bits = precision / 2;
+    let (x, y) = deinterleave(geohash, precision);
+    let (x_min, x_max) = cell_range(x, x_bits, range.x_min, range.x_max);
+    let (y_min, y_max) = cell_range(y, y_bits, range.y_min, range.y_max);
+    return Ok(Bounds::new(x_min, x_max, y_min, y_max));
+}
+
+/// decode an integer geohash with specified precision by bisecting the
+/// bounds one bit at a time, without checking the precision. This gives
+/// the same bounds as `decode_precision_nocheck()`, which is faster, and is
+/// kept as a reference for it.
+pub fn decode_precision_bisect(
+    geohash: u64,
+    precision: u8,
+    range: &Bounds,
+) -> Result<Bounds, GeohashError> {
     let mut current_range: Bounds = range.clone();
 
     let mut do_x = true;
@@ -416,6 +721,120 @@ pub fn decode_precision_nocheck(
 }
 
 
+/// The index of the cell containing `value`, along an axis from `min` to
+/// `max` split into `2^bits` cells. A value on the boundary between two
+/// cells is in the lower one, and values outside of the axis are in the
+/// first or last cell, as when bisecting.
+fn quantise(value: f64, min: f64, max: f64, bits: u8) -> u64 {
+    let n = 1u64 << bits;
+    if !(value > min) {
+        return 0;
+    }
+
+    if !(value < max) {
+        return n - 1;
+    }
+
+    let width = (max - min) / n as f64;
+    let mut i = (((value - min) / width).ceil() as u64).max(1).min(n) - 1;
+
+    // the division may round across a boundary, so check against the
+    // boundaries themselves, which are where bisection would put them.
+    while i > 0 && !(value > min + i as f64 * width) {
+        i -= 1;
+    }
+
+    while i < n - 1 && value > min + (i + 1) as f64 * width {
+        i += 1;
+    }
+
+    return i;
+}
+
+/// The range of the cell at `index` along an axis from `min` to `max`
+/// split into `2^bits` cells.
+fn cell_range(index: u64, bits: u8, min: f64, max: f64) -> (f64, f64) {
+    let n = 1u64 << bits;
+    let width = (max - min) / n as f64;
+    let low = min + index as f64 * width;
+    let high = if index + 1 >= n {
+        max
+    } else {
+        min + (index + 1) as f64 * width
+    };
+
+    return (low, high);
+}
+
+/// Spread the low 32 bits of `value` out to the even bits of a `u64`.
+fn spread_bits(value: u64) -> u64 {
+    let mut v = value & 0x0000_0000_ffff_ffff;
+    v = (v | v << 16) & 0x0000_ffff_0000_ffff;
+    v = (v | v << 8) & 0x00ff_00ff_00ff_00ff;
+    v = (v | v << 4) & 0x0f0f_0f0f_0f0f_0f0f;
+    v = (v | v << 2) & 0x3333_3333_3333_3333;
+    v = (v | v << 1) & 0x5555_5555_5555_5555;
+    return v;
+}
+
+/// Gather the even bits of `value` into the low 32 bits of a `u64`, the
+/// inverse of `spread_bits()`.
+fn compact_bits(value: u64) -> u64 {
+    let mut v = value & 0x5555_5555_5555_5555;
+    v = (v | v >> 1) & 0x3333_3333_3333_3333;
+    v = (v | v >> 2) & 0x0f0f_0f0f_0f0f_0f0f;
+    v = (v | v >> 4) & 0x00ff_00ff_00ff_00ff;
+    v = (v | v >> 8) & 0x0000_ffff_0000_ffff;
+    v = (v | v >> 16) & 0x0000_0000_ffff_ffff;
+    return v;
+}
+
+/// Reverse the order of the bits of `value`.
+pub(crate) fn reverse_bits(value: u64) -> u64 {
+    let mut v = value;
+    v = (v >> 1) & 0x5555_5555_5555_5555 | (v & 0x5555_5555_5555_5555) << 1;
+    v = (v >> 2) & 0x3333_3333_3333_3333 | (v & 0x3333_3333_3333_3333) << 2;
+    v = (v >> 4) & 0x0f0f_0f0f_0f0f_0f0f | (v & 0x0f0f_0f0f_0f0f_0f0f) << 4;
+    v = (v >> 8) & 0x00ff_00ff_00ff_00ff | (v & 0x00ff_00ff_00ff_00ff) << 8;
+    v = (v >> 16) & 0x0000_ffff_0000_ffff | (v & 0x0000_ffff_0000_ffff) << 16;
+    return v >> 32 | v << 32;
+}
+
+/// Interleave the indices of a cell along x and y into an integer geohash.
+///
+/// The hash bits alternate between x and y, starting with the most
+/// significant bit of x at the lowest bit. This is a Morton (Z-order) code
+/// with its bits reversed, so the Morton code is built with the finest bit
+/// at the bottom, and then reversed into place.
+fn interleave(x: u64, y: u64, precision: u8) -> u64 {
+    // the finest bit is from x when the precision is odd
+    let morton = if precision % 2 == 0 {
+        spread_bits(x) << 1 | spread_bits(y)
+    } else {
+        spread_bits(x) | spread_bits(y) << 1
+    };
+
+    let bits = reverse_bits(morton) >> (64 - precision as u32);
+    return bits << PRECISION_BITS | (precision - 1) as u64;
+}
+
+/// Deinterleave the first `precision` bits of an integer geohash into the
+/// indices of its cell along x and y, the inverse of `interleave()`.
+fn deinterleave(geohash: u64, precision: u8) -> (u64, u64) {
+    if precision == 0 {
+        return (0, 0);
+    }
+
+    let bits = (geohash >> PRECISION_BITS) & ((1 << precision) - 1);
+    let morton = reverse_bits(bits) >> (64 - precision as u32);
+
+    if precision % 2 == 0 {
+        return (compact_bits(morton >> 1), compact_bits(morton));
+    } else {
+        return (compact_bits(morton), compact_bits(morton >> 1));
+    }
+}
+
 /// Get the precision value for an integer geohash.
 ///
 /// # Example
@@ -430,7 +849,7 @@ pub fn decode_precision_nocheck(
 /// length of 8:
 /// ```
 /// # use oldnav_lib::navdata::geohash::*;
-/// let gh = hash_from_string("00000000");
+/// let gh = hash_from_string("00000000").unwrap();
 /// assert_eq!(8, hash_precision(gh));
 /// ```
 pub fn hash_precision(geohash: u64) -> u8 {
@@ -438,32 +857,945 @@ pub fn hash_precision(geohash: u64) -> u8 {
 }
 
 
-/// Get the neighboring hash in the direction specified
-/// spherical: is whether or not to use spherical rectification/bounds wrapping.
+/// Split an integer geohash into the indices of its cell along x and y,
+/// and the number of bits of each.
+///
+/// # Example
+///
+/// ```
+/// # use oldnav_lib::navdata::geohash::*;
+/// let gh = hash_from_string("11010").unwrap();
+/// assert_eq!((0b100, 0b11, 3, 2), cell_indices(gh));
+/// assert_eq!(gh, from_cell_indices(0b100, 0b11, 5));
+/// ```
+pub fn cell_indices(geohash: u64) -> (u64, u64, u8, u8) {
+    let precision = hash_precision(geohash);
+    let (x, y) = deinterleave(geohash, precision);
+    return (x, y, (precision + 1) / 2, precision / 2);
+}
+
+/// Join the indices of a cell along x and y into an integer geohash of
+/// `precision`, the inverse of `cell_indices()`. The indices must fit in
+/// the bits for x and y at that precision.
+pub fn from_cell_indices(x: u64, y: u64, precision: u8) -> u64 {
+    return interleave(x, y, precision);
+}
+
+/// Get the neighboring hash in the direction specified, as a number of
+/// cells along x and y, e.g. `(1, 0)` for the neighbor in the positive x
+/// direction.
+///
+/// spherical: is whether or not to use spherical bounds wrapping, as for
+/// `LATLON_BOUNDS`. Going past the x bounds wraps around to the other side
+/// (across the antimeridian), and going past the y bounds continues over
+/// the pole, on the opposite side of the range in x. Otherwise it is an
+/// error to go past the bounds.
+///
 /// # Example
 ///
 /// ```
+/// # extern crate nalgebra;
+/// # extern crate oldnav_lib;
+/// # use oldnav_lib::navdata::geohash::*;
+/// use nalgebra::Vector2;
+/// # fn main() {
+/// let gh = encode(&Vector2::new(179.9, 10.0), 20, &LATLON_BOUNDS).unwrap();
+/// let east = neighbor(gh, (1, 0), true).unwrap();
+/// let east_bounds = decode(east, &LATLON_BOUNDS).unwrap();
+/// assert!(east_bounds.contains(&Vector2::new(-179.9, 10.0)));
 ///
+/// assert!(neighbor(gh, (1, 0), false).is_err());
+/// # }
 /// ```
-pub fn neighbor(
-    geohash: u64,
-    dir: (i8, i8),
-    range: &Bounds,
-    spherical: bool,
-) -> Result<u64, String> {
-    let b: Bounds = try!(decode(geohash, range));
+pub fn neighbor(geohash: u64, dir: (i8, i8), spherical: bool) -> Result<u64, GeohashError> {
+    let precision = hash_precision(geohash);
+    let (x, y, x_bits, y_bits) = cell_indices(geohash);
+    let n_x = 1i64 << x_bits;
+    let n_y = 1i64 << y_bits;
+
+    let mut x = x as i64 + dir.0 as i64;
+    let mut y = y as i64 + dir.1 as i64;
+
+    if !spherical {
+        if x < 0 || x >= n_x || y < 0 || y >= n_y {
+            return Err(GeohashError::OutOfBounds {
+                geohash: geohash,
+                dir: dir,
+            });
+        }
+
+        return Ok(interleave(x as u64, y as u64, precision));
+    }
+
+    // over a pole, continuing on the other side of it
+    y = ((y % (2 * n_y)) + 2 * n_y) % (2 * n_y);
+    if y >= n_y {
+        y = 2 * n_y - 1 - y;
+        x += n_x / 2;
+    }
+    x = ((x % n_x) + n_x) % n_x;
+
+    return Ok(interleave(x as u64, y as u64, precision));
+}
+
+/// Get all of the neighbors of a hash, see `neighbor()`, starting with the
+/// neighbor in the positive y direction and going clockwise.
+///
+/// Each neighbor is only listed once, and the hash itself is not listed,
+/// so there are fewer than eight where the neighbors collapse into the
+/// same cells, such as over a pole or at low precision. Without spherical
+/// wrapping there are no neighbors past the bounds.
+///
+/// # Example
+///
+/// ```
+/// # extern crate nalgebra;
+/// # extern crate oldnav_lib;
+/// # use oldnav_lib::navdata::geohash::*;
+/// use nalgebra::Vector2;
+/// # fn main() {
+/// let gh = encode(&Vector2::new(121.473, 31.23), 20, &LATLON_BOUNDS).unwrap();
+/// assert_eq!(8, neighbors(gh, true).len());
+///
+/// // a corner has three neighbors within the bounds
+/// let corner = encode(&Vector2::new(179.9, 89.9), 20, &LATLON_BOUNDS).unwrap();
+/// assert_eq!(3, neighbors(corner, false).len());
+/// # }
+/// ```
+pub fn neighbors(geohash: u64, spherical: bool) -> Vec<u64> {
+    let directions = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+    let mut hashes: Vec<u64> = Vec::with_capacity(8);
+
+    for dir in &directions {
+        if let Ok(hash) = neighbor(geohash, *dir, spherical) {
+            if hash != geohash && !hashes.contains(&hash) {
+                hashes.push(hash);
+            }
+        }
+    }
+
+    return hashes;
+}
+
+/// Get the hash of the cell containing this one, with one less bit of
+/// precision.
+///
+/// # Example
+///
+/// ```
+/// # use oldnav_lib::navdata::geohash::*;
+/// let gh = hash_from_string("1101").unwrap();
+/// assert_eq!("110", hash_to_string(parent(gh).unwrap()).unwrap());
+/// assert!(parent(hash_from_string("1").unwrap()).is_err());
+/// ```
+pub fn parent(geohash: u64) -> Result<u64, GeohashError> {
+    return truncate(geohash, hash_precision(geohash) - 1);
+}
+
+/// Get the hashes of the two cells this one is split into, with one more
+/// bit of precision.
+///
+/// # Example
+///
+/// ```
+/// # use oldnav_lib::navdata::geohash::*;
+/// let gh = hash_from_string("110").unwrap();
+/// let children = children(gh).unwrap();
+/// assert_eq!("1100", hash_to_string(children[0]).unwrap());
+/// assert_eq!("1101", hash_to_string(children[1]).unwrap());
+/// ```
+pub fn children(geohash: u64) -> Result<[u64; 2], GeohashError> {
     let precision = hash_precision(geohash);
-    let (x_dir, y_dir) = dir;
-    let midpoint = b.mid();
+    let low = try!(extend(geohash, precision + 1));
+    return Ok([low, low | 1 << (PRECISION_BITS + precision)]);
+}
+
+/// Whether the cell of `ancestor` contains the cell of `geohash`, i.e.
+/// whether `geohash` starts with all the bits of `ancestor`. A hash counts
+/// as its own ancestor.
+///
+/// # Example
+///
+/// ```
+/// # use oldnav_lib::navdata::geohash::*;
+/// let gh = hash_from_string("110101").unwrap();
+/// assert!(is_ancestor_of(hash_from_string("110").unwrap(), gh));
+/// assert!(is_ancestor_of(gh, gh));
+/// assert!(!is_ancestor_of(hash_from_string("111").unwrap(), gh));
+/// assert!(!is_ancestor_of(gh, hash_from_string("110").unwrap()));
+/// ```
+pub fn is_ancestor_of(ancestor: u64, geohash: u64) -> bool {
+    let precision = hash_precision(ancestor);
+    if precision > hash_precision(geohash) {
+        return false;
+    }
+
+    let mask = ((1 << precision) - 1) << PRECISION_BITS;
+    return ancestor & mask == geohash & mask;
+}
+
+/// Get the hash of the smallest cell containing both hashes, which is their
+/// longest common prefix. Returns `None` if they don't share the first bit.
+///
+/// # Example
+///
+/// ```
+/// # use oldnav_lib::navdata::geohash::*;
+/// let a = hash_from_string("110101").unwrap();
+/// let b = hash_from_string("1100").unwrap();
+/// assert_eq!("110", hash_to_string(common_prefix(a, b).unwrap()).unwrap());
+/// assert_eq!(None, common_prefix(a, hash_from_string("0").unwrap()));
+/// ```
+pub fn common_prefix(a: u64, b: u64) -> Option<u64> {
+    let max_precision = hash_precision(a).min(hash_precision(b));
+    let differing = (a ^ b) >> PRECISION_BITS;
+    let precision = (differing.trailing_zeros() as u8).min(max_precision);
+
+    if precision < PRECISION_MIN {
+        return None;
+    }
+
+    return truncate(a, precision).ok();
+}
+
+/// Reduce the precision of a hash, keeping its first `precision` bits.
+///
+/// # Example
+///
+/// ```
+/// # use oldnav_lib::navdata::geohash::*;
+/// let gh = hash_from_string("110101").unwrap();
+/// assert_eq!("1101", hash_to_string(truncate(gh, 4).unwrap()).unwrap());
+/// assert!(truncate(gh, 7).is_err());
+/// ```
+pub fn truncate(geohash: u64, precision: u8) -> Result<u64, GeohashError> {
+    try!(check_precision(precision, PRECISION_MIN, hash_precision(geohash)));
+
+    let mask = ((1 << precision) - 1) << PRECISION_BITS;
+    return Ok((geohash & mask) | (precision - 1) as u64);
+}
+
+/// Increase the precision of a hash, filling the extra bits with zeros.
+/// The result is the first of the hashes with that precision within the
+/// cell of `geohash`.
+///
+/// # Example
+///
+/// ```
+/// # use oldnav_lib::navdata::geohash::*;
+/// let gh = hash_from_string("11").unwrap();
+/// assert_eq!("1100", hash_to_string(extend(gh, 4).unwrap()).unwrap());
+/// assert!(extend(gh, 1).is_err());
+/// ```
+pub fn extend(geohash: u64, precision: u8) -> Result<u64, GeohashError> {
+    try!(check_precision(precision, hash_precision(geohash), PRECISION_MAX));
+
+    return Ok((geohash & !PRECISION_MASK) | (precision - 1) as u64);
+}
+
+/// The position of the cell of a hash along the Z-order (Morton) curve, as
+/// a key of `PRECISION_MAX` bits with the coarsest bit of the hash the most
+/// significant. Sorting hashes of `PRECISION_MAX` by their keys sorts them
+/// along the curve, so that the hashes within any cell have a contiguous
+/// range of keys, see `morton_key_range()`.
+///
+/// # Example
+///
+/// ```
+/// # use oldnav_lib::navdata::geohash::*;
+/// let a = hash_from_string("01").unwrap();
+/// let b = hash_from_string("10").unwrap();
+/// assert!(a > b);
+/// assert!(morton_key(a) < morton_key(b));
+/// ```
+pub fn morton_key(geohash: u64) -> u64 {
+    return reverse_bits(geohash & !PRECISION_MASK);
+}
+
+/// The first and last keys, see `morton_key()`, of the hashes of
+/// `PRECISION_MAX` within the cell of `geohash`.
+///
+/// # Example
+///
+/// ```
+/// # use oldnav_lib::navdata::geohash::*;
+/// let cell = hash_from_string("110").unwrap();
+/// let (first, last) = morton_key_range(cell);
+/// let inside = extend(hash_from_string("1101").unwrap(), PRECISION_MAX).unwrap();
+/// assert!(morton_key(inside) >= first && morton_key(inside) <= last);
+/// ```
+pub fn morton_key_range(geohash: u64) -> (u64, u64) {
+    let first = morton_key(geohash);
+    let free_bits = PRECISION_MAX - hash_precision(geohash);
+    return (first, first | ((1 << free_bits) - 1));
+}
+
+/// A validated integer geohash, with the bounds it was encoded within if
+/// they are known.
+///
+/// The precision of a `Geohash` is always in range, and no bits are set
+/// past it. Using a hash with a different `Bounds` than the ones it was
+/// encoded within, or combining two hashes of different bounds, is an
+/// error.
+///
+/// Geohashes are ordered along the Z-order curve, see `morton_key()`, with
+/// each cell before the cells within it, so sorting hashes sorts them
+/// spatially. Hashes in the same place of different bounds are then
+/// ordered by their bounds, so that the ordering is consistent with
+/// equality.
+///
+/// A `Geohash` is displayed as its binary string, see `hash_to_string()`,
+/// which is parsed by `from_str()` into a hash without bounds.
+///
+/// # Example
+///
+/// ```
+/// # extern crate nalgebra;
+/// # extern crate oldnav_lib;
+/// # use oldnav_lib::navdata::geohash::*;
+/// use nalgebra::Vector2;
+/// # fn main() {
+/// let p = Vector2::new(121.473, 31.23);
+/// let gh = Geohash::encode(&p, 8, &LATLON_BOUNDS).unwrap();
+/// assert_eq!("11100110", gh.to_string());
+/// assert!(gh.decode(&LATLON_BOUNDS).unwrap().contains(&p));
+///
+/// let other_bounds = Bounds::new(0.0, 1.0, 0.0, 1.0);
+/// assert!(gh.decode(&other_bounds).is_err());
+///
+/// let parsed: Geohash = "1110".parse().unwrap();
+/// assert!(parsed < gh);
+/// assert!(parsed.is_ancestor_of(&gh).unwrap());
+/// assert!("1112".parse::<Geohash>().is_err());
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Geohash {
+    hash: u64,
+    bounds: Option<Bounds>,
+}
+
+impl Geohash {
+    /// Validate an integer geohash, whose bounds aren't known.
+    pub fn new(geohash: u64) -> Result<Geohash, GeohashError> {
+        let precision = hash_precision(geohash);
+        try!(check_precision(precision, PRECISION_MIN, PRECISION_MAX));
+
+        if precision < PRECISION_MAX && geohash >> (PRECISION_BITS + precision) != 0 {
+            return Err(GeohashError::InvalidBits { geohash: geohash });
+        }
+
+        return Ok(Geohash {
+            hash: geohash,
+            bounds: None,
+        });
+    }
+
+    /// Validate an integer geohash encoded within `bounds`.
+    pub fn with_bounds(geohash: u64, bounds: &Bounds) -> Result<Geohash, GeohashError> {
+        let mut gh = try!(Geohash::new(geohash));
+        gh.bounds = Some(*bounds);
+        return Ok(gh);
+    }
+
+    /// Encode a position within `range`, see `encode()`.
+    pub fn encode(
+        position: &Vector2<f64>,
+        precision: u8,
+        range: &Bounds,
+    ) -> Result<Geohash, GeohashError> {
+        return Geohash::with_bounds(try!(encode(position, precision, range)), range);
+    }
+
+    /// Read a standard base32 geohash string, of `LATLON_BOUNDS`, see
+    /// `hash_from_base32()`.
+    pub fn from_base32(string: &str) -> Result<Geohash, GeohashError> {
+        return Geohash::with_bounds(try!(hash_from_base32(string)), &LATLON_BOUNDS);
+    }
+
+    /// This hash as a standard base32 geohash string, see
+    /// `hash_to_base32()`. It must be of `LATLON_BOUNDS`, if its bounds are
+    /// known.
+    pub fn to_base32(&self) -> Result<String, GeohashError> {
+        try!(self.check_bounds(&Some(LATLON_BOUNDS)));
+        return hash_to_base32(self.hash);
+    }
+
+    /// The integer geohash.
+    pub fn as_u64(&self) -> u64 {
+        return self.hash;
+    }
+
+    /// The precision of this hash, in bits.
+    pub fn precision(&self) -> u8 {
+        return hash_precision(self.hash);
+    }
+
+    /// The bounds this hash was encoded within, if they are known.
+    pub fn bounds(&self) -> Option<&Bounds> {
+        return self.bounds.as_ref();
+    }
+
+    /// Decode this hash into the bounds of its cell within `range`, which
+    /// must be the bounds of this hash if they are known.
+    pub fn decode(&self, range: &Bounds) -> Result<Bounds, GeohashError> {
+        if let Some(bounds) = self.bounds {
+            if bounds != *range {
+                return Err(GeohashError::BoundsMismatch {
+                    expected: *range,
+                    found: bounds,
+                });
+            }
+        }
+
+        return decode(self.hash, range);
+    }
+
+    /// The hash of the cell containing this one, see `parent()`.
+    pub fn parent(&self) -> Result<Geohash, GeohashError> {
+        return Ok(self.derive(try!(parent(self.hash))));
+    }
+
+    /// The hashes of the two cells within this one, see `children()`.
+    pub fn children(&self) -> Result<[Geohash; 2], GeohashError> {
+        let children = try!(children(self.hash));
+        return Ok([self.derive(children[0]), self.derive(children[1])]);
+    }
+
+    /// The neighboring hash in a direction, see `neighbor()`.
+    pub fn neighbor(&self, dir: (i8, i8), spherical: bool) -> Result<Geohash, GeohashError> {
+        return Ok(self.derive(try!(neighbor(self.hash, dir, spherical))));
+    }
+
+    /// All of the neighboring hashes, see `neighbors()`.
+    pub fn neighbors(&self, spherical: bool) -> Vec<Geohash> {
+        return neighbors(self.hash, spherical)
+            .into_iter()
+            .map(|hash| self.derive(hash))
+            .collect();
+    }
+
+    /// This hash with a lower precision, see `truncate()`.
+    pub fn truncate(&self, precision: u8) -> Result<Geohash, GeohashError> {
+        return Ok(self.derive(try!(truncate(self.hash, precision))));
+    }
+
+    /// This hash with a higher precision, see `extend()`.
+    pub fn extend(&self, precision: u8) -> Result<Geohash, GeohashError> {
+        return Ok(self.derive(try!(extend(self.hash, precision))));
+    }
+
+    /// Whether the cell of this hash contains the cell of `other`, see
+    /// `is_ancestor_of()`. The hashes must be of the same bounds, if they
+    /// are known.
+    pub fn is_ancestor_of(&self, other: &Geohash) -> Result<bool, GeohashError> {
+        try!(self.check_bounds(&other.bounds));
+        return Ok(is_ancestor_of(self.hash, other.hash));
+    }
+
+    /// The smallest cell containing this hash and `other`, see
+    /// `common_prefix()`. The hashes must be of the same bounds, if they
+    /// are known.
+    pub fn common_prefix(&self, other: &Geohash) -> Result<Option<Geohash>, GeohashError> {
+        try!(self.check_bounds(&other.bounds));
+        return Ok(common_prefix(self.hash, other.hash).map(|hash| {
+            Geohash {
+                hash: hash,
+                bounds: self.bounds.or(other.bounds),
+            }
+        }));
+    }
+
+    /// The position of this hash along the Z-order curve, see
+    /// `morton_key()`.
+    pub fn morton_key(&self) -> u64 {
+        return morton_key(self.hash);
+    }
+
+    /// A hash of the same bounds as this one.
+    fn derive(&self, hash: u64) -> Geohash {
+        return Geohash {
+            hash: hash,
+            bounds: self.bounds,
+        };
+    }
+
+    /// Check that `bounds` match the bounds of this hash, where both are
+    /// known.
+    fn check_bounds(&self, bounds: &Option<Bounds>) -> Result<(), GeohashError> {
+        if let (Some(own), Some(other)) = (self.bounds, *bounds) {
+            if own != other {
+                return Err(GeohashError::BoundsMismatch {
+                    expected: own,
+                    found: other,
+                });
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// The bits of the bounds, which (unlike `f64`) are totally ordered
+    /// and hashable.
+    fn bounds_bits(&self) -> Option<[u64; 4]> {
+        return self.bounds.map(|b| {
+            [b.x_min.to_bits(), b.x_max.to_bits(), b.y_min.to_bits(), b.y_max.to_bits()]
+        });
+    }
+}
+
+impl From<Geohash> for u64 {
+    fn from(geohash: Geohash) -> u64 {
+        return geohash.hash;
+    }
+}
+
+impl PartialEq for Geohash {
+    fn eq(&self, other: &Geohash) -> bool {
+        return self.hash == other.hash && self.bounds_bits() == other.bounds_bits();
+    }
+}
+
+impl Eq for Geohash {}
+
+impl PartialOrd for Geohash {
+    fn partial_cmp(&self, other: &Geohash) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Ord for Geohash {
+    fn cmp(&self, other: &Geohash) -> Ordering {
+        return (self.morton_key(), self.precision(), self.bounds_bits()).cmp(&(
+            other.morton_key(),
+            other.precision(),
+            other.bounds_bits(),
+        ));
+    }
+}
+
+impl Hash for Geohash {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash.hash(state);
+        self.bounds_bits().hash(state);
+    }
+}
+
+impl fmt::Display for Geohash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for i in 0..self.precision() {
+            let bit = (self.hash >> (PRECISION_BITS + i)) & 1;
+            try!(write!(f, "{}", bit));
+        }
+
+        return Ok(());
+    }
+}
+
+impl FromStr for Geohash {
+    type Err = GeohashError;
+
+    fn from_str(string: &str) -> Result<Geohash, GeohashError> {
+        return Geohash::new(try!(hash_from_string(string)));
+    }
+}
+
+/// How a region overlaps a geohash cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlap {
+    /// The cell is entirely outside the region
+    Outside,
+
+    /// The cell is partly inside the region
+    Partial,
+
+    /// The cell is entirely inside the region
+    Inside,
+}
+
+/// A region which can be covered with geohashes by `cover()`.
+pub trait CoverRegion {
+    /// How this region overlaps a cell. `Partial` is always a safe answer,
+    /// but the region is then covered with more hashes than it needs.
+    fn overlap(&self, cell: &Bounds) -> Overlap;
+}
+
+/// A rectangular region. If `x_min` is greater than `x_max` the region
+/// wraps around, e.g. a box of longitudes crossing the antimeridian.
+impl CoverRegion for Bounds {
+    fn overlap(&self, cell: &Bounds) -> Overlap {
+        if self.x_min > self.x_max {
+            let east = Bounds::new(self.x_min, INFINITY, self.y_min, self.y_max);
+            let west = Bounds::new(NEG_INFINITY, self.x_max, self.y_min, self.y_max);
+            return match (east.overlap(cell), west.overlap(cell)) {
+                (Overlap::Inside, _) | (_, Overlap::Inside) => Overlap::Inside,
+                (Overlap::Outside, Overlap::Outside) => Overlap::Outside,
+                _ => Overlap::Partial,
+            };
+        }
+
+        // cells which touch the region are partial, as positions on the
+        // edge of a cell are hashed into either one of the cells sharing it.
+        if cell.x_min > self.x_max || cell.x_max < self.x_min || cell.y_min > self.y_max ||
+            cell.y_max < self.y_min
+        {
+            return Overlap::Outside;
+        }
+
+        if cell.x_min >= self.x_min && cell.x_max <= self.x_max && cell.y_min >= self.y_min &&
+            cell.y_max <= self.y_max
+        {
+            return Overlap::Inside;
+        }
+
+        return Overlap::Partial;
+    }
+}
+
+/// A circular region on the surface of the earth, everything within a
+/// distance of its centre. Covered in latitude/longitude `LATLON_BOUNDS`.
+#[derive(Debug, Clone, Copy)]
+pub struct SphericalCap {
+    lat: f64,
+    lon: f64,
+    angle: f64,
+}
+
+impl SphericalCap {
+    /// A cap of `radius` (in meters along the surface) around `centre`.
+    pub fn new(centre: &SphericalCoordinate, radius: f64) -> SphericalCap {
+        return SphericalCap {
+            lat: centre.lat().to_radians(),
+            lon: centre.lon().to_radians(),
+            angle: radius / EARTH_MSL_RADIUS,
+        };
+    }
+
+    /// Angle (in radians) between the centre and a position (in degrees).
+    fn angle_to(&self, lat: f64, lon: f64) -> f64 {
+        let lat = lat.to_radians();
+        let cos_angle = self.lat.sin() * lat.sin() +
+            self.lat.cos() * lat.cos() * (lon.to_radians() - self.lon).cos();
+        return cos_angle.max(-1.0).min(1.0).acos();
+    }
+
+    /// The smallest angle (in radians) between the centre and a cell.
+    fn min_angle(&self, cell: &Bounds) -> f64 {
+        let lat = self.lat.to_degrees();
+        let lon = self.lon.to_degrees();
+        let in_lons = lon >= cell.x_min && lon <= cell.x_max;
+        if in_lons && lat >= cell.y_min && lat <= cell.y_max {
+            return 0.0;
+        }
+
+        let mut angle = INFINITY;
+        for &x in &[cell.x_min, cell.x_max] {
+            for &y in &[cell.y_min, cell.y_max] {
+                angle = angle.min(self.angle_to(y, x));
+            }
+        }
+
+        // the closest point of a parallel is on the centre's meridian
+        if in_lons {
+            angle = angle.min((lat - cell.y_min).abs().to_radians());
+            angle = angle.min((lat - cell.y_max).abs().to_radians());
+        }
+
+        // along a meridian the angle is smallest at one latitude
+        for &x in &[cell.x_min, cell.x_max] {
+            let closest = self.lat
+                .sin()
+                .atan2(self.lat.cos() * (x.to_radians() - self.lon).cos())
+                .to_degrees();
+            if closest >= cell.y_min && closest <= cell.y_max {
+                angle = angle.min(self.angle_to(closest, x));
+            }
+        }
+
+        return angle;
+    }
+}
+
+impl CoverRegion for SphericalCap {
+    fn overlap(&self, cell: &Bounds) -> Overlap {
+        if self.angle >= PI {
+            return Overlap::Inside;
+        }
+
+        if self.min_angle(cell) > self.angle {
+            return Overlap::Outside;
+        }
+
+        // the furthest point of a cell is one of its corners, unless the
+        // antipode of the centre is in it.
+        let antipode = Vector2::new(
+            if self.lon > 0.0 { self.lon - PI } else { self.lon + PI }.to_degrees(),
+            -self.lat.to_degrees(),
+        );
+        if cell.contains(&antipode) {
+            return Overlap::Partial;
+        }
+
+        for &x in &[cell.x_min, cell.x_max] {
+            for &y in &[cell.y_min, cell.y_max] {
+                if self.angle_to(y, x) > self.angle {
+                    return Overlap::Partial;
+                }
+            }
+        }
+
+        return Overlap::Inside;
+    }
+}
+
+/// A polygon of latitude/longitude positions (x is the longitude), whose
+/// edges are straight lines in latitude/longitude.
+///
+/// A polygon crossing the antimeridian continues past ±180 degrees, e.g.
+/// from 170 to 190, and a polygon containing a pole includes it as an
+/// edge, e.g. from (-180, 80) to (180, 80) to (180, 90) to (-180, 90).
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    /// The vertices of the polygon, the last of which is joined to the
+    /// first.
+    pub vertices: Vec<Vector2<f64>>,
+}
+
+impl Polygon {
+    /// Constructor for `Polygon`.
+    pub fn new(vertices: Vec<Vector2<f64>>) -> Polygon {
+        return Polygon { vertices: vertices };
+    }
+
+    /// The edges of the polygon.
+    fn edges<'a>(&'a self) -> Box<Iterator<Item = (&'a Vector2<f64>, &'a Vector2<f64>)> + 'a> {
+        let next = self.vertices.iter().cycle().skip(1);
+        return Box::new(self.vertices.iter().zip(next));
+    }
+
+    /// Whether a position is inside the polygon, by counting the edges
+    /// which a ray from it crosses.
+    fn contains(&self, point: &Vector2<f64>) -> bool {
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            if (a.y > point.y) != (b.y > point.y) &&
+                point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+            {
+                inside = !inside;
+            }
+        }
+
+        return inside;
+    }
+
+    /// How this polygon overlaps a cell, without wrapping around.
+    fn overlap_unwrapped(&self, cell: &Bounds) -> Overlap {
+        if self.edges().any(|(a, b)| segment_intersects(a, b, cell)) {
+            return Overlap::Partial;
+        }
+
+        // with no edges crossing the cell, it is either entirely inside or
+        // entirely outside.
+        if self.contains(&cell.mid()) {
+            return Overlap::Inside;
+        }
+
+        return Overlap::Outside;
+    }
+}
+
+impl CoverRegion for Polygon {
+    fn overlap(&self, cell: &Bounds) -> Overlap {
+        let mut overlap = Overlap::Outside;
+        for &shift in &[-360.0, 0.0, 360.0] {
+            let shifted = Bounds::new(
+                cell.x_min + shift,
+                cell.x_max + shift,
+                cell.y_min,
+                cell.y_max,
+            );
+            match self.overlap_unwrapped(&shifted) {
+                Overlap::Inside => return Overlap::Inside,
+                Overlap::Partial => overlap = Overlap::Partial,
+                Overlap::Outside => {}
+            }
+        }
+
+        return overlap;
+    }
+}
+
+/// Whether the line segment from `a` to `b` touches a rectangle, by
+/// clipping it to the rectangle (the Liang-Barsky algorithm).
+fn segment_intersects(a: &Vector2<f64>, b: &Vector2<f64>, rect: &Bounds) -> bool {
+    let (mut t_min, mut t_max) = (0.0, 1.0);
+    let d = Vector2::new(b.x - a.x, b.y - a.y);
+    let sides = [
+        (-d.x, a.x - rect.x_min),
+        (d.x, rect.x_max - a.x),
+        (-d.y, a.y - rect.y_min),
+        (d.y, rect.y_max - a.y),
+    ];
+
+    for &(p, q) in &sides {
+        if p == 0.0 {
+            if q < 0.0 {
+                return false;
+            }
+            continue;
+        }
+
+        let t = q / p;
+        if p < 0.0 {
+            if t > t_max {
+                return false;
+            }
+            t_min = t.max(t_min);
+        } else {
+            if t < t_min {
+                return false;
+            }
+            t_max = t.min(t_max);
+        }
+    }
+
+    return true;
+}
+
+/// Split a cell into the two cells of the next precision.
+fn split_cell(geohash: u64, precision: u8, bounds: &Bounds) -> [(u64, Bounds); 2] {
+    let base = (geohash & !PRECISION_MASK) | (precision as u64);
+    let (mut low, mut high) = (bounds.clone(), bounds.clone());
+    let mid = bounds.mid();
+
+    // the bits alternate between x and y, starting with x
+    if precision % 2 == 0 {
+        low.x_max = mid.x;
+        high.x_min = mid.x;
+    } else {
+        low.y_max = mid.y;
+        high.y_min = mid.y;
+    }
+
+    return [(base, low), (base | 1 << (PRECISION_BITS + precision), high)];
+}
+
+/// Replace pairs of sibling hashes with their parent, finest first, so that
+/// no two hashes in a cover could be merged.
+fn merge_siblings(hashes: Vec<u64>) -> Vec<u64> {
+    let mut set: HashSet<u64> = hashes.into_iter().collect();
+
+    for precision in (PRECISION_MIN + 1..PRECISION_MAX + 1).rev() {
+        let bit = 1 << (PRECISION_BITS + precision - 1);
+        let level: Vec<u64> = set.iter()
+            .cloned()
+            .filter(|hash| hash_precision(*hash) == precision && hash & bit == 0)
+            .collect();
+
+        for hash in level {
+            if set.contains(&(hash | bit)) {
+                set.remove(&hash);
+                set.remove(&(hash | bit));
+                set.insert(parent(hash).expect("merged hashes have a parent"));
+            }
+        }
+    }
+
+    let mut merged: Vec<u64> = set.into_iter().collect();
+    merged.sort_by_key(|hash| (hash_precision(*hash), *hash));
+    return merged;
+}
+
+/// Cover a region with a set of geohashes of mixed precision, up to
+/// `max_precision` bits. The cells of the hashes overlap the region, and
+/// every position in the region is in one of them.
+///
+/// Cells entirely inside the region are kept at the coarsest precision
+/// which fits, and cells on its edge are split until `max_precision`, or
+/// until splitting them could give more than `max_hashes` hashes (which
+/// should be at least 2). Hashes are ordered by precision, coarsest first.
+///
+/// # Example
+///
+/// ```
+/// # extern crate nalgebra;
+/// # extern crate oldnav_lib;
+/// # use oldnav_lib::navdata::geohash::*;
+/// use nalgebra::Vector2;
+/// # fn main() {
+/// // a box crossing the antimeridian
+/// let region = Bounds::new(170.0, -170.0, -10.0, 10.0);
+/// let hashes = cover(&region, &LATLON_BOUNDS, 20, 16).unwrap();
+/// assert!(hashes.len() <= 16);
+///
+/// let p = Vector2::new(-175.0, 5.0);
+/// assert!(hashes.iter().any(|hash| decode(*hash, &LATLON_BOUNDS).unwrap().contains(&p)));
+/// # }
+/// ```
+pub fn cover<R: CoverRegion>(
+    region: &R,
+    range: &Bounds,
+    max_precision: u8,
+    max_hashes: usize,
+) -> Result<Vec<u64>, GeohashError> {
+    try!(check_precision(max_precision, PRECISION_MIN, PRECISION_MAX));
+
+    let mut hashes: Vec<u64> = Vec::new();
+    let mut cells: Vec<(u64, Bounds)> = split_cell(0, 0, range).to_vec();
+    let mut precision = PRECISION_MIN;
+
+    loop {
+        let mut partial: Vec<(u64, Bounds)> = Vec::new();
+        for (hash, bounds) in cells {
+            match region.overlap(&bounds) {
+                Overlap::Outside => {}
+                Overlap::Inside => hashes.push(hash),
+                Overlap::Partial => partial.push((hash, bounds)),
+            }
+        }
 
-    let mut np = Vector2::new(
-        midpoint.x + b.x_range() * (x_dir as f64),
-        midpoint.y + b.y_range() * (y_dir as f64),
-    );
+        if precision >= max_precision || hashes.len() + partial.len() * 2 > max_hashes {
+            hashes.extend(partial.iter().map(|&(hash, _)| hash));
+            break;
+        }
 
-    if spherical {
-        np.spherical_rectify(range)
+        cells = partial
+            .iter()
+            .flat_map(|&(hash, ref bounds)| split_cell(hash, precision, bounds).to_vec())
+            .collect();
+        precision += 1;
     }
 
-    return encode(&np, precision, range);
+    return Ok(merge_siblings(hashes));
+}
+
+/// Cover a rectangular region of `range` with geohashes, see `cover()`.
+pub fn cover_bounds(
+    region: &Bounds,
+    range: &Bounds,
+    max_precision: u8,
+    max_hashes: usize,
+) -> Result<Vec<u64>, GeohashError> {
+    return cover(region, range, max_precision, max_hashes);
+}
+
+/// Cover everything within `radius` (in meters) of `centre` with
+/// latitude/longitude geohashes, see `cover()`.
+pub fn cover_cap(
+    centre: &SphericalCoordinate,
+    radius: f64,
+    max_precision: u8,
+    max_hashes: usize,
+) -> Result<Vec<u64>, GeohashError> {
+    let cap = SphericalCap::new(centre, radius);
+    return cover(&cap, &LATLON_BOUNDS, max_precision, max_hashes);
+}
+
+/// Cover a latitude/longitude `Polygon` with geohashes, see `cover()`.
+pub fn cover_polygon(
+    polygon: &Polygon,
+    max_precision: u8,
+    max_hashes: usize,
+) -> Result<Vec<u64>, GeohashError> {
+    return cover(polygon, &LATLON_BOUNDS, max_precision, max_hashes);
 }
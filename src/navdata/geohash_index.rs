@@ -0,0 +1,357 @@
+//! A spatial index of any `Geohashable` items, stored in an array sorted by
+//! their geohash.
+//!
+//! Each item is keyed by its position along the Z-order curve at
+//! `PRECISION_MAX`, see `geohash::morton_key()`. The items within any
+//! geohash cell then have a contiguous range of keys, so a region query
+//! covers the region with geohashes, and binary searches the array for the
+//! range of each of them. This gives the candidates, a superset of the
+//! items in the region, which are then filtered by their exact position.
+//!
+//! The items may be keyed along the Hilbert curve instead, see
+//! `navdata::hilbert`, which maps a region onto fewer ranges of keys.
+
+use navdata::coord::{SphericalCoordinate, METERS_PER_NAUTICAL_MILE};
+use navdata::geohash::{self, Bounds, GeohashError, Geohashable, LATLON_BOUNDS};
+use navdata::geohash::PRECISION_MAX;
+use navdata::hilbert;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// The maximum number of geohashes used to cover the region of a query.
+/// More hashes fit the region more closely, giving fewer candidates to
+/// filter, but there are more ranges of the array to search.
+pub static GEOHASH_INDEX_COVER_HASHES: usize = 32;
+
+/// The curve along which the items of a `GeohashIndex` are sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexCurve {
+    /// The Z-order curve of the geohash, see `geohash::morton_key()`
+    ZOrder,
+
+    /// The Hilbert curve, see `hilbert::key()`
+    Hilbert,
+}
+
+/// A spatial index of `Geohashable` items, sorted by geohash.
+///
+/// # Examples
+///
+/// ```
+/// # use oldnav_lib::navdata::coord::SphericalCoordinate;
+/// # use oldnav_lib::navdata::geohash_index::GeohashIndex;
+/// # use oldnav_lib::navdata::waypoint::{Waypoint, WaypointInterface};
+/// # use std::sync::Arc;
+/// let wendy = SphericalCoordinate::from_geographic(0.0, -37.5, 145.0);
+/// let ml = SphericalCoordinate::from_geographic(0.0, -37.66, 144.84);
+/// let index = GeohashIndex::build(vec![
+///     Arc::new(Waypoint::new("WENDY", "WENDY", wendy, None)),
+///     Arc::new(Waypoint::new("ML", "MELBOURNE", ml, None)),
+/// ]).unwrap();
+///
+/// let aircraft = SphericalCoordinate::from_geographic(0.0, -37.7, 144.8);
+/// let found = index.within_radius_nm(&aircraft, 10.0, |waypoint| *waypoint.pos());
+/// assert_eq!(1, found.len());
+/// assert_eq!("ML", found[0].1.code);
+/// ```
+pub struct GeohashIndex<T> {
+    /// the curve along which the keys are
+    curve: IndexCurve,
+
+    /// items and their keys, sorted by key
+    entries: Vec<(u64, T)>,
+}
+
+impl<T> GeohashIndex<T> {
+    /// An empty index, along the Z-order curve.
+    pub fn new() -> GeohashIndex<T> {
+        return GeohashIndex::with_curve(IndexCurve::ZOrder);
+    }
+
+    /// An empty index, along the given curve.
+    pub fn with_curve(curve: IndexCurve) -> GeohashIndex<T> {
+        return GeohashIndex {
+            curve: curve,
+            entries: Vec::new(),
+        };
+    }
+
+    /// An index of `items` along the Z-order curve, sorted once after they
+    /// are all hashed, which is quicker than inserting them one at a time.
+    pub fn build<I, D>(items: I) -> Result<GeohashIndex<T>, GeohashError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Geohashable<D>,
+    {
+        return GeohashIndex::build_with_curve(items, IndexCurve::ZOrder);
+    }
+
+    /// An index of `items` along the given curve, see `build()`.
+    pub fn build_with_curve<I, D>(
+        items: I,
+        curve: IndexCurve,
+    ) -> Result<GeohashIndex<T>, GeohashError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Geohashable<D>,
+    {
+        let mut index = GeohashIndex::with_curve(curve);
+        for item in items {
+            let key = try!(index.key(&item));
+            index.entries.push((key, item));
+        }
+
+        index.entries.sort_by(|a, b| a.0.cmp(&b.0));
+        return Ok(index);
+    }
+
+    /// The curve along which the items of this index are sorted.
+    pub fn curve(&self) -> IndexCurve {
+        return self.curve;
+    }
+
+    /// The number of items in this index.
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    /// Whether this index is empty.
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    /// The items in this index, in the order of their keys.
+    pub fn iter<'a>(&'a self) -> Box<Iterator<Item = &'a T> + 'a> {
+        return Box::new(self.entries.iter().map(|entry| &entry.1));
+    }
+
+    /// Add an item to this index, after any items with the same key.
+    pub fn insert<D>(&mut self, item: T) -> Result<(), GeohashError>
+    where
+        T: Geohashable<D>,
+    {
+        let key = try!(self.key(&item));
+        let i = self.upper_bound(key);
+        self.entries.insert(i, (key, item));
+        return Ok(());
+    }
+
+    /// Remove an item equal to `item` from this index, returning it if it
+    /// was found.
+    pub fn remove<D>(&mut self, item: &T) -> Option<T>
+    where
+        T: Geohashable<D> + PartialEq,
+    {
+        return self.remove_by(item, |a, b| a == b);
+    }
+
+    /// Remove an item which `is_same` as `item` from this index, returning
+    /// it if it was found. Only the items with the same key as `item` are
+    /// compared, e.g. with `Arc::ptr_eq` for shared waypoints.
+    pub fn remove_by<D, F>(&mut self, item: &T, is_same: F) -> Option<T>
+    where
+        T: Geohashable<D>,
+        F: Fn(&T, &T) -> bool,
+    {
+        let key = self.key(item).ok()?;
+        let mut i = self.lower_bound(key);
+        while i < self.entries.len() && self.entries[i].0 == key {
+            if is_same(&self.entries[i].1, item) {
+                return Some(self.entries.remove(i).1);
+            }
+            i += 1;
+        }
+
+        return None;
+    }
+
+    /// The ranges of keys of the items within the cells of a set of
+    /// geohashes, in order, with overlapping and adjacent ranges joined.
+    /// The hashes must be of `LATLON_BOUNDS`, as used by `Geohashable`.
+    pub fn key_ranges(&self, hashes: &[u64]) -> Vec<(u64, u64)> {
+        let mut ranges: Vec<(u64, u64)> = Vec::with_capacity(hashes.len());
+        for hash in hashes {
+            match self.curve {
+                IndexCurve::ZOrder => ranges.push(geohash::morton_key_range(*hash)),
+                IndexCurve::Hilbert => {
+                    // a Hilbert cell is square, so a cell with an odd precision
+                    // is covered by its two children
+                    let cells = if geohash::hash_precision(*hash) % 2 == 0 {
+                        vec![*hash]
+                    } else {
+                        geohash::children(*hash)
+                            .expect("a hash of odd precision has children")
+                            .to_vec()
+                    };
+
+                    for cell in cells {
+                        let hilbert_hash = hilbert::from_geohash(cell)
+                            .expect("the precision of the cell is even");
+                        ranges.push(hilbert::key_range(hilbert_hash));
+                    }
+                }
+            }
+        }
+        ranges.sort();
+
+        let mut joined: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+        for (first, last) in ranges {
+            if let Some(previous) = joined.last_mut() {
+                if first <= previous.1.saturating_add(1) {
+                    previous.1 = previous.1.max(last);
+                    continue;
+                }
+            }
+            joined.push((first, last));
+        }
+
+        return joined;
+    }
+
+    /// The items within the cells of a set of geohashes, such as a cover
+    /// from `geohash::cover()`. The hashes must be of `LATLON_BOUNDS`, as
+    /// used by `Geohashable`.
+    pub fn scan(&self, hashes: &[u64]) -> Vec<&T> {
+        let mut found = Vec::new();
+        for (first, last) in self.key_ranges(hashes) {
+            let mut i = self.lower_bound(first);
+            while i < self.entries.len() && self.entries[i].0 <= last {
+                found.push(&self.entries[i].1);
+                i += 1;
+            }
+        }
+
+        return found;
+    }
+
+    /// The candidates for the items within `radius` (in meters) of
+    /// `centre`: every item within the radius, and some outside of it.
+    pub fn candidates_within_radius(
+        &self,
+        centre: &SphericalCoordinate,
+        radius: f64,
+    ) -> Vec<&T> {
+        let hashes = geohash::cover_cap(centre, radius, PRECISION_MAX, GEOHASH_INDEX_COVER_HASHES)
+            .expect("cover precision is within range");
+        return self.scan(&hashes);
+    }
+
+    /// The items within `radius` (in meters) of `centre`, and their
+    /// distances, closest first. The candidates are filtered by the
+    /// distance to their exact `position`.
+    pub fn within_radius<F>(
+        &self,
+        centre: &SphericalCoordinate,
+        radius: f64,
+        position: F,
+    ) -> Vec<(f64, &T)>
+    where
+        F: Fn(&T) -> SphericalCoordinate,
+    {
+        let mut found: Vec<(f64, &T)> = self.candidates_within_radius(centre, radius)
+            .into_iter()
+            .map(|item| (position(item).arc_distance(centre), item))
+            .filter(|&(distance, _)| distance <= radius)
+            .collect();
+
+        found.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        return found;
+    }
+
+    /// The items within `radius` (in nautical miles) of `centre`, see
+    /// `within_radius()`.
+    pub fn within_radius_nm<F>(
+        &self,
+        centre: &SphericalCoordinate,
+        radius: f64,
+        position: F,
+    ) -> Vec<(f64, &T)>
+    where
+        F: Fn(&T) -> SphericalCoordinate,
+    {
+        return self.within_radius(centre, radius * METERS_PER_NAUTICAL_MILE, position);
+    }
+
+    /// The candidates for the items within a latitude/longitude box, where
+    /// x is the longitude and y the latitude: every item within the box,
+    /// and some outside of it. A box with an `x_min` greater than its
+    /// `x_max` crosses the antimeridian.
+    pub fn candidates_in_bounds(&self, bounds: &Bounds) -> Vec<&T> {
+        let hashes = geohash::cover_bounds(
+            bounds,
+            &LATLON_BOUNDS,
+            PRECISION_MAX,
+            GEOHASH_INDEX_COVER_HASHES,
+        ).expect("cover precision is within range");
+        return self.scan(&hashes);
+    }
+
+    /// The items within a latitude/longitude box, see
+    /// `candidates_in_bounds()`. The candidates are filtered by their exact
+    /// `position`.
+    pub fn within_bounds<F>(&self, bounds: &Bounds, position: F) -> Vec<&T>
+    where
+        F: Fn(&T) -> SphericalCoordinate,
+    {
+        let wraps = bounds.x_min > bounds.x_max;
+        return self.candidates_in_bounds(bounds)
+            .into_iter()
+            .filter(|item| {
+                let pos = position(item);
+                let (lat, lon) = (pos.lat(), pos.lon());
+                let in_lons = if wraps {
+                    lon >= bounds.x_min || lon <= bounds.x_max
+                } else {
+                    lon >= bounds.x_min && lon <= bounds.x_max
+                };
+                in_lons && lat >= bounds.y_min && lat <= bounds.y_max
+            })
+            .collect();
+    }
+
+    /// The key of an item along the curve of this index, from its geohash
+    /// at `PRECISION_MAX`.
+    fn key<D>(&self, item: &T) -> Result<u64, GeohashError>
+    where
+        T: Geohashable<D>,
+    {
+        let gh = try!(item.integer_encode(PRECISION_MAX));
+        return match self.curve {
+            IndexCurve::ZOrder => Ok(geohash::morton_key(gh)),
+            IndexCurve::Hilbert => Ok(hilbert::key(try!(hilbert::from_geohash(gh)))),
+        };
+    }
+
+    /// The index of the first entry with a key of at least `key`.
+    fn lower_bound(&self, key: u64) -> usize {
+        return match self.entries.binary_search_by(|entry| if entry.0 < key {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }) {
+            Ok(i) | Err(i) => i,
+        };
+    }
+
+    /// The index of the first entry with a key greater than `key`.
+    fn upper_bound(&self, key: u64) -> usize {
+        return match self.entries.binary_search_by(|entry| if entry.0 <= key {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }) {
+            Ok(i) | Err(i) => i,
+        };
+    }
+}
+
+impl<T> fmt::Debug for GeohashIndex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "GeohashIndex: {{curve: {:?}, n_items: {}}}",
+            self.curve,
+            self.entries.len()
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! An integer Hilbert curve hash, an alternative to the Z-order geohash.
+//!
+//! A Hilbert hash has the same layout as an integer geohash: the low
+//! `PRECISION_BITS` store its precision, and the bits above them its
+//! position along the curve, with the coarsest bit lowest. As each pair of
+//! bits picks a quarter of the cell of the bits before it, the functions in
+//! `navdata::geohash` which work on the bits of a hash, such as
+//! `truncate()`, `is_ancestor_of()` and `morton_key()`, also work on
+//! Hilbert hashes, as long as the precisions stay even.
+//!
+//! Both hashes split the bounds into a grid of cells, but the Hilbert curve
+//! only steps between neighbouring cells, so cells which are close together
+//! are more often close together along the curve. A region then maps onto
+//! fewer ranges of the curve than it does with Z-order, which jumps across
+//! the seams between quadrants.
+//!
+//! The curve visits a square grid, so the precision of a Hilbert hash must
+//! be even, with the same number of bits along x and y.
+
+use nalgebra::Vector2;
+use navdata::geohash::{self, Bounds, GeohashError, Geohashable};
+use navdata::geohash::{PRECISION_BITS, PRECISION_MAX};
+
+/// An object which is able to be hashed along the Hilbert curve, as any
+/// `Geohashable` object is.
+pub trait HilbertHashable<T> {
+    /// decode this object of type `T` from an integer Hilbert hash
+    fn hilbert_decode(hash: u64) -> Result<T, GeohashError>;
+
+    /// encode this object of type `T` into an integer Hilbert hash
+    fn hilbert_encode(&self, precision: u8) -> Result<u64, GeohashError>;
+}
+
+impl<T, G: Geohashable<T>> HilbertHashable<T> for G {
+    fn hilbert_decode(hash: u64) -> Result<T, GeohashError> {
+        return G::integer_decode(to_geohash(hash));
+    }
+
+    fn hilbert_encode(&self, precision: u8) -> Result<u64, GeohashError> {
+        try!(check_precision(precision));
+        return from_geohash(try!(self.integer_encode(precision)));
+    }
+}
+
+/// Check that a precision is even, and in the range of a geohash.
+fn check_precision(precision: u8) -> Result<(), GeohashError> {
+    if precision < 2 || precision > PRECISION_MAX {
+        return Err(GeohashError::PrecisionOutOfRange {
+            precision: precision,
+            min: 2,
+            max: PRECISION_MAX,
+        });
+    }
+
+    if precision % 2 != 0 {
+        return Err(GeohashError::PrecisionNotMultiple {
+            precision: precision,
+            multiple: 2,
+        });
+    }
+
+    return Ok(());
+}
+
+/// Encode a `Vector2` position into an integer Hilbert hash of an even
+/// `precision`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate nalgebra;
+/// # extern crate oldnav_lib;
+/// # use oldnav_lib::navdata::geohash::LATLON_BOUNDS;
+/// # use oldnav_lib::navdata::hilbert;
+/// use nalgebra::Vector2;
+/// # fn main() {
+/// let p = Vector2::new(148.234, -38.12);
+/// let hash = hilbert::encode(&p, 40, &LATLON_BOUNDS).unwrap();
+/// assert!(hilbert::decode(hash, &LATLON_BOUNDS).unwrap().contains(&p));
+///
+/// assert!(hilbert::encode(&p, 39, &LATLON_BOUNDS).is_err());
+/// # }
+/// ```
+pub fn encode(
+    position: &Vector2<f64>,
+    precision: u8,
+    range: &Bounds,
+) -> Result<u64, GeohashError> {
+    try!(check_precision(precision));
+    return from_geohash(try!(geohash::encode(position, precision, range)));
+}
+
+/// Decode an integer Hilbert hash into the `Bounds` of its cell.
+pub fn decode(hash: u64, range: &Bounds) -> Result<Bounds, GeohashError> {
+    return geohash::decode(to_geohash(hash), range);
+}
+
+/// Convert an integer geohash of an even precision into the Hilbert hash
+/// of the same cell.
+///
+/// # Example
+///
+/// ```
+/// # use oldnav_lib::navdata::geohash::hash_from_string;
+/// # use oldnav_lib::navdata::hilbert;
+/// let gh = hash_from_string("1101").unwrap();
+/// let hash = hilbert::from_geohash(gh).unwrap();
+/// assert_eq!(gh, hilbert::to_geohash(hash));
+/// assert!(hilbert::from_geohash(hash_from_string("110").unwrap()).is_err());
+/// ```
+pub fn from_geohash(geohash: u64) -> Result<u64, GeohashError> {
+    let precision = geohash::hash_precision(geohash);
+    try!(check_precision(precision));
+
+    let (x, y, order, _) = geohash::cell_indices(geohash);
+    let d = xy_to_curve(x, y, order);
+    return Ok(reverse_distance(d, precision) << PRECISION_BITS | (precision - 1) as u64);
+}
+
+/// Convert an integer Hilbert hash into the geohash of the same cell.
+pub fn to_geohash(hash: u64) -> u64 {
+    let precision = geohash::hash_precision(hash);
+    let d = reverse_distance(hash >> PRECISION_BITS, precision);
+    let (x, y) = curve_to_xy(d, precision / 2);
+    return geohash::from_cell_indices(x, y, precision);
+}
+
+/// The position of the cell of a hash along the Hilbert curve, as a key of
+/// `PRECISION_MAX` bits. Sorting hashes of `PRECISION_MAX` by their keys
+/// sorts them along the curve, so that the hashes within any cell have a
+/// contiguous range of keys, see `key_range()`.
+///
+/// This is the same as `geohash::morton_key()`, as the hashes share their
+/// layout.
+pub fn key(hash: u64) -> u64 {
+    return geohash::morton_key(hash);
+}
+
+/// The first and last keys, see `key()`, of the hashes of `PRECISION_MAX`
+/// within the cell of `hash`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate nalgebra;
+/// # extern crate oldnav_lib;
+/// # use oldnav_lib::navdata::geohash::{LATLON_BOUNDS, PRECISION_MAX};
+/// # use oldnav_lib::navdata::hilbert;
+/// use nalgebra::Vector2;
+/// # fn main() {
+/// let p = Vector2::new(148.234, -38.12);
+/// let cell = hilbert::encode(&p, 10, &LATLON_BOUNDS).unwrap();
+/// let (first, last) = hilbert::key_range(cell);
+///
+/// let fine = hilbert::encode(&p, PRECISION_MAX, &LATLON_BOUNDS).unwrap();
+/// assert!(hilbert::key(fine) >= first && hilbert::key(fine) <= last);
+/// # }
+/// ```
+pub fn key_range(hash: u64) -> (u64, u64) {
+    return geohash::morton_key_range(hash);
+}
+
+/// Reverse the order of the first `precision` bits of `bits`, converting
+/// between a distance along the curve, with its coarsest bit highest, and
+/// the bits of a Hilbert hash, with its coarsest bit lowest.
+fn reverse_distance(bits: u64, precision: u8) -> u64 {
+    return geohash::reverse_bits(bits & ((1 << precision) - 1)) >> (64 - precision as u32);
+}
+
+/// The distance along the Hilbert curve of `order` (a grid of `2^order` by
+/// `2^order` cells) of the cell at `x`, `y`.
+fn xy_to_curve(x: u64, y: u64, order: u8) -> u64 {
+    let n: u64 = 1 << order;
+    let (mut x, mut y) = (x, y);
+    let mut d = 0;
+
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = if x & s > 0 { 1 } else { 0 };
+        let ry = if y & s > 0 { 1 } else { 0 };
+        d += s * s * ((3 * rx) ^ ry);
+        rotate(n, &mut x, &mut y, rx, ry);
+        s /= 2;
+    }
+
+    return d;
+}
+
+/// The cell at a distance `d` along the Hilbert curve of `order`, the
+/// inverse of `xy_to_curve()`.
+fn curve_to_xy(d: u64, order: u8) -> (u64, u64) {
+    let n: u64 = 1 << order;
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+
+    let mut s = 1;
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        rotate(s, &mut x, &mut y, rx, ry);
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+
+    return (x, y);
+}
+
+/// Rotate and flip a quadrant of `n` by `n` cells, so that the curve within
+/// it starts and ends at the right corners.
+fn rotate(n: u64, x: &mut u64, y: &mut u64, rx: u64, ry: u64) {
+    if ry == 0 {
+        if rx == 1 {
+            *x = n - 1 - *x;
+            *y = n - 1 - *y;
+        }
+
+        let t = *x;
+        *x = *y;
+        *y = t;
+    }
+}
@@ -0,0 +1,311 @@
+//! The kinds of waypoint stored in the `Database`, sets of them for
+//! filtering lookups, and the ARINC 424 waypoint type columns.
+
+use std::fmt;
+use std::ops::BitOr;
+
+/// The kind of a waypoint, as far as a user is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WaypointKind {
+    /// A fix outside of any terminal area
+    EnrouteFix,
+
+    /// A fix in the terminal area of an airport
+    TerminalFix,
+
+    /// A VOR, VOR-DME or VORTAC
+    Vor,
+
+    /// A non-directional beacon
+    Ndb,
+
+    /// A DME or TACAN which isn't part of a VOR
+    Dme,
+
+    /// A localizer, glideslope, marker or other approach navaid
+    Ils,
+
+    /// An airport
+    Airport,
+
+    /// A runway threshold
+    RunwayThreshold,
+
+    /// A waypoint defined by the user
+    User,
+
+    /// A fix named after its latitude and longitude, such as `5030N`
+    LatLon,
+}
+
+/// All the `WaypointKind`s, in the order of their bits in `WaypointKinds`.
+pub const WAYPOINT_KINDS: [WaypointKind; 10] = [
+    WaypointKind::EnrouteFix,
+    WaypointKind::TerminalFix,
+    WaypointKind::Vor,
+    WaypointKind::Ndb,
+    WaypointKind::Dme,
+    WaypointKind::Ils,
+    WaypointKind::Airport,
+    WaypointKind::RunwayThreshold,
+    WaypointKind::User,
+    WaypointKind::LatLon,
+];
+
+impl WaypointKind {
+    /// The bit of this kind in a `WaypointKinds`.
+    fn bit(self) -> u16 {
+        return 1 << (self as u16);
+    }
+}
+
+impl BitOr for WaypointKind {
+    type Output = WaypointKinds;
+
+    fn bitor(self, other: WaypointKind) -> WaypointKinds {
+        return WaypointKinds::from(self) | other;
+    }
+}
+
+/// A set of `WaypointKind`s, stored as a bitset.
+///
+/// # Examples
+///
+/// ```
+/// # use oldnav_lib::navdata::kind::{WaypointKind, WaypointKinds};
+/// let radio = WaypointKind::Vor | WaypointKind::Ndb;
+/// assert!(radio.contains(WaypointKind::Ndb));
+/// assert!(!radio.contains(WaypointKind::EnrouteFix));
+/// assert_eq!(2, radio.len());
+///
+/// let mut kinds = WaypointKinds::empty();
+/// kinds.insert(WaypointKind::Airport);
+/// assert_eq!(vec![WaypointKind::Airport], kinds.kinds());
+/// assert!(WaypointKinds::all().contains(WaypointKind::LatLon));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct WaypointKinds {
+    bits: u16,
+}
+
+impl WaypointKinds {
+    /// A set containing no kinds.
+    pub fn empty() -> WaypointKinds {
+        return WaypointKinds { bits: 0 };
+    }
+
+    /// A set containing every kind.
+    pub fn all() -> WaypointKinds {
+        let mut kinds = WaypointKinds::empty();
+        for kind in &WAYPOINT_KINDS {
+            kinds.insert(*kind);
+        }
+
+        return kinds;
+    }
+
+    /// The kinds of radio navaid: VORs, NDBs, DMEs and ILS components.
+    pub fn navaids() -> WaypointKinds {
+        return WaypointKind::Vor | WaypointKind::Ndb | WaypointKind::Dme | WaypointKind::Ils;
+    }
+
+    /// The kinds of fix, including user waypoints.
+    pub fn fixes() -> WaypointKinds {
+        return WaypointKind::EnrouteFix | WaypointKind::TerminalFix |
+            WaypointKind::RunwayThreshold | WaypointKind::User | WaypointKind::LatLon;
+    }
+
+    /// Whether `kind` is in this set.
+    pub fn contains(&self, kind: WaypointKind) -> bool {
+        return self.bits & kind.bit() != 0;
+    }
+
+    /// Add `kind` to this set.
+    pub fn insert(&mut self, kind: WaypointKind) {
+        self.bits |= kind.bit();
+    }
+
+    /// Remove `kind` from this set.
+    pub fn remove(&mut self, kind: WaypointKind) {
+        self.bits &= !kind.bit();
+    }
+
+    /// Whether this set contains no kinds.
+    pub fn is_empty(&self) -> bool {
+        return self.bits == 0;
+    }
+
+    /// The number of kinds in this set.
+    pub fn len(&self) -> usize {
+        return self.bits.count_ones() as usize;
+    }
+
+    /// The kinds in this set, in the order of `WAYPOINT_KINDS`.
+    pub fn kinds(&self) -> Vec<WaypointKind> {
+        return WAYPOINT_KINDS
+            .iter()
+            .cloned()
+            .filter(|kind| self.contains(*kind))
+            .collect();
+    }
+}
+
+impl From<WaypointKind> for WaypointKinds {
+    fn from(kind: WaypointKind) -> WaypointKinds {
+        return WaypointKinds { bits: kind.bit() };
+    }
+}
+
+impl BitOr<WaypointKind> for WaypointKinds {
+    type Output = WaypointKinds;
+
+    fn bitor(self, kind: WaypointKind) -> WaypointKinds {
+        return WaypointKinds { bits: self.bits | kind.bit() };
+    }
+}
+
+impl BitOr for WaypointKinds {
+    type Output = WaypointKinds;
+
+    fn bitor(self, other: WaypointKinds) -> WaypointKinds {
+        return WaypointKinds { bits: self.bits | other.bits };
+    }
+}
+
+impl fmt::Debug for WaypointKinds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "WaypointKinds {:?}", self.kinds());
+    }
+}
+
+/// The three ARINC 424 waypoint type columns (section 5.42), as stored for
+/// fixes in x-plane's `earth_fix.dat`.
+///
+/// The first column is the type of the waypoint, the second its function
+/// in a procedure or airspace, and the third the procedures which use it.
+/// A blank column is stored as a space.
+///
+/// # Examples
+///
+/// ```
+/// # use oldnav_lib::navdata::kind::ArincWaypointType;
+/// let waypoint_type = ArincWaypointType::new("WIF");
+/// assert!(waypoint_type.is_rnav());
+/// assert!(waypoint_type.is_initial_approach_fix());
+/// assert!(waypoint_type.is_approach());
+/// assert!(!waypoint_type.is_sid());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArincWaypointType {
+    /// Column 1: the type of waypoint, e.g. `R` for a named intersection
+    pub waypoint_type: char,
+
+    /// Column 2: the function of the waypoint, e.g. `A` for a final
+    /// approach fix
+    pub function: char,
+
+    /// Column 3: the procedures using the waypoint, `D` for SIDs, `E` for
+    /// STARs, `F` for approaches, or `Z` for several of them
+    pub usage: char,
+}
+
+impl ArincWaypointType {
+    /// Read the type columns from a string such as `"RU "`. Missing columns
+    /// are blank.
+    pub fn new(columns: &str) -> ArincWaypointType {
+        let mut chars = columns.chars();
+        let mut column = || chars.next().unwrap_or(' ');
+        return ArincWaypointType {
+            waypoint_type: column(),
+            function: column(),
+            usage: column(),
+        };
+    }
+
+    /// Whether this is an RNAV waypoint.
+    pub fn is_rnav(&self) -> bool {
+        return self.waypoint_type == 'W' || self.waypoint_type == 'C';
+    }
+
+    /// Whether this is a named intersection.
+    pub fn is_named_intersection(&self) -> bool {
+        return self.waypoint_type == 'R' || self.waypoint_type == 'C';
+    }
+
+    /// Whether this is a runway used as a waypoint.
+    pub fn is_runway(&self) -> bool {
+        return self.waypoint_type == 'G';
+    }
+
+    /// Whether this is a VFR waypoint.
+    pub fn is_vfr(&self) -> bool {
+        return self.waypoint_type == 'V';
+    }
+
+    /// Whether this is a full or half degree latitude/longitude
+    /// intersection.
+    pub fn is_lat_lon(&self) -> bool {
+        return self.function == 'V' || self.function == 'W';
+    }
+
+    /// Whether this is an initial approach fix.
+    pub fn is_initial_approach_fix(&self) -> bool {
+        return "IBKN".contains(self.function);
+    }
+
+    /// Whether this is a final approach fix.
+    pub fn is_final_approach_fix(&self) -> bool {
+        return self.function == 'A' || self.function == 'B';
+    }
+
+    /// Whether this is a missed approach fix.
+    pub fn is_missed_approach_fix(&self) -> bool {
+        return self.function == 'M' || self.function == 'N';
+    }
+
+    /// Whether this waypoint is used by a SID.
+    pub fn is_sid(&self) -> bool {
+        return self.usage == 'D' || self.usage == 'Z';
+    }
+
+    /// Whether this waypoint is used by a STAR.
+    pub fn is_star(&self) -> bool {
+        return self.usage == 'E' || self.usage == 'Z';
+    }
+
+    /// Whether this waypoint is used by an approach.
+    pub fn is_approach(&self) -> bool {
+        return self.usage == 'F' || self.usage == 'Z';
+    }
+}
+
+/// Whether `code` is an ARINC 424 latitude/longitude waypoint ident (section
+/// 5.13), such as `5030N`, `50N30` or `N5030`.
+///
+/// # Examples
+///
+/// ```
+/// # use oldnav_lib::navdata::kind::is_lat_lon_code;
+/// assert!(is_lat_lon_code("1630N"));
+/// assert!(is_lat_lon_code("75N70"));
+/// assert!(is_lat_lon_code("H5030"));
+/// assert!(!is_lat_lon_code("WENDY"));
+/// assert!(!is_lat_lon_code("07EBA"));
+/// ```
+pub fn is_lat_lon_code(code: &str) -> bool {
+    let chars: Vec<char> = code.chars().collect();
+    if chars.len() != 5 {
+        return false;
+    }
+
+    let letters: Vec<usize> = (0..5).filter(|&i| !chars[i].is_digit(10)).collect();
+    if letters.len() != 1 {
+        return false;
+    }
+
+    return match letters[0] {
+        0 => "NESWH".contains(chars[0]),
+        2 | 4 => "NESW".contains(chars[letters[0]]),
+        _ => false,
+    };
+}
@@ -0,0 +1,201 @@
+//! Loading a `Database` in the background.
+//!
+//! Loading a full set of navdata takes a while, too long to block the
+//! x-plane flight loop for. A `DatabaseLoader` loads the database on a
+//! worker thread instead, and the flight loop polls it for progress until
+//! the database is handed over.
+
+use navdata::database::{Database, DatabaseError, LoadMode};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// How far loading has got through one of the files being read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadProgress {
+    /// Path of the file being read
+    pub path: String,
+
+    /// Number of records loaded from the file so far
+    pub records: usize,
+
+    /// Number of bytes of the file read so far
+    pub bytes_read: u64,
+
+    /// Size of the file (in bytes)
+    pub total_bytes: u64,
+}
+
+impl LoadProgress {
+    /// The fraction of the file which has been read, from 0 to 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::loader::LoadProgress;
+    /// let progress = LoadProgress {
+    ///     path: String::from("earth_fix.dat"),
+    ///     records: 100,
+    ///     bytes_read: 250,
+    ///     total_bytes: 1000,
+    /// };
+    /// assert_eq!(0.25, progress.fraction());
+    /// ```
+    pub fn fraction(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 1.0;
+        }
+
+        return self.bytes_read as f64 / self.total_bytes as f64;
+    }
+}
+
+/// Called with the progress while a `Database` is loaded, see
+/// `Database::load_with_progress()`. Returning `false` cancels loading.
+pub type ProgressCallback<'a> = FnMut(&LoadProgress) -> bool + 'a;
+
+/// The messages sent from the worker thread to the `DatabaseLoader`.
+#[derive(Debug)]
+enum LoaderMessage {
+    Progress(LoadProgress),
+    Finished(Result<Database, DatabaseError>),
+}
+
+/// Loads a `Database` on a worker thread.
+///
+/// The loader is polled with `poll()`, which doesn't block, so it can be
+/// called from the flight loop each frame. Dropping the loader before the
+/// database has been handed over cancels loading.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use std::path::PathBuf;
+/// # use oldnav_lib::navdata::database::LoadMode;
+/// # use oldnav_lib::navdata::loader::DatabaseLoader;
+/// let mut loader = DatabaseLoader::spawn(
+///     PathBuf::from("navdata"),
+///     PathBuf::from("resources"),
+///     LoadMode::Strict,
+/// );
+///
+/// // each flight loop callback
+/// match loader.poll() {
+///     Some(Ok(db)) => println!("Loaded {}", db),
+///     Some(Err(error)) => println!("Unable to load navdata: {}", error),
+///     None => {
+///         if let Some(progress) = loader.progress() {
+///             println!("Reading {} ({:.0}%)", progress.path, progress.fraction() * 100.0);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DatabaseLoader {
+    receiver: Receiver<LoaderMessage>,
+    cancelled: Arc<AtomicBool>,
+    progress: Option<LoadProgress>,
+    finished: bool,
+}
+
+impl DatabaseLoader {
+    /// Start loading the navdata in `navdata_dir` on a new thread, as
+    /// `Database::load()` would.
+    pub fn spawn(navdata_dir: PathBuf, resources_dir: PathBuf, mode: LoadMode) -> DatabaseLoader {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let thread_cancelled = cancelled.clone();
+
+        thread::spawn(move || {
+            let result = Database::load_with_progress(
+                navdata_dir,
+                resources_dir,
+                mode,
+                &mut |progress| {
+                    // the loader may already have been dropped, in which
+                    // case loading is cancelled anyway.
+                    let _ = sender.send(LoaderMessage::Progress(progress.clone()));
+                    return !thread_cancelled.load(Ordering::SeqCst);
+                },
+            );
+
+            let _ = sender.send(LoaderMessage::Finished(result));
+        });
+
+        return DatabaseLoader {
+            receiver: receiver,
+            cancelled: cancelled,
+            progress: None,
+            finished: false,
+        };
+    }
+
+    /// Ask the worker thread to stop loading. It stops at the next progress
+    /// report, and the result is then `DatabaseError::Cancelled`.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// The latest progress received by `poll()`.
+    pub fn progress(&self) -> Option<&LoadProgress> {
+        return self.progress.as_ref();
+    }
+
+    /// Whether the result has been handed over by `poll()` or `wait()`.
+    pub fn is_finished(&self) -> bool {
+        return self.finished;
+    }
+
+    /// Check on the worker thread without blocking.
+    ///
+    /// Returns the loaded `Database` (or the error which stopped it from
+    /// loading) once, when the worker thread has finished, and `None`
+    /// before and after that.
+    pub fn poll(&mut self) -> Option<Result<Database, DatabaseError>> {
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            match self.receiver.try_recv() {
+                Ok(LoaderMessage::Progress(progress)) => self.progress = Some(progress),
+                Ok(LoaderMessage::Finished(result)) => {
+                    self.finished = true;
+                    return Some(result);
+                }
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    self.finished = true;
+                    return Some(Err(DatabaseError::LoaderPanicked));
+                }
+            }
+        }
+    }
+
+    /// Block until the worker thread has finished, and return its result.
+    pub fn wait(mut self) -> Result<Database, DatabaseError> {
+        loop {
+            match self.receiver.recv() {
+                Ok(LoaderMessage::Progress(progress)) => self.progress = Some(progress),
+                Ok(LoaderMessage::Finished(result)) => {
+                    self.finished = true;
+                    return result;
+                }
+                Err(_) => {
+                    self.finished = true;
+                    return Err(DatabaseError::LoaderPanicked);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DatabaseLoader {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.cancel();
+        }
+    }
+}
@@ -1,11 +1,31 @@
 //! Navigation data functionality.
 //!
 
+pub mod airac;
 pub mod airport;
 pub mod coord;
+pub mod geodesic;
 pub mod waypoint;
+pub mod kind;
+pub mod navaid;
 pub mod country;
 pub mod route;
+pub mod airway;
+pub mod procedure;
 pub mod database;
+pub mod cache;
+pub mod loader;
+pub mod overlay;
+pub mod report;
 pub mod multihash;
 pub mod geohash;
+pub mod geohash_index;
+pub mod hilbert;
+pub mod spatial;
+
+/// Address of the object behind an `Arc`, used to identify it, e.g. to find
+/// its index while writing the cache.
+pub(crate) fn address<T: ?Sized>(object: &T) -> usize {
+    let pointer: *const T = object;
+    return pointer as *const u8 as usize;
+}
@@ -7,7 +7,7 @@ use std::hash::Hash;
 use std::clone::Clone;
 use std::fmt;
 
-/// Not currently used
+/// A map from each key to all the values associated with it.
 pub struct MultiHash<K, V> {
     map: HashMap<K, Vec<V>>,
 }
@@ -1 +1,272 @@
-//! A module with methods for `Navaid` and other associated functions.
\ No newline at end of file
+//! A module with methods for `Navaid` and other associated functions.
+
+use navdata::country::Country;
+use navdata::kind::WaypointKind;
+use navdata::waypoint::Waypoint;
+use navdata::waypoint::WaypointInterface;
+use navdata::coord::SphericalCoordinate;
+use std::fmt;
+use std::sync::Arc;
+
+/// The different types of radio navigation aid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NavaidType {
+    /// Non-directional beacon
+    Ndb,
+
+    /// VHF omnidirectional range
+    Vor,
+
+    /// VOR with a co-located DME
+    VorDme,
+
+    /// VOR with a co-located TACAN
+    Vortac,
+
+    /// Distance measuring equipment, either stand alone or the DME element
+    /// of a VOR-DME, VORTAC, NDB-DME or ILS.
+    Dme,
+
+    /// Military tactical air navigation system
+    Tacan,
+
+    /// Localizer component of an instrument landing system
+    IlsLocalizer,
+
+    /// Localizer component of a localizer only approach (LOC, LDA or SDF)
+    Localizer,
+
+    /// Glideslope component of an instrument landing system
+    Glideslope,
+
+    /// Outer marker
+    OuterMarker,
+
+    /// Middle marker
+    MiddleMarker,
+
+    /// Inner marker
+    InnerMarker,
+
+    /// Final approach path alignment point of an SBAS or GBAS approach
+    Fpap,
+
+    /// GBAS differential ground station of a GLS
+    Gls,
+
+    /// Landing threshold point or fictitious threshold point of an SBAS or
+    /// GBAS approach
+    Threshold,
+}
+
+impl NavaidType {
+    /// Determine the `NavaidType` from the row code used in x-plane's
+    /// `earth_nav.dat`, and the name of the navaid (which is needed to
+    /// distinguish a VOR-DME or VORTAC from a plain VOR, and a TACAN from
+    /// a plain DME).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::navaid::NavaidType;
+    /// assert_eq!(Some(NavaidType::Ndb), NavaidType::from_row_code(2, "A P HILL NDB"));
+    /// assert_eq!(Some(NavaidType::Vortac), NavaidType::from_row_code(3, "SEATTLE VORTAC"));
+    /// assert_eq!(None, NavaidType::from_row_code(99, ""));
+    /// ```
+    pub fn from_row_code(row_code: u32, name: &str) -> Option<NavaidType> {
+        let navaid_type = match row_code {
+            2 => NavaidType::Ndb,
+            3 => {
+                if name.ends_with("VORTAC") {
+                    NavaidType::Vortac
+                } else if name.ends_with("VOR-DME") {
+                    NavaidType::VorDme
+                } else {
+                    NavaidType::Vor
+                }
+            }
+            4 => NavaidType::IlsLocalizer,
+            5 => NavaidType::Localizer,
+            6 => NavaidType::Glideslope,
+            7 => NavaidType::OuterMarker,
+            8 => NavaidType::MiddleMarker,
+            9 => NavaidType::InnerMarker,
+            12 | 13 => {
+                if name.ends_with("TACAN") {
+                    NavaidType::Tacan
+                } else {
+                    NavaidType::Dme
+                }
+            }
+            14 => NavaidType::Fpap,
+            15 => NavaidType::Gls,
+            16 => NavaidType::Threshold,
+            _ => return None,
+        };
+
+        return Some(navaid_type);
+    }
+
+    /// The `WaypointKind` of this type of navaid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::navaid::NavaidType;
+    /// # use oldnav_lib::navdata::kind::WaypointKind;
+    /// assert_eq!(WaypointKind::Vor, NavaidType::Vortac.kind());
+    /// assert_eq!(WaypointKind::Ils, NavaidType::OuterMarker.kind());
+    /// ```
+    pub fn kind(&self) -> WaypointKind {
+        match *self {
+            NavaidType::Ndb => WaypointKind::Ndb,
+            NavaidType::Vor | NavaidType::VorDme | NavaidType::Vortac => WaypointKind::Vor,
+            NavaidType::Dme | NavaidType::Tacan => WaypointKind::Dme,
+            NavaidType::IlsLocalizer |
+            NavaidType::Localizer |
+            NavaidType::Glideslope |
+            NavaidType::OuterMarker |
+            NavaidType::MiddleMarker |
+            NavaidType::InnerMarker |
+            NavaidType::Fpap |
+            NavaidType::Gls => WaypointKind::Ils,
+            NavaidType::Threshold => WaypointKind::RunwayThreshold,
+        }
+    }
+
+    /// Whether this type of navaid is associated with a runway, and has
+    /// a runway column in x-plane's `earth_nav.dat`.
+    pub fn has_runway(&self) -> bool {
+        match *self {
+            NavaidType::IlsLocalizer |
+            NavaidType::Localizer |
+            NavaidType::Glideslope |
+            NavaidType::OuterMarker |
+            NavaidType::MiddleMarker |
+            NavaidType::InnerMarker |
+            NavaidType::Fpap |
+            NavaidType::Gls |
+            NavaidType::Threshold => true,
+            _ => false,
+        }
+    }
+}
+
+/// A radio navigation aid.
+///
+/// # Examples
+///
+/// ```
+/// # use oldnav_lib::navdata::navaid::{Navaid, NavaidType};
+/// # use oldnav_lib::navdata::waypoint::WaypointInterface;
+/// # use oldnav_lib::navdata::coord::SphericalCoordinate;
+/// let pos = SphericalCoordinate::from_geographic(0.0, 47.435372, -122.309616);
+/// let mut navaid = Navaid::new("SEA", "SEATTLE VORTAC", pos, NavaidType::Vortac);
+/// navaid.frequency = Some(116800.0);
+/// navaid.range = Some(130.0);
+///
+/// assert_eq!("SEA", navaid.code());
+/// ```
+pub struct Navaid {
+    /// This navaid's waypoint.
+    pub waypoint: Waypoint,
+
+    /// The type of navaid
+    pub navaid_type: NavaidType,
+
+    /// Elevation above mean sea level (in feet)
+    pub elevation: f64,
+
+    /// Frequency (in kHz), `None` for markers and for the SBAS/GBAS types
+    /// which use a `channel` instead.
+    pub frequency: Option<f64>,
+
+    /// SBAS/GBAS channel number
+    pub channel: Option<u32>,
+
+    /// Maximum reception range (in nautical miles)
+    pub range: Option<f64>,
+
+    /// The magnetic variation the VOR's radials are slaved to (in degrees,
+    /// negative for west).
+    pub slaved_variation: Option<f64>,
+
+    /// True bearing of a localizer, glideslope, marker or approach course
+    /// (in degrees).
+    pub bearing: Option<f64>,
+
+    /// Glide path angle of a glideslope or SBAS/GBAS approach (in degrees)
+    pub glide_path_angle: Option<f64>,
+
+    /// DME bias (in nautical miles)
+    pub bias: Option<f64>,
+
+    /// The runway this navaid serves
+    pub runway: Option<String>,
+}
+
+impl Navaid {
+    /// Constructor for `Navaid`.
+    pub fn new<S: Into<String>>(
+        code: S,
+        name: S,
+        pos: SphericalCoordinate,
+        navaid_type: NavaidType,
+    ) -> Navaid {
+        return Navaid {
+            waypoint: Waypoint::new(code, name, pos, None),
+            navaid_type: navaid_type,
+            elevation: 0.0,
+            frequency: None,
+            channel: None,
+            range: None,
+            slaved_variation: None,
+            bearing: None,
+            glide_path_angle: None,
+            bias: None,
+            runway: None,
+        };
+    }
+}
+
+impl WaypointInterface for Navaid {
+    fn code(&self) -> &str {
+        return &self.waypoint.code;
+    }
+
+    fn name(&self) -> &str {
+        return &self.waypoint.name;
+    }
+
+    fn pos(&self) -> &SphericalCoordinate {
+        return &self.waypoint.pos;
+    }
+
+    fn region(&self) -> &str {
+        return &self.waypoint.region;
+    }
+
+    fn country(&self) -> Option<&Arc<Country>> {
+        return self.waypoint.country.as_ref();
+    }
+
+    fn kind(&self) -> WaypointKind {
+        return self.navaid_type.kind();
+    }
+}
+
+impl fmt::Debug for Navaid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "Navaid: {{code: {}, name: {}, type: {:?}, frequency: {:?}, pos: [{}, {}]}}",
+            self.waypoint.code,
+            self.waypoint.name,
+            self.navaid_type,
+            self.frequency,
+            self.pos().lat(),
+            self.pos().lon()
+        );
+
+    }
+}
@@ -0,0 +1,529 @@
+//! User defined waypoints layered over a `Database`.
+//!
+//! As in x-plane, the fixes and navaids of the default navdata can be
+//! extended and overridden by the user's own waypoints (`user_fix.dat` and
+//! `user_nav.dat`), and those by the navdata in the `Custom Data`
+//! directory. A fix is overridden by one with the same code and ICAO
+//! region, and a navaid by one which also has the same type.
+//!
+//! The `Database` itself is never modified, so it can still be shared
+//! between threads while the overlay is changed.
+
+use navdata::address;
+use navdata::database::{Database, DatabaseError};
+use navdata::waypoint::{Waypoint, WaypointInterface};
+use navdata::kind::WaypointKinds;
+use navdata::navaid::{Navaid, NavaidType};
+use navdata::coord::SphericalCoordinate;
+use navdata::report::LoadReport;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write, BufWriter};
+use std::fs::{self, File};
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+use std::fmt;
+
+/// Where a waypoint came from, in increasing order of precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WaypointSource {
+    /// The default navdata, loaded into the `Database`
+    Default,
+
+    /// The user's own waypoints
+    User,
+
+    /// The navdata in x-plane's `Custom Data` directory
+    CustomData,
+}
+
+/// Identifies the waypoints in different layers which override each other.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OverlayKey {
+    /// Code of the waypoint
+    pub code: String,
+
+    /// ICAO region of the waypoint
+    pub region: String,
+
+    /// Type of the navaid, `None` for a fix
+    pub navaid_type: Option<NavaidType>,
+}
+
+/// A fix or navaid in one of the layers of an `Overlay`.
+#[derive(Debug, Clone)]
+pub enum OverlayWaypoint {
+    /// A fix
+    Fix(Arc<Waypoint>),
+
+    /// A navaid
+    Navaid(Arc<Navaid>),
+}
+
+impl OverlayWaypoint {
+    /// The waypoint, for use in lookups and `Route`s.
+    pub fn waypoint(&self) -> Arc<WaypointInterface> {
+        match *self {
+            OverlayWaypoint::Fix(ref fix) => fix.clone(),
+            OverlayWaypoint::Navaid(ref navaid) => navaid.clone(),
+        }
+    }
+
+    /// The key of this waypoint.
+    pub fn key(&self) -> OverlayKey {
+        match *self {
+            OverlayWaypoint::Fix(ref fix) => {
+                OverlayKey {
+                    code: fix.code.clone(),
+                    region: fix.region.clone(),
+                    navaid_type: None,
+                }
+            }
+            OverlayWaypoint::Navaid(ref navaid) => {
+                OverlayKey {
+                    code: navaid.waypoint.code.clone(),
+                    region: navaid.waypoint.region.clone(),
+                    navaid_type: Some(navaid.navaid_type),
+                }
+            }
+        }
+    }
+}
+
+/// A waypoint found in an `Overlay`, with the layer it came from.
+#[derive(Debug, Clone)]
+pub struct OverlayMatch {
+    /// The layer the waypoint came from
+    pub source: WaypointSource,
+
+    /// The waypoint
+    pub waypoint: Arc<WaypointInterface>,
+}
+
+#[derive(Debug, Clone)]
+struct OverlayEntry {
+    source: WaypointSource,
+    key: OverlayKey,
+    waypoint: OverlayWaypoint,
+}
+
+/// The user and `Custom Data` layers over a `Database`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use oldnav_lib::navdata::database::Database;
+/// # use oldnav_lib::navdata::overlay::{Overlay, WaypointSource};
+/// # use oldnav_lib::navdata::kind::WaypointKinds;
+/// # use oldnav_lib::navdata::waypoint::Waypoint;
+/// # use oldnav_lib::navdata::coord::SphericalCoordinate;
+/// # use std::path::{Path, PathBuf};
+/// # use std::sync::Arc;
+/// let db = Arc::new(Database::new(PathBuf::new(), PathBuf::new()).unwrap());
+/// let mut overlay = Overlay::load_xplane(db, Path::new("X-Plane 11")).unwrap();
+///
+/// let pos = SphericalCoordinate::from_geographic(0.0, 47.5, -122.3);
+/// overlay.add_user_waypoint(Waypoint::new("HOME", "HOME", pos, None));
+/// assert_eq!(WaypointSource::User, overlay.find("HOME", WaypointKinds::all())[0].source);
+///
+/// overlay.save_user_waypoints(Path::new("X-Plane 11/Custom Data/user_fix.dat")).unwrap();
+/// ```
+pub struct Overlay {
+    /// The default navdata
+    pub base: Arc<Database>,
+
+    /// Diagnostics for each of the files read into the overlay
+    pub load_report: LoadReport,
+
+    /// The waypoints in the user and `Custom Data` layers, by code
+    entries: HashMap<String, Vec<OverlayEntry>>,
+
+    /// The types of the navaids in `base`, by their address, so that the
+    /// key of a waypoint found in `base.waypoint_hash` can be worked out.
+    base_navaid_types: HashMap<usize, NavaidType>,
+}
+
+impl Overlay {
+    /// Create an empty overlay over `base`.
+    pub fn new(base: Arc<Database>) -> Overlay {
+        let mut base_navaid_types = HashMap::new();
+        for navaid in &base.navaids {
+            base_navaid_types.insert(address(&**navaid), navaid.navaid_type);
+        }
+
+        return Overlay {
+            base: base,
+            load_report: LoadReport::new(),
+            entries: HashMap::new(),
+            base_navaid_types: base_navaid_types,
+        };
+    }
+
+    /// Create an overlay over `base` with the user and `Custom Data` layers
+    /// read from the `Custom Data` directory of an x-plane installation.
+    ///
+    /// Reads `earth_fix.dat` and `earth_nav.dat` into the `CustomData`
+    /// layer, and `user_fix.dat` and `user_nav.dat` into the `User` layer.
+    /// Files which aren't present are skipped.
+    pub fn load_xplane(base: Arc<Database>, xplane_dir: &Path) -> Result<Overlay, DatabaseError> {
+        let custom_data = xplane_dir.join("Custom Data");
+        let mut overlay = Overlay::new(base);
+
+        let files = [
+            ("earth_fix.dat", WaypointSource::CustomData, false),
+            ("earth_nav.dat", WaypointSource::CustomData, true),
+            ("user_fix.dat", WaypointSource::User, false),
+            ("user_nav.dat", WaypointSource::User, true),
+        ];
+
+        for &(name, source, is_nav) in &files {
+            let path = custom_data.join(name);
+            if !path.exists() {
+                continue;
+            }
+
+            if is_nav {
+                try!(overlay.read_navaids(&path, source));
+            } else {
+                try!(overlay.read_fixes(&path, source));
+            }
+        }
+
+        return Ok(overlay);
+    }
+
+    /// Read the fixes in a file in the format of `earth_fix.dat` into a
+    /// layer of the overlay.
+    ///
+    /// User waypoints with the region `ZZ` written by
+    /// `save_user_waypoints()` are given no ICAO region again.
+    pub fn read_fixes(
+        &mut self,
+        file_path: &Path,
+        source: WaypointSource,
+    ) -> Result<(), DatabaseError> {
+        let (fixes, report) = try!(self.base.read_fix_file(file_path));
+        for mut fix in fixes {
+            fix.user = source == WaypointSource::User;
+            if fix.user && fix.region == UNKNOWN_REGION {
+                fix.region = String::new();
+            }
+
+            self.insert(source, OverlayWaypoint::Fix(Arc::new(fix)));
+        }
+
+        self.load_report.files.push(report);
+        return Ok(());
+    }
+
+    /// Read the navaids in a file in the format of `earth_nav.dat` into a
+    /// layer of the overlay.
+    pub fn read_navaids(
+        &mut self,
+        file_path: &Path,
+        source: WaypointSource,
+    ) -> Result<(), DatabaseError> {
+        let (navaids, report) = try!(self.base.read_nav_file(file_path));
+        for navaid in navaids {
+            self.insert(source, OverlayWaypoint::Navaid(Arc::new(navaid)));
+        }
+
+        self.load_report.files.push(report);
+        return Ok(());
+    }
+
+    /// Read a list of user waypoints into the `User` layer.
+    ///
+    /// Each line has the latitude, longitude and code of a waypoint,
+    /// separated by commas, and optionally its name. Empty lines, and lines
+    /// starting with `#` are ignored. The waypoints have no ICAO region.
+    ///
+    /// ```text
+    /// # home airfield circuit
+    /// -37.5, 145.1, CIRC1, Crosswind turn
+    /// ```
+    pub fn read_waypoint_list(&mut self, file_path: &Path) -> Result<(), DatabaseError> {
+        let path = file_path.display().to_string();
+        let io_error = |error| {
+            DatabaseError::Io {
+                path: path.clone(),
+                error: error,
+            }
+        };
+
+        let file = try!(File::open(file_path).map_err(&io_error));
+        let mut waypoints = Vec::new();
+
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = try!(line.map_err(&io_error));
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let columns: Vec<&str> = trimmed.splitn(4, ',').map(|c| c.trim()).collect();
+            let lat = columns.get(0).and_then(|c| c.parse::<f64>().ok());
+            let lon = columns.get(1).and_then(|c| c.parse::<f64>().ok());
+            let code = columns.get(2).cloned().unwrap_or("");
+
+            let (lat, lon) = match (lat, lon) {
+                (Some(lat), Some(lon)) if !code.is_empty() => (lat, lon),
+                _ => {
+                    return Err(DatabaseError::Parse {
+                        path: path.clone(),
+                        line: index + 1,
+                        column: None,
+                        text: line.clone(),
+                        message: String::from("Expected a latitude, longitude and code"),
+                    });
+                }
+            };
+
+            let name = columns.get(3).cloned().unwrap_or(code);
+            let pos = SphericalCoordinate::from_geographic(0.0, lat, lon);
+            waypoints.push(Waypoint::new(code, name, pos, None));
+        }
+
+        for waypoint in waypoints {
+            self.add_user_waypoint(waypoint);
+        }
+
+        return Ok(());
+    }
+
+    /// Add a waypoint to a layer, replacing any waypoint in the same layer
+    /// with the same key.
+    pub fn insert(&mut self, source: WaypointSource, waypoint: OverlayWaypoint) {
+        let key = waypoint.key();
+        let entries = self.entries.entry(key.code.clone()).or_insert_with(Vec::new);
+        entries.retain(|entry| entry.source != source || entry.key != key);
+        entries.push(OverlayEntry {
+            source: source,
+            key: key,
+            waypoint: waypoint,
+        });
+    }
+
+    /// Add a user waypoint, replacing any user waypoint with the same code
+    /// and ICAO region. The waypoint is linked to the `Country` of its
+    /// region in the base database.
+    pub fn add_user_waypoint(&mut self, mut waypoint: Waypoint) {
+        waypoint.country = self.base.find_country(&waypoint.region);
+        waypoint.user = true;
+        self.insert(WaypointSource::User, OverlayWaypoint::Fix(Arc::new(waypoint)));
+    }
+
+    /// Remove the user waypoint (not navaid) with the given code and ICAO
+    /// region. Returns `false` if there was no such waypoint.
+    ///
+    /// Any waypoint it was overriding is found by lookups again.
+    pub fn remove_user_waypoint(&mut self, code: &str, region: &str) -> bool {
+        let key = OverlayKey {
+            code: String::from(code),
+            region: String::from(region),
+            navaid_type: None,
+        };
+
+        let (removed, is_empty) = match self.entries.get_mut(code) {
+            Some(entries) => {
+                let len = entries.len();
+                entries.retain(|entry| entry.source != WaypointSource::User || entry.key != key);
+                (entries.len() != len, entries.is_empty())
+            }
+            None => (false, false),
+        };
+
+        if is_empty {
+            self.entries.remove(code);
+        }
+
+        return removed;
+    }
+
+    /// All the user waypoints (not navaids), sorted by code.
+    pub fn user_waypoints(&self) -> Vec<Arc<Waypoint>> {
+        let mut waypoints: Vec<Arc<Waypoint>> = self.entries
+            .values()
+            .flat_map(|entries| entries.iter())
+            .filter(|entry| entry.source == WaypointSource::User)
+            .filter_map(|entry| match entry.waypoint {
+                OverlayWaypoint::Fix(ref fix) => Some(fix.clone()),
+                OverlayWaypoint::Navaid(_) => None,
+            })
+            .collect();
+
+        waypoints.sort_by(|a, b| (&a.code, &a.region).cmp(&(&b.code, &b.region)));
+        return waypoints;
+    }
+
+    /// Write the user waypoints to a file in the format of x-plane's
+    /// `user_fix.dat`, so that they can be read with `read_fixes()`.
+    ///
+    /// User navaids are not written. Waypoints without an ICAO region are
+    /// written with the region `ZZ`, as x-plane requires one, and those
+    /// without a type with the type `0`.
+    ///
+    /// The waypoints are written to a temporary file, which then replaces
+    /// `file_path`, so the existing file is kept if writing fails.
+    pub fn save_user_waypoints(&self, file_path: &Path) -> io::Result<()> {
+        let temp_path = file_path.with_extension(format!("tmp{}", process::id()));
+
+        let result = self.write_user_waypoints(&temp_path);
+        if let Err(error) = result {
+            let _ = fs::remove_file(&temp_path);
+            return Err(error);
+        }
+
+        return fs::rename(&temp_path, file_path);
+    }
+
+    /// Write the user waypoints to a new file at `file_path`, see
+    /// `save_user_waypoints()`.
+    fn write_user_waypoints(&self, file_path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(try!(File::create(file_path)));
+        try!(writeln!(writer, "I"));
+        try!(writeln!(writer, "1101 Version - user waypoints saved by oldnav"));
+        try!(writeln!(writer, ""));
+
+        for waypoint in self.user_waypoints() {
+            try!(writeln!(writer, "{}", fix_line(&waypoint)));
+        }
+
+        try!(writeln!(writer, "99"));
+        return writer.flush();
+    }
+
+    /// Find the waypoints of one of the `kinds` with the given code, taking
+    /// the one with the highest precedence for each key.
+    ///
+    /// Airports from the `Database` are included, but can't be overridden.
+    /// A waypoint which is overridden is left out even if the waypoint
+    /// overriding it is not one of the `kinds`.
+    pub fn find(&self, code: &str, kinds: WaypointKinds) -> Vec<OverlayMatch> {
+        let mut matches: Vec<OverlayMatch> = Vec::new();
+        let entries: &[OverlayEntry] = match self.entries.get(code) {
+            Some(entries) => entries,
+            None => &[],
+        };
+
+        for entry in entries {
+            let overridden = entries.iter().any(|other| {
+                other.key == entry.key && other.source > entry.source
+            });
+
+            if !overridden {
+                let waypoint = entry.waypoint.waypoint();
+                if kinds.contains(waypoint.kind()) {
+                    matches.push(OverlayMatch {
+                        source: entry.source,
+                        waypoint: waypoint,
+                    });
+                }
+            }
+        }
+
+        if let Some(base_waypoints) = self.base.waypoint_hash.get(&String::from(code)) {
+            for waypoint in base_waypoints {
+                if !kinds.contains(waypoint.kind()) {
+                    continue;
+                }
+
+                let overridden = match self.base_key(waypoint) {
+                    Some(key) => entries.iter().any(|entry| entry.key == key),
+                    None => false,
+                };
+
+                if !overridden {
+                    matches.push(OverlayMatch {
+                        source: WaypointSource::Default,
+                        waypoint: waypoint.clone(),
+                    });
+                }
+            }
+        }
+
+        return matches;
+    }
+
+    /// The key of a waypoint in the `Database`, or `None` if it is an
+    /// airport.
+    fn base_key(&self, waypoint: &Arc<WaypointInterface>) -> Option<OverlayKey> {
+        let address = address(&**waypoint);
+
+        if let Some(airport) = self.base.airport_hash.get(waypoint.code()) {
+            if address == self::address(&**airport) {
+                return None;
+            }
+        }
+
+        return Some(OverlayKey {
+            code: String::from(waypoint.code()),
+            region: String::from(waypoint.region()),
+            navaid_type: self.base_navaid_types.get(&address).cloned(),
+        });
+    }
+}
+
+impl fmt::Debug for Overlay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n_waypoints: usize = self.entries.values().map(|entries| entries.len()).sum();
+        return write!(
+            f,
+            "Overlay: {{base: {:?}, n_waypoints: {}}}",
+            self.base,
+            n_waypoints
+        );
+    }
+}
+
+/// The ICAO region written for waypoints which don't have one, as the
+/// region column can't be empty.
+const UNKNOWN_REGION: &'static str = "ZZ";
+
+/// Encode the ARINC 424 waypoint type columns in the integer used by
+/// x-plane, the inverse of the decoding done when reading `earth_fix.dat`.
+fn encode_waypoint_type(waypoint_type: &str) -> u32 {
+    return waypoint_type
+        .bytes()
+        .take(3)
+        .enumerate()
+        .fold(0, |code, (i, byte)| code | (byte as u32) << (8 * i));
+}
+
+/// Format a fix as a line of a version 1101 `earth_fix.dat`.
+fn fix_line(fix: &Waypoint) -> String {
+    let airport = match fix.airport {
+        Some(ref airport) => String::from(airport.code()),
+        None => String::from("ENRT"),
+    };
+    let region = if fix.region.is_empty() {
+        UNKNOWN_REGION
+    } else {
+        &fix.region
+    };
+
+    let mut line = format!(
+        "{:13.9} {:14.9}  {} {} {}",
+        fix.pos.lat(),
+        fix.pos.lon(),
+        fix.code,
+        airport,
+        region
+    );
+
+    // the type column can only be left out when there is no name after it,
+    // else a name starting with a number would be read as the type
+    match fix.waypoint_type {
+        Some(ref waypoint_type) => {
+            line.push_str(&format!(" {}", encode_waypoint_type(waypoint_type)));
+        }
+        None if fix.name != fix.code => line.push_str(" 0"),
+        None => (),
+    }
+
+    if fix.name != fix.code {
+        line.push_str(&format!(" {}", fix.name));
+    }
+
+    return line;
+}
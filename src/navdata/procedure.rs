@@ -0,0 +1,895 @@
+//! Terminal procedures (SIDs, STARs and approaches) read from the per
+//! airport CIFP files provided with x-plane's navdata.
+//!
+//! Each airport has its own file (`CIFP/<airport code>.dat`) with one
+//! record per line, in the form `<record type>:<fields>;` where the fields
+//! are comma separated, and their values follow the ARINC 424
+//! specification. The record types are:
+//!
+//! + `SID`, `STAR` and `APPCH` - one leg of a procedure
+//! + `RWY` - information about a runway threshold
+//! + `PRDAT` - preferred route data, which is kept as its raw fields
+//!
+//! The fields of a `SID`, `STAR` or `APPCH` record, by position, are:
+//!
+//! | index | field                               |
+//! |-------|-------------------------------------|
+//! | 0     | sequence number                     |
+//! | 1     | route type                          |
+//! | 2     | procedure identifier                |
+//! | 3     | transition identifier               |
+//! | 4     | fix identifier                      |
+//! | 5     | fix ICAO region                     |
+//! | 6     | fix section code                    |
+//! | 7     | fix subsection code                 |
+//! | 8     | waypoint description code           |
+//! | 9     | turn direction                      |
+//! | 10    | RNP                                 |
+//! | 11    | path and termination                |
+//! | 12    | turn direction valid                |
+//! | 13    | recommended navaid                  |
+//! | 14    | recommended navaid ICAO region      |
+//! | 15    | arc radius                          |
+//! | 16    | theta                               |
+//! | 17    | rho                                 |
+//! | 18    | magnetic course                     |
+//! | 19    | route distance, holding distance or time |
+//! | 20    | recommended navaid section code     |
+//! | 21    | recommended navaid subsection code  |
+//! | 22-23 | reserved                            |
+//! | 24    | altitude description                |
+//! | 25    | ATC indicator                       |
+//! | 26    | altitude 1                          |
+//! | 27    | altitude 2                          |
+//! | 28    | transition altitude                 |
+//! | 29    | speed limit description             |
+//! | 30    | speed limit                         |
+//! | 31    | vertical angle                      |
+//! | 32    | center fix                          |
+//! | 33    | multiple code                       |
+//! | 34    | center fix ICAO region              |
+//! | 35    | center fix section code             |
+//! | 36    | center fix subsection code          |
+//! | 37    | GNSS/FMS indication                 |
+//! | 38    | route qualifier 1                   |
+//! | 39    | route qualifier 2                   |
+
+use navdata::coord::SphericalCoordinate;
+use navdata::coord::dms_to_deg;
+use navdata::database::DatabaseError;
+use std::io::{BufReader, BufRead};
+use std::fs::File;
+use std::path::Path;
+use std::fmt;
+
+/// The type of terminal procedure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcedureType {
+    /// Standard instrument departure
+    Sid,
+
+    /// Standard terminal arrival route
+    Star,
+
+    /// Approach procedure
+    Approach,
+}
+
+impl ProcedureType {
+    /// Get the `ProcedureType` for a CIFP record type.
+    pub fn from_record_type(record_type: &str) -> Option<ProcedureType> {
+        match record_type {
+            "SID" => Some(ProcedureType::Sid),
+            "STAR" => Some(ProcedureType::Star),
+            "APPCH" => Some(ProcedureType::Approach),
+            _ => None,
+        }
+    }
+}
+
+/// The ARINC 424 path and terminator of a procedure `Leg`, which defines
+/// the path to be flown and how the leg ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathTerminator {
+    /// Initial fix
+    IF,
+
+    /// Track to a fix
+    TF,
+
+    /// Course to a fix
+    CF,
+
+    /// Direct to a fix
+    DF,
+
+    /// Course from a fix to an altitude
+    FA,
+
+    /// Course from a fix to an along track distance
+    FC,
+
+    /// Course from a fix to a DME distance
+    FD,
+
+    /// Course from a fix to a manual termination
+    FM,
+
+    /// Course to an altitude
+    CA,
+
+    /// Course to a DME distance
+    CD,
+
+    /// Course to an intercept
+    CI,
+
+    /// Course to a radial termination
+    CR,
+
+    /// Constant radius arc
+    RF,
+
+    /// Arc to a fix
+    AF,
+
+    /// Heading to an altitude
+    VA,
+
+    /// Heading to a DME distance
+    VD,
+
+    /// Heading to an intercept
+    VI,
+
+    /// Heading to a manual termination
+    VM,
+
+    /// Heading to a radial termination
+    VR,
+
+    /// Procedure turn
+    PI,
+
+    /// Holding in lieu of a procedure turn, terminating at an altitude
+    HA,
+
+    /// Holding in lieu of a procedure turn, terminating at the fix after
+    /// one circuit
+    HF,
+
+    /// Holding in lieu of a procedure turn, with a manual termination
+    HM,
+}
+
+impl PathTerminator {
+    /// Get the `PathTerminator` for its two letter code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::procedure::PathTerminator;
+    /// assert_eq!(Some(PathTerminator::TF), PathTerminator::from_code("TF"));
+    /// assert_eq!(None, PathTerminator::from_code("XX"));
+    /// ```
+    pub fn from_code(code: &str) -> Option<PathTerminator> {
+        let path_terminator = match code {
+            "IF" => PathTerminator::IF,
+            "TF" => PathTerminator::TF,
+            "CF" => PathTerminator::CF,
+            "DF" => PathTerminator::DF,
+            "FA" => PathTerminator::FA,
+            "FC" => PathTerminator::FC,
+            "FD" => PathTerminator::FD,
+            "FM" => PathTerminator::FM,
+            "CA" => PathTerminator::CA,
+            "CD" => PathTerminator::CD,
+            "CI" => PathTerminator::CI,
+            "CR" => PathTerminator::CR,
+            "RF" => PathTerminator::RF,
+            "AF" => PathTerminator::AF,
+            "VA" => PathTerminator::VA,
+            "VD" => PathTerminator::VD,
+            "VI" => PathTerminator::VI,
+            "VM" => PathTerminator::VM,
+            "VR" => PathTerminator::VR,
+            "PI" => PathTerminator::PI,
+            "HA" => PathTerminator::HA,
+            "HF" => PathTerminator::HF,
+            "HM" => PathTerminator::HM,
+            _ => return None,
+        };
+
+        return Some(path_terminator);
+    }
+
+    /// Whether a leg with this path terminator ends at its fix.
+    pub fn ends_at_fix(&self) -> bool {
+        match *self {
+            PathTerminator::IF |
+            PathTerminator::TF |
+            PathTerminator::CF |
+            PathTerminator::DF |
+            PathTerminator::RF |
+            PathTerminator::AF |
+            PathTerminator::HF => true,
+            _ => false,
+        }
+    }
+}
+
+/// The direction of a turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnDirection {
+    /// Turn left
+    Left,
+
+    /// Turn right
+    Right,
+
+    /// Turn in whichever direction is shortest
+    Either,
+}
+
+impl TurnDirection {
+    /// Get the `TurnDirection` for its code (`L`, `R` or `E`).
+    pub fn from_code(code: &str) -> Option<TurnDirection> {
+        match code {
+            "L" => Some(TurnDirection::Left),
+            "R" => Some(TurnDirection::Right),
+            "E" => Some(TurnDirection::Either),
+            _ => None,
+        }
+    }
+}
+
+/// An altitude used in a procedure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Altitude {
+    /// An altitude (in feet)
+    Feet(i32),
+
+    /// A flight level
+    FlightLevel(u32),
+}
+
+impl Altitude {
+    /// Parse an ARINC 424 altitude such as `05000` or `FL180`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::procedure::Altitude;
+    /// assert_eq!(Some(Altitude::Feet(5000)), Altitude::parse("05000"));
+    /// assert_eq!(Some(Altitude::FlightLevel(180)), Altitude::parse("FL180"));
+    /// assert_eq!(None, Altitude::parse("     "));
+    /// ```
+    pub fn parse(value: &str) -> Option<Altitude> {
+        let value = value.trim();
+        if value.starts_with("FL") {
+            return value[2..].parse().ok().map(Altitude::FlightLevel);
+        }
+        return value.parse().ok().map(Altitude::Feet);
+    }
+}
+
+/// How the altitudes of an `AltitudeConstraint` are to be applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AltitudeDescription {
+    /// At `altitude1` (`@` or blank)
+    At,
+
+    /// At or above `altitude1` (`+`)
+    AtOrAbove,
+
+    /// At or below `altitude1` (`-`)
+    AtOrBelow,
+
+    /// At or above `altitude2`, and at or below `altitude1` (`B`)
+    Between,
+
+    /// At or above `altitude2` (`C`)
+    AtOrAboveSecond,
+
+    /// Glide slope intercept at `altitude1` (`G` or `I`)
+    GlideSlopeIntercept,
+
+    /// Another ARINC 424 altitude description code, such as the codes used
+    /// for step down fixes and vertical navigation.
+    Other(char),
+}
+
+impl AltitudeDescription {
+    /// Get the `AltitudeDescription` for its ARINC 424 code.
+    pub fn from_code(code: char) -> AltitudeDescription {
+        match code {
+            ' ' | '@' => AltitudeDescription::At,
+            '+' => AltitudeDescription::AtOrAbove,
+            '-' => AltitudeDescription::AtOrBelow,
+            'B' => AltitudeDescription::Between,
+            'C' => AltitudeDescription::AtOrAboveSecond,
+            'G' | 'I' => AltitudeDescription::GlideSlopeIntercept,
+            _ => AltitudeDescription::Other(code),
+        }
+    }
+}
+
+/// An altitude constraint on a procedure `Leg`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AltitudeConstraint {
+    /// How the altitudes are to be applied
+    pub description: AltitudeDescription,
+
+    /// The first altitude
+    pub altitude1: Option<Altitude>,
+
+    /// The second altitude
+    pub altitude2: Option<Altitude>,
+}
+
+/// How the speed of a `SpeedConstraint` is to be applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedDescription {
+    /// At the speed
+    At,
+
+    /// At or above the speed
+    AtOrAbove,
+
+    /// At or below the speed
+    AtOrBelow,
+}
+
+/// A speed constraint on a procedure `Leg`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeedConstraint {
+    /// How the speed is to be applied
+    pub description: SpeedDescription,
+
+    /// Indicated airspeed (in knots)
+    pub speed: u32,
+}
+
+/// A fix referenced by a procedure, identified in the same way as in the
+/// ARINC 424 data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcedureFix {
+    /// Code of the fix, e.g. `BANGR` or `RW16L`
+    pub code: String,
+
+    /// ICAO region of the fix
+    pub region: String,
+
+    /// ARINC 424 section code of the fix, e.g. `D` for a navaid, `E` for
+    /// an enroute waypoint, or `P` for an airport terminal waypoint.
+    pub section: String,
+
+    /// ARINC 424 subsection code of the fix
+    pub subsection: String,
+}
+
+/// A leg of a procedure `Transition`.
+#[derive(Debug, Clone)]
+pub struct Leg {
+    /// Sequence number of the leg within its transition
+    pub sequence: u32,
+
+    /// The fix this leg is associated with
+    pub fix: Option<ProcedureFix>,
+
+    /// ARINC 424 waypoint description code (4 columns)
+    pub description: String,
+
+    /// Whether the fix is a fly-over rather than fly-by fix
+    pub fly_over: bool,
+
+    /// Direction of the turn at the start of the leg
+    pub turn_direction: Option<TurnDirection>,
+
+    /// Whether the turn direction is mandatory
+    pub turn_direction_valid: bool,
+
+    /// Required navigation performance (in nautical miles)
+    pub rnp: Option<f64>,
+
+    /// The path to be flown and how the leg ends
+    pub path_terminator: PathTerminator,
+
+    /// Recommended navaid, used for the theta and rho values
+    pub recommended_navaid: Option<ProcedureFix>,
+
+    /// Radius of an `RF` leg (in nautical miles)
+    pub arc_radius: Option<f64>,
+
+    /// Magnetic bearing from the recommended navaid to the fix (in degrees)
+    pub theta: Option<f64>,
+
+    /// Distance from the recommended navaid to the fix (in nautical miles)
+    pub rho: Option<f64>,
+
+    /// Course of the leg (in degrees)
+    pub course: Option<f64>,
+
+    /// Whether `course` is a true rather than magnetic course
+    pub true_course: bool,
+
+    /// Route or holding distance (in nautical miles)
+    pub distance: Option<f64>,
+
+    /// Holding time (in minutes)
+    pub time: Option<f64>,
+
+    /// Altitude constraint
+    pub altitude: Option<AltitudeConstraint>,
+
+    /// Transition altitude (in feet)
+    pub transition_altitude: Option<u32>,
+
+    /// Speed constraint
+    pub speed: Option<SpeedConstraint>,
+
+    /// Vertical angle of the descent to the fix (in degrees)
+    pub vertical_angle: Option<f64>,
+
+    /// Center fix of an `RF` or `AF` leg
+    pub center_fix: Option<ProcedureFix>,
+}
+
+impl Leg {
+    /// Read a leg from the fields of a `SID`, `STAR` or `APPCH` record.
+    ///
+    /// Returns `None` if the record is invalid.
+    pub fn from_fields(fields: &[&str]) -> Option<Leg> {
+        if fields.len() < 32 {
+            return None;
+        }
+
+        let description = String::from(fields[8]);
+        let fly_over = match description.chars().nth(1) {
+            Some('Y') | Some('B') => true,
+            _ => false,
+        };
+
+        let (course, true_course) = match parse_course(fields[18]) {
+            Some((course, true_course)) => (Some(course), true_course),
+            None => (None, false),
+        };
+
+        let (distance, time) = if fields[19].trim().starts_with('T') {
+            (None, parse_tenths(&fields[19].trim()[1..]))
+        } else {
+            (parse_tenths(fields[19]), None)
+        };
+
+        let altitude1 = Altitude::parse(fields[26]);
+        let altitude2 = Altitude::parse(fields[27]);
+        let altitude = if altitude1.is_some() || altitude2.is_some() {
+            Some(AltitudeConstraint {
+                description: AltitudeDescription::from_code(
+                    fields[24].chars().next().unwrap_or(' '),
+                ),
+                altitude1: altitude1,
+                altitude2: altitude2,
+            })
+        } else {
+            None
+        };
+
+        let speed = fields[30].trim().parse::<u32>().ok().map(|speed| {
+            SpeedConstraint {
+                description: match fields[29].trim() {
+                    "+" => SpeedDescription::AtOrAbove,
+                    "-" => SpeedDescription::AtOrBelow,
+                    _ => SpeedDescription::At,
+                },
+                speed: speed,
+            }
+        });
+
+        return Some(Leg {
+            sequence: fields[0].trim().parse().ok()?,
+            fix: parse_fix(fields[4], fields[5], fields[6], fields[7]),
+            description: description,
+            fly_over: fly_over,
+            turn_direction: TurnDirection::from_code(fields[9].trim()),
+            turn_direction_valid: fields[12].trim() == "Y",
+            rnp: parse_rnp(fields[10]),
+            path_terminator: PathTerminator::from_code(fields[11].trim())?,
+            recommended_navaid: parse_fix(fields[13], fields[14], fields[20], fields[21]),
+            arc_radius: fields[15].trim().parse::<f64>().ok().map(|radius| radius / 1000.0),
+            theta: parse_tenths(fields[16]),
+            rho: parse_tenths(fields[17]),
+            course: course,
+            true_course: true_course,
+            distance: distance,
+            time: time,
+            altitude: altitude,
+            transition_altitude: fields[28].trim().parse().ok(),
+            speed: speed,
+            vertical_angle: fields[31].trim().parse::<f64>().ok().map(|angle| angle / 100.0),
+            center_fix: if fields.len() > 36 {
+                parse_fix(fields[32], fields[34], fields[35], fields[36])
+            } else {
+                None
+            },
+        });
+    }
+}
+
+/// A transition of a `Procedure`. For a SID this is a runway or enroute
+/// transition, for a STAR an enroute or runway transition, and for an
+/// approach an approach transition or the final approach itself.
+#[derive(Debug, Clone)]
+pub struct Transition {
+    /// Identifier of the transition, e.g. `RW16L` or `BANGR`, empty for
+    /// the common route of a SID or STAR, or the final approach.
+    pub ident: String,
+
+    /// ARINC 424 route type of the transition
+    pub route_type: String,
+
+    /// The legs to be flown, in order
+    pub legs: Vec<Leg>,
+}
+
+/// A SID, STAR or approach procedure at an airport.
+#[derive(Debug, Clone)]
+pub struct Procedure {
+    /// The type of procedure
+    pub procedure_type: ProcedureType,
+
+    /// Identifier of the procedure, e.g. `BANGR9` or `I16L`
+    pub ident: String,
+
+    /// The transitions which make up this procedure
+    pub transitions: Vec<Transition>,
+}
+
+impl Procedure {
+    /// Find the transition with the given identifier.
+    pub fn transition(&self, ident: &str) -> Option<&Transition> {
+        return self.transitions.iter().find(
+            |transition| transition.ident == ident,
+        );
+    }
+}
+
+/// Information about a runway threshold from a CIFP `RWY` record.
+#[derive(Debug, Clone)]
+pub struct ProcedureRunway {
+    /// Identifier of the runway, e.g. `RW16L`
+    pub ident: String,
+
+    /// Position of the landing threshold
+    pub threshold: SphericalCoordinate,
+
+    /// Elevation of the landing threshold (in feet)
+    pub threshold_elevation: Option<i32>,
+
+    /// Threshold crossing height (in feet)
+    pub threshold_crossing_height: Option<u32>,
+
+    /// Length of the displaced threshold (in feet)
+    pub displaced_threshold: Option<u32>,
+
+    /// Identifier of the localizer, MLS or GLS serving the runway
+    pub localizer: Option<String>,
+}
+
+impl ProcedureRunway {
+    /// Read a runway from the fields of a `RWY` record.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oldnav_lib::navdata::procedure::ProcedureRunway;
+    /// let record = "RW16L,     ,      ,00432, ,ISNQ,3 ,   ;N47275955,W122184567,0000";
+    /// let fields: Vec<&str> = record.split(|c| c == ',' || c == ';').collect();
+    /// let runway = ProcedureRunway::from_fields(&fields).unwrap();
+    ///
+    /// assert_eq!("RW16L", runway.ident);
+    /// assert_eq!(Some(432), runway.threshold_elevation);
+    /// assert_eq!(Some(String::from("ISNQ")), runway.localizer);
+    /// assert!((runway.threshold.lat() - 47.466542).abs() < 0.00001);
+    /// assert!((runway.threshold.lon() - -122.312686).abs() < 0.00001);
+    ///
+    /// // a corrupt threshold is rejected
+    /// let record = "RW16L,     ,      ,00432, ,ISNQ,3 ,   ;N4\u{e9}27595,W12\u{e9}184567,0000";
+    /// let fields: Vec<&str> = record.split(|c| c == ',' || c == ';').collect();
+    /// assert!(ProcedureRunway::from_fields(&fields).is_none());
+    /// ```
+    pub fn from_fields(fields: &[&str]) -> Option<ProcedureRunway> {
+        if fields.len() < 11 {
+            return None;
+        }
+
+        return Some(ProcedureRunway {
+            ident: String::from(fields[0].trim()),
+            threshold: SphericalCoordinate::from_geographic(
+                0.0,
+                parse_arinc_latitude(fields[8].trim())?,
+                parse_arinc_longitude(fields[9].trim())?,
+            ),
+            threshold_elevation: fields[3].trim().parse().ok(),
+            threshold_crossing_height: fields[7].trim().parse().ok(),
+            displaced_threshold: fields[10].trim().parse().ok(),
+            localizer: parse_optional(fields[5]),
+        });
+    }
+}
+
+/// All the procedures for an airport, read from its CIFP file.
+#[derive(Debug, Clone)]
+pub struct AirportProcedures {
+    /// Code of the airport
+    pub airport_code: String,
+
+    /// Standard instrument departures
+    pub sids: Vec<Procedure>,
+
+    /// Standard terminal arrival routes
+    pub stars: Vec<Procedure>,
+
+    /// Approach procedures
+    pub approaches: Vec<Procedure>,
+
+    /// Runway thresholds referenced by the procedures
+    pub runways: Vec<ProcedureRunway>,
+
+    /// Preferred route records, kept as their raw fields
+    pub preferred_routes: Vec<Vec<String>>,
+}
+
+impl AirportProcedures {
+    /// Constructor for `AirportProcedures`
+    pub fn new<S: Into<String>>(airport_code: S) -> AirportProcedures {
+        AirportProcedures {
+            airport_code: airport_code.into(),
+            sids: Vec::new(),
+            stars: Vec::new(),
+            approaches: Vec::new(),
+            runways: Vec::new(),
+            preferred_routes: Vec::new(),
+        }
+    }
+
+    /// Get the procedures of a given type.
+    pub fn procedures(&self, procedure_type: ProcedureType) -> &Vec<Procedure> {
+        match procedure_type {
+            ProcedureType::Sid => &self.sids,
+            ProcedureType::Star => &self.stars,
+            ProcedureType::Approach => &self.approaches,
+        }
+    }
+
+    /// Find a procedure by its type and identifier.
+    pub fn procedure(&self, procedure_type: ProcedureType, ident: &str) -> Option<&Procedure> {
+        return self.procedures(procedure_type).iter().find(
+            |procedure| procedure.ident == ident,
+        );
+    }
+
+    /// Add a record (one line of a CIFP file) to these procedures.
+    ///
+    /// Records for the same procedure and transition are expected to be
+    /// consecutive, as they are in the CIFP files. Returns `None` if the
+    /// record is invalid, unknown record types are ignored.
+    pub fn add_record(&mut self, record: &str) -> Option<()> {
+        let record = record.trim().trim_right_matches(';');
+        let separator = record.find(':')?;
+        let (record_type, record) = record.split_at(separator);
+        let record = &record[1..];
+
+        if let Some(procedure_type) = ProcedureType::from_record_type(record_type) {
+            let fields: Vec<&str> = record.split(',').collect();
+            if fields.len() < 4 {
+                return None;
+            }
+
+            let leg = Leg::from_fields(&fields)?;
+            let ident = fields[2].trim();
+            let transition_ident = fields[3].trim();
+            let route_type = fields[1].trim();
+
+            let procedures = match procedure_type {
+                ProcedureType::Sid => &mut self.sids,
+                ProcedureType::Star => &mut self.stars,
+                ProcedureType::Approach => &mut self.approaches,
+            };
+
+            let new_procedure = match procedures.last() {
+                Some(procedure) => procedure.ident != ident,
+                None => true,
+            };
+
+            if new_procedure {
+                procedures.push(Procedure {
+                    procedure_type: procedure_type,
+                    ident: String::from(ident),
+                    transitions: Vec::new(),
+                });
+            }
+
+            let procedure = procedures.last_mut().unwrap();
+
+            let new_transition = match procedure.transitions.last() {
+                Some(transition) => {
+                    transition.ident != transition_ident || transition.route_type != route_type
+                }
+                None => true,
+            };
+
+            if new_transition {
+                procedure.transitions.push(Transition {
+                    ident: String::from(transition_ident),
+                    route_type: String::from(route_type),
+                    legs: Vec::new(),
+                });
+            }
+
+            procedure.transitions.last_mut().unwrap().legs.push(leg);
+            return Some(());
+        }
+
+        match record_type {
+            "RWY" => {
+                let fields: Vec<&str> = record.split(|c| c == ',' || c == ';').collect();
+                self.runways.push(ProcedureRunway::from_fields(&fields)?);
+            }
+            "PRDAT" => {
+                self.preferred_routes.push(
+                    record.split(',').map(|field| String::from(field.trim())).collect(),
+                );
+            }
+            _ => {}
+        }
+
+        return Some(());
+    }
+}
+
+impl fmt::Display for AirportProcedures {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "{}: {} SIDs, {} STARs, {} approaches",
+            self.airport_code,
+            self.sids.len(),
+            self.stars.len(),
+            self.approaches.len()
+        );
+    }
+}
+
+/// Read the procedures for an airport from its CIFP file.
+pub fn read_procedures(
+    airport_code: &str,
+    file_path: &Path,
+) -> Result<AirportProcedures, DatabaseError> {
+    let f = try!(File::open(file_path).map_err(|error| {
+        DatabaseError::Io {
+            path: file_path.display().to_string(),
+            error: error,
+        }
+    }));
+    let bf = BufReader::new(f);
+
+    let mut procedures = AirportProcedures::new(airport_code);
+
+    for (index, line) in bf.lines().enumerate() {
+        let l = try!(line.map_err(|error| {
+            DatabaseError::Io {
+                path: file_path.display().to_string(),
+                error: error,
+            }
+        }));
+
+        if l.trim().is_empty() {
+            continue;
+        }
+
+        if procedures.add_record(&l).is_none() {
+            return Err(DatabaseError::Parse {
+                path: file_path.display().to_string(),
+                line: index + 1,
+                column: None,
+                text: l,
+                message: String::from("Unable to parse procedure record"),
+            });
+        }
+    }
+
+    return Ok(procedures);
+}
+
+/// Parse a field which may be blank.
+fn parse_optional(field: &str) -> Option<String> {
+    let field = field.trim();
+    if field.is_empty() {
+        return None;
+    }
+    return Some(String::from(field));
+}
+
+/// Parse the fields identifying a fix, which may be blank.
+fn parse_fix(code: &str, region: &str, section: &str, subsection: &str) -> Option<ProcedureFix> {
+    return parse_optional(code).map(|code| {
+        ProcedureFix {
+            code: code,
+            region: String::from(region.trim()),
+            section: String::from(section.trim()),
+            subsection: String::from(subsection.trim()),
+        }
+    });
+}
+
+/// Parse a value stored in tenths, such as a distance or bearing.
+fn parse_tenths(field: &str) -> Option<f64> {
+    return field.trim().parse::<f64>().ok().map(|value| value / 10.0);
+}
+
+/// Parse a course, which is in tenths of a degree if it is magnetic, or
+/// whole degrees followed by `T` if it is true.
+fn parse_course(field: &str) -> Option<(f64, bool)> {
+    let field = field.trim();
+    if field.ends_with('T') {
+        return field[..field.len() - 1].parse::<f64>().ok().map(
+            |course| (course, true),
+        );
+    }
+    return parse_tenths(field).map(|course| (course, false));
+}
+
+/// Parse an ARINC 424 RNP value, where the first two digits are the value
+/// and the last digit is a negative power of ten, e.g. `010` is 1.0 and
+/// `103` is 0.01.
+fn parse_rnp(field: &str) -> Option<f64> {
+    let field = field.trim();
+    if field.len() != 3 || !field.is_ascii() {
+        return None;
+    }
+
+    let value: f64 = field[0..2].parse().ok()?;
+    let exponent: i32 = field[2..3].parse().ok()?;
+    return Some(value * 10f64.powi(-exponent));
+}
+
+/// Parse an ARINC 424 latitude such as `N47275955` (degrees, minutes,
+/// seconds and hundredths of a second).
+fn parse_arinc_latitude(field: &str) -> Option<f64> {
+    if field.len() != 9 || !field.is_ascii() {
+        return None;
+    }
+
+    let degrees = dms_to_deg(
+        field[1..3].parse().ok()?,
+        field[3..5].parse().ok()?,
+        field[5..9].parse::<f64>().ok()? / 100.0,
+    );
+
+    match &field[0..1] {
+        "N" => Some(degrees),
+        "S" => Some(-degrees),
+        _ => None,
+    }
+}
+
+/// Parse an ARINC 424 longitude such as `W122184567` (degrees, minutes,
+/// seconds and hundredths of a second).
+fn parse_arinc_longitude(field: &str) -> Option<f64> {
+    if field.len() != 10 || !field.is_ascii() {
+        return None;
+    }
+
+    let degrees = dms_to_deg(
+        field[1..4].parse().ok()?,
+        field[4..6].parse().ok()?,
+        field[6..10].parse::<f64>().ok()? / 100.0,
+    );
+
+    match &field[0..1] {
+        "E" => Some(degrees),
+        "W" => Some(-degrees),
+        _ => None,
+    }
+}
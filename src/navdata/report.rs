@@ -0,0 +1,146 @@
+//! Diagnostics collected while loading navdata into a `Database`.
+
+use std::fmt;
+
+/// A line which was skipped while loading a file, because it was invalid
+/// or a duplicate of an earlier line.
+#[derive(Debug, Clone)]
+pub struct SkippedLine {
+    /// Line number in the file (starting at 1)
+    pub line: usize,
+
+    /// The text of the line
+    pub text: String,
+
+    /// Why the line was skipped
+    pub reason: String,
+}
+
+/// A waypoint referred to by a record which could not be found in the
+/// database, e.g. the end of an airway segment.
+#[derive(Debug, Clone)]
+pub struct UnresolvedReference {
+    /// Line number in the file (starting at 1)
+    pub line: usize,
+
+    /// Code of the waypoint which could not be found
+    pub code: String,
+
+    /// ICAO region of the waypoint, if the record includes it
+    pub region: Option<String>,
+
+    /// What the reference was for, e.g. `airway H65`
+    pub context: String,
+}
+
+/// The diagnostics for one file read while loading a `Database`.
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    /// Path of the file
+    pub path: String,
+
+    /// Number of records which were loaded
+    pub loaded: usize,
+
+    /// Lines which were skipped
+    pub skipped: Vec<SkippedLine>,
+
+    /// References to waypoints which could not be found
+    pub unresolved: Vec<UnresolvedReference>,
+}
+
+impl FileReport {
+    /// Constructor for `FileReport`
+    pub fn new<S: Into<String>>(path: S) -> FileReport {
+        FileReport {
+            path: path.into(),
+            loaded: 0,
+            skipped: Vec::new(),
+            unresolved: Vec::new(),
+        }
+    }
+}
+
+/// The diagnostics collected while loading a `Database`, with a
+/// `FileReport` for each file read.
+///
+/// # Examples
+///
+/// ```
+/// # use oldnav_lib::navdata::report::{LoadReport, FileReport};
+/// let mut report = LoadReport::new();
+/// let mut file = FileReport::new("earth_fix.dat");
+/// file.loaded = 10;
+/// report.files.push(file);
+///
+/// assert_eq!(10, report.total_loaded());
+/// assert_eq!(0, report.total_skipped());
+/// assert!(report.file("earth_fix.dat").is_some());
+/// ```
+#[derive(Debug, Clone)]
+pub struct LoadReport {
+    /// The reports for each file, in the order they were read
+    pub files: Vec<FileReport>,
+}
+
+impl LoadReport {
+    /// Constructor for `LoadReport`
+    pub fn new() -> LoadReport {
+        LoadReport { files: Vec::new() }
+    }
+
+    /// Find the report for a file, by the end of its path (e.g. its name).
+    pub fn file(&self, path: &str) -> Option<&FileReport> {
+        return self.files.iter().find(|file| file.path.ends_with(path));
+    }
+
+    /// Total number of records loaded from all files.
+    pub fn total_loaded(&self) -> usize {
+        return self.files.iter().map(|file| file.loaded).sum();
+    }
+
+    /// Total number of lines skipped in all files.
+    pub fn total_skipped(&self) -> usize {
+        return self.files.iter().map(|file| file.skipped.len()).sum();
+    }
+
+    /// Total number of unresolved references in all files.
+    pub fn total_unresolved(&self) -> usize {
+        return self.files.iter().map(|file| file.unresolved.len()).sum();
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for file in &self.files {
+            try!(writeln!(
+                f,
+                "{}: {} loaded, {} skipped, {} unresolved",
+                file.path,
+                file.loaded,
+                file.skipped.len(),
+                file.unresolved.len()
+            ));
+
+            for skipped in &file.skipped {
+                try!(writeln!(
+                    f,
+                    "  line {}: {}: \"{}\"",
+                    skipped.line,
+                    skipped.reason,
+                    skipped.text
+                ));
+            }
+
+            for unresolved in &file.unresolved {
+                try!(write!(f, "  line {}: {}", unresolved.line, unresolved.code));
+                if let Some(ref region) = unresolved.region {
+                    try!(write!(f, " ({})", region));
+                }
+                try!(writeln!(f, " not found for {}", unresolved.context));
+            }
+        }
+
+        return Ok(());
+    }
+}
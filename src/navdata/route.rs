@@ -1,31 +1,32 @@
 //! Routes and Legs
 
-use navdata::waypoint::Waypoint;
-use std::rc::Rc;
-use linked_list::LinkedList;
+use navdata::country::Country;
+use navdata::waypoint::WaypointInterface;
+use std::sync::Arc;
+use std::collections::VecDeque;
 
 /// Defines a route
 ///
 /// # Examples
 /// ```
 /// # use oldnav_lib::navdata::coord::SphericalCoordinate;
-/// # use oldnav_lib::navdata::waypoint::Waypoint;
+/// # use oldnav_lib::navdata::waypoint::{Waypoint, WaypointInterface};
 /// # use oldnav_lib::navdata::route::Route;
-/// # use std::rc::Rc;
+/// # use std::sync::Arc;
 /// let p1 = SphericalCoordinate::from_geographic(0.0, 38.0, 144.0);
-/// let c1 = Rc::new(Waypoint::new("1A", "Waypoint 1A", p1, None));
+/// let c1 = Arc::new(Waypoint::new("1A", "Waypoint 1A", p1, None));
 /// let p2 = SphericalCoordinate::from_geographic(0.0, 39.0, 144.0);
-/// let c2 = Rc::new(Waypoint::new("1B", "Waypoint 1B", p2, None));
+/// let c2 = Arc::new(Waypoint::new("1B", "Waypoint 1B", p2, None));
 /// let p3 = SphericalCoordinate::from_geographic(0.0, 39.0, 145.0);
-/// let c3 = Rc::new(Waypoint::new("1C", "Waypoint 1C", p3, None));
+/// let c3 = Arc::new(Waypoint::new("1C", "Waypoint 1C", p3, None));
 ///
 /// let mut route = Route::new(Some(String::from("a new route")));
 /// route.append_waypoint(c1.clone());
 /// route.append_waypoint(c2.clone());
 /// route.append_waypoint(c3.clone());
 ///
-/// assert_eq!(route.first().unwrap().name, c1.name);
-/// assert_eq!(route.last().unwrap().name, c3.name);
+/// assert_eq!(route.first().unwrap().name(), c1.name());
+/// assert_eq!(route.last().unwrap().name(), c3.name());
 /// ```
 #[derive(Debug)]
 pub struct Route {
@@ -33,13 +34,12 @@ pub struct Route {
     pub name: Option<String>,
 
     /// Legs of the `Route`
-    pub waypoints: LinkedList<Rc<Waypoint>>,
+    pub waypoints: VecDeque<Arc<WaypointInterface>>,
 }
 
-// TODO TODO TODO!!!!!!!!!!!!!!!!!!!!!!!!!!!!!! ################################
-// maybe a route should be comprised just of waypoints?
-// what would be the deal with having SIDs and STARs?
-// more research needs to be done here.
+// A route is comprised just of waypoints. Terminal procedures (SIDs, STARs
+// and approaches) have leg types which cannot be expressed as a list of
+// waypoints, so they are modelled separately in `navdata::procedure`.
 
 
 impl Route {
@@ -47,25 +47,23 @@ impl Route {
     pub fn new(name: Option<String>) -> Route {
         Route {
             name: name,
-            waypoints: LinkedList::new(),
+            waypoints: VecDeque::new(),
         }
     }
 
     /// Move all legs from `other` route to the end of this route.
-    /// This reuses all the nodes from `other` and moves them into
-    /// `self`. After this operation `other` route is emptied.
-    /// This operation should be in O(1) time and memory.
+    /// After this operation `other` route is emptied.
     pub fn append(&mut self, other: &mut Route) {
         self.waypoints.append(&mut other.waypoints);
     }
 
     /// Insert a waypoint into this route at the given index position.
-    pub fn insert_waypoint(&mut self, index: usize, waypoint: Rc<Waypoint>) {
+    pub fn insert_waypoint(&mut self, index: usize, waypoint: Arc<WaypointInterface>) {
         self.waypoints.insert(index, waypoint);
     }
 
     /// Append a waypoint to the end of this route.
-    pub fn append_waypoint(&mut self, waypoint: Rc<Waypoint>) {
+    pub fn append_waypoint(&mut self, waypoint: Arc<WaypointInterface>) {
         self.waypoints.push_back(waypoint);
     }
 
@@ -75,12 +73,31 @@ impl Route {
     }
 
     /// Get the first waypoint in the route.
-    pub fn first(&self) -> Option<&Rc<Waypoint>> {
+    pub fn first(&self) -> Option<&Arc<WaypointInterface>> {
         return self.waypoints.front();
     }
 
     /// Get the last waypoint in the route.
-    pub fn last(&self) -> Option<&Rc<Waypoint>> {
+    pub fn last(&self) -> Option<&Arc<WaypointInterface>> {
         return self.waypoints.back();
     }
+
+    /// Get the countries which the route crosses, in the order in which
+    /// they are first reached.
+    ///
+    /// Only the countries of the route's waypoints are known, so a country
+    /// which is crossed between two waypoints isn't included. Countries
+    /// with several ICAO regions are only listed once.
+    pub fn countries(&self) -> Vec<Arc<Country>> {
+        let mut countries: Vec<Arc<Country>> = Vec::new();
+        for waypoint in &self.waypoints {
+            if let Some(country) = waypoint.country() {
+                if !countries.iter().any(|c| c.name == country.name) {
+                    countries.push(country.clone());
+                }
+            }
+        }
+
+        return countries;
+    }
 }
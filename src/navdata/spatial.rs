@@ -0,0 +1,368 @@
+//! A spatial index of waypoints, for nearest, radius and bounding box
+//! queries.
+//!
+//! Waypoints are stored in buckets keyed by the integer geohash of the cell
+//! containing them. A query visits only the cells which overlap the region
+//! being searched, walking from one to the next with `geohash::neighbor()`,
+//! then filters their waypoints by exact distance. The cells wrap around
+//! the antimeridian, and every longitude is searched when a region reaches
+//! over a pole.
+
+use navdata::coord::{SphericalCoordinate, EARTH_MSL_RADIUS, METERS_PER_NAUTICAL_MILE};
+use navdata::database::WaypointCandidate;
+use navdata::geohash::{self, Bounds, LATLON_BOUNDS};
+use navdata::kind::WaypointKinds;
+use navdata::waypoint::WaypointInterface;
+use nalgebra::Vector2;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::PI;
+use std::fmt;
+use std::sync::Arc;
+
+/// Default geohash precision of the cells in a `SpatialIndex`. 16 bits
+/// gives cells of 1.4 by 0.7 degrees, around 150 by 80km at the equator.
+pub static SPATIAL_INDEX_PRECISION: u8 = 16;
+
+/// A spatial index of waypoints, bucketed by geohash.
+///
+/// # Examples
+///
+/// ```
+/// # use oldnav_lib::navdata::coord::SphericalCoordinate;
+/// # use oldnav_lib::navdata::kind::WaypointKinds;
+/// # use oldnav_lib::navdata::spatial::SpatialIndex;
+/// # use oldnav_lib::navdata::waypoint::{Waypoint, WaypointInterface};
+/// # use std::sync::Arc;
+/// let mut index = SpatialIndex::new();
+/// let pos = SphericalCoordinate::from_geographic(0.0, -37.5, 145.0);
+/// index.insert(Arc::new(Waypoint::new("WENDY", "WENDY", pos, None)));
+///
+/// let aircraft = SphericalCoordinate::from_geographic(0.0, -37.7, 144.8);
+/// let nearest = index.nearest(&aircraft, 20, WaypointKinds::all());
+/// assert_eq!("WENDY", nearest[0].waypoint.code());
+/// assert!(index.within_radius_nm(&aircraft, 10.0, WaypointKinds::all()).is_empty());
+/// ```
+pub struct SpatialIndex {
+    precision: u8,
+    n_x: usize,
+    n_y: usize,
+    buckets: HashMap<u64, Vec<Arc<WaypointInterface>>>,
+    len: usize,
+}
+
+impl SpatialIndex {
+    /// An empty index with cells of `SPATIAL_INDEX_PRECISION`.
+    pub fn new() -> SpatialIndex {
+        return SpatialIndex::with_precision(SPATIAL_INDEX_PRECISION);
+    }
+
+    /// An empty index with cells of the given geohash precision (in bits),
+    /// from 1 to 32. Finer cells suit dense data and small queries.
+    pub fn with_precision(precision: u8) -> SpatialIndex {
+        assert!(
+            precision >= geohash::PRECISION_MIN && precision <= 32,
+            "spatial index precision must be in the range of {} to 32, not {}",
+            geohash::PRECISION_MIN,
+            precision
+        );
+
+        // the geohash bits alternate between x and y, starting with x
+        let x_bits = (precision + 1) / 2;
+        let y_bits = precision / 2;
+
+        return SpatialIndex {
+            precision: precision,
+            n_x: 1 << x_bits,
+            n_y: 1 << y_bits,
+            buckets: HashMap::new(),
+            len: 0,
+        };
+    }
+
+    /// The geohash precision of the cells in this index.
+    pub fn precision(&self) -> u8 {
+        return self.precision;
+    }
+
+    /// The number of waypoints in this index.
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    /// Whether this index is empty.
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    /// Add a waypoint to this index.
+    pub fn insert(&mut self, waypoint: Arc<WaypointInterface>) {
+        let x = self.cell_x(waypoint.pos().lon());
+        let y = self.cell_y(waypoint.pos().lat());
+        let key = self.cell_key(x, y);
+        self.buckets.entry(key).or_insert_with(Vec::new).push(waypoint);
+        self.len += 1;
+    }
+
+    /// Find the `n` waypoints of one of the `kinds` closest to `position`,
+    /// closest first.
+    pub fn nearest(
+        &self,
+        position: &SphericalCoordinate,
+        n: usize,
+        kinds: WaypointKinds,
+    ) -> Vec<WaypointCandidate> {
+        if n == 0 || self.is_empty() {
+            return Vec::new();
+        }
+
+        // widen the search until it contains n waypoints, all of which are
+        // then closer than any waypoint outside of it. Each cell is only
+        // visited by the first search which overlaps it, so the distance to
+        // each waypoint is found once.
+        let max_radius = PI * EARTH_MSL_RADIUS;
+        let mut radius = self.cell_height().to_radians() * EARTH_MSL_RADIUS;
+        let mut visited: HashSet<u64> = HashSet::new();
+        let mut found: Vec<(f64, &Arc<WaypointInterface>)> = Vec::new();
+        loop {
+            let (lat_min, lat_max, lon_range) = cap_bounds(position, radius);
+            for (key, waypoints) in self.cells(lat_min, lat_max, lon_range) {
+                if !visited.insert(key) {
+                    continue;
+                }
+
+                for waypoint in waypoints {
+                    if kinds.contains(waypoint.kind()) {
+                        found.push((waypoint.pos().arc_distance(position), waypoint));
+                    }
+                }
+            }
+
+            let n_within = found
+                .iter()
+                .filter(|&&(distance, _)| distance <= radius)
+                .count();
+            if n_within >= n || radius >= max_radius {
+                found.retain(|&(distance, _)| distance <= radius);
+                return closest_candidates(found, n);
+            }
+
+            radius *= 2.0;
+        }
+    }
+
+    /// Find the waypoints of one of the `kinds` within `radius` (in
+    /// meters) of `position`, closest first.
+    pub fn within_radius(
+        &self,
+        position: &SphericalCoordinate,
+        radius: f64,
+        kinds: WaypointKinds,
+    ) -> Vec<WaypointCandidate> {
+        let (lat_min, lat_max, lon_range) = cap_bounds(position, radius);
+
+        let mut found: Vec<(f64, &Arc<WaypointInterface>)> = Vec::new();
+        for (_, waypoints) in self.cells(lat_min, lat_max, lon_range) {
+            for waypoint in waypoints {
+                if !kinds.contains(waypoint.kind()) {
+                    continue;
+                }
+
+                let distance = waypoint.pos().arc_distance(position);
+                if distance <= radius {
+                    found.push((distance, waypoint));
+                }
+            }
+        }
+
+        let n = found.len();
+        return closest_candidates(found, n);
+    }
+
+    /// Find the waypoints of one of the `kinds` within `radius` (in nautical
+    /// miles) of `position`, closest first.
+    pub fn within_radius_nm(
+        &self,
+        position: &SphericalCoordinate,
+        radius: f64,
+        kinds: WaypointKinds,
+    ) -> Vec<WaypointCandidate> {
+        return self.within_radius(position, radius * METERS_PER_NAUTICAL_MILE, kinds);
+    }
+
+    /// Find the waypoints of one of the `kinds` within a latitude/longitude
+    /// box, where x is the longitude and y the latitude. A box with an
+    /// `x_min` greater than its `x_max` crosses the antimeridian.
+    pub fn within_bounds(
+        &self,
+        bounds: &Bounds,
+        kinds: WaypointKinds,
+    ) -> Vec<Arc<WaypointInterface>> {
+        let wraps = bounds.x_min > bounds.x_max;
+        let in_lon_range = |lon: f64| if wraps {
+            lon >= bounds.x_min || lon <= bounds.x_max
+        } else {
+            lon >= bounds.x_min && lon <= bounds.x_max
+        };
+
+        let lon_range = if wraps {
+            Some((bounds.x_min, bounds.x_max + 360.0))
+        } else {
+            Some((bounds.x_min, bounds.x_max))
+        };
+
+        let mut found = Vec::new();
+        for (_, waypoints) in self.cells(bounds.y_min, bounds.y_max, lon_range) {
+            for waypoint in waypoints {
+                let pos = waypoint.pos();
+                if pos.lat() >= bounds.y_min && pos.lat() <= bounds.y_max &&
+                    in_lon_range(pos.lon()) && kinds.contains(waypoint.kind())
+                {
+                    found.push(waypoint.clone());
+                }
+            }
+        }
+
+        return found;
+    }
+
+    /// The keys and buckets of the cells overlapping a range of latitudes,
+    /// and a range of longitudes (which may extend past ±180), or all
+    /// longitudes if it is `None`.
+    fn cells(
+        &self,
+        lat_min: f64,
+        lat_max: f64,
+        lon_range: Option<(f64, f64)>,
+    ) -> Vec<(u64, &Vec<Arc<WaypointInterface>>)> {
+        let (first_x, n_columns) = match lon_range {
+            Some((lon_min, lon_max)) if lon_max - lon_min < 360.0 - self.cell_width() => {
+                let first = self.cell_x(lon_min);
+                let last = self.cell_x(lon_max);
+                (first, (last + self.n_x - first) % self.n_x + 1)
+            }
+            _ => (0, self.n_x),
+        };
+
+        let first_y = self.cell_y(lat_min);
+        let n_rows = self.cell_y(lat_max) - first_y + 1;
+
+        // walk east along each row from its first cell, and north to the
+        // first cell of the next row
+        let mut cells = Vec::new();
+        let mut row = self.cell_key(first_x, first_y);
+        for _ in 0..n_rows {
+            let mut key = row;
+            for _ in 0..n_columns {
+                if let Some(waypoints) = self.buckets.get(&key) {
+                    cells.push((key, waypoints));
+                }
+                key = neighbor(key, (1, 0));
+            }
+            row = neighbor(row, (0, 1));
+        }
+
+        return cells;
+    }
+
+    /// Width of a cell in degrees of longitude.
+    fn cell_width(&self) -> f64 {
+        return LATLON_BOUNDS.x_range() / self.n_x as f64;
+    }
+
+    /// Height of a cell in degrees of latitude.
+    fn cell_height(&self) -> f64 {
+        return LATLON_BOUNDS.y_range() / self.n_y as f64;
+    }
+
+    /// The column of cells containing a longitude, wrapping around the
+    /// antimeridian.
+    fn cell_x(&self, lon: f64) -> usize {
+        let offset = (lon - LATLON_BOUNDS.x_min) % 360.0;
+        let offset = if offset < 0.0 { offset + 360.0 } else { offset };
+        return ((offset / self.cell_width()) as usize).min(self.n_x - 1);
+    }
+
+    /// The row of cells containing a latitude.
+    fn cell_y(&self, lat: f64) -> usize {
+        let offset = (lat - LATLON_BOUNDS.y_min).max(0.0);
+        return ((offset / self.cell_height()) as usize).min(self.n_y - 1);
+    }
+
+    /// The geohash of a cell, encoded from its centre so that it is
+    /// unaffected by rounding at the cell's edges.
+    fn cell_key(&self, x: usize, y: usize) -> u64 {
+        let centre = Vector2::new(
+            LATLON_BOUNDS.x_min + (x as f64 + 0.5) * self.cell_width(),
+            LATLON_BOUNDS.y_min + (y as f64 + 0.5) * self.cell_height(),
+        );
+
+        return geohash::encode(&centre, self.precision, &LATLON_BOUNDS)
+            .expect("spatial index precision is checked on construction");
+    }
+}
+
+/// The range of latitudes, and of longitudes (which may extend past ±180)
+/// or `None` for all longitudes, covering the spherical cap of `radius` (in
+/// meters) around `position`.
+fn cap_bounds(position: &SphericalCoordinate, radius: f64) -> (f64, f64, Option<(f64, f64)>) {
+    let lat = position.lat();
+    let lon = position.lon();
+    let angle = (radius / EARTH_MSL_RADIUS).to_degrees();
+
+    let lat_min = (lat - angle).max(-90.0);
+    let lat_max = (lat + angle).min(90.0);
+
+    // the widest longitude of a spherical cap is where it touches the
+    // meridians, unless it covers a pole.
+    let sin_angle = angle.to_radians().sin();
+    let cos_lat = lat.to_radians().cos();
+    let covers_pole = lat + angle >= 90.0 || lat - angle <= -90.0 || angle >= 90.0;
+    let lon_range = if covers_pole || sin_angle >= cos_lat {
+        None
+    } else {
+        let delta = (sin_angle / cos_lat).asin().to_degrees();
+        Some((lon - delta, lon + delta))
+    };
+
+    return (lat_min, lat_max, lon_range);
+}
+
+/// The `n` closest of the waypoints `found`, with their distances, as
+/// `WaypointCandidate`s sorted closest first.
+fn closest_candidates(
+    mut found: Vec<(f64, &Arc<WaypointInterface>)>,
+    n: usize,
+) -> Vec<WaypointCandidate> {
+    found.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+    return found
+        .into_iter()
+        .take(n)
+        .map(|(distance, waypoint)| {
+            WaypointCandidate {
+                waypoint: waypoint.clone(),
+                region: String::from(waypoint.region()),
+                kind: waypoint.kind(),
+                distance: distance,
+            }
+        })
+        .collect();
+}
+
+/// The neighboring cell in a direction, wrapping around the antimeridian
+/// and over the poles.
+fn neighbor(key: u64, dir: (i8, i8)) -> u64 {
+    return geohash::neighbor(key, dir, true).expect("spherical neighbors are always in bounds");
+}
+
+impl fmt::Debug for SpatialIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "SpatialIndex: {{precision: {}, n_waypoints: {}, n_buckets: {}}}",
+            self.precision,
+            self.len,
+            self.buckets.len()
+        );
+    }
+}
@@ -2,8 +2,11 @@
 
 use navdata::coord::SphericalCoordinate;
 use navdata::airport::Airport;
+use navdata::country::Country;
+use navdata::geohash::{GeohashError, Geohashable};
+use navdata::kind::{WaypointKind, ArincWaypointType, is_lat_lon_code};
 use std::fmt;
-use std::rc::Rc;
+use std::sync::Arc;
 
 /// An ICAO waypoint
 ///
@@ -12,7 +15,7 @@ use std::rc::Rc;
 /// ```
 /// # use oldnav_lib::navdata::waypoint::Waypoint;
 /// # use oldnav_lib::navdata::coord::SphericalCoordinate;
-/// # use std::rc::Rc;
+/// # use std::sync::Arc;
 /// let pos = SphericalCoordinate::from_geographic(0.0, -9.66483, 161.02166);
 /// let waypoint = Waypoint::new("ERVOS", "ERVOS", pos, None);
 /// ```
@@ -27,11 +30,28 @@ pub struct Waypoint {
     pub pos: SphericalCoordinate,
 
     /// `Airport` terminal area containing this `Waypoint`
-    pub airport: Option<Rc<Airport>>,
+    pub airport: Option<Arc<Airport>>,
+
+    /// ICAO region code (e.g. `DT` or `K1`), empty if unknown
+    pub region: String,
+
+    /// ARINC 424 waypoint type (section 5.42) columns, if known
+    pub waypoint_type: Option<String>,
+
+    /// The `Country` of the ICAO region, if it is known
+    pub country: Option<Arc<Country>>,
+
+    /// Whether this waypoint was defined by the user rather than read from
+    /// the navdata
+    pub user: bool,
 }
 
 /// A common interface for accessing objects which can provide waypoint information.
-pub trait WaypointInterface {
+///
+/// This allows fixes, navaids and airports to be stored side by side in
+/// `Route`s and `Database` lookups. Implementations need to be `Send` and
+/// `Sync`, so that a `Database` can be loaded and used on other threads.
+pub trait WaypointInterface: fmt::Debug + Send + Sync {
     /// Get the ICAO code for this waypoint.
     fn code(&self) -> &str;
 
@@ -40,6 +60,15 @@ pub trait WaypointInterface {
 
     /// Get the position of this waypoint.
     fn pos(&self) -> &SphericalCoordinate;
+
+    /// Get the ICAO region code for this waypoint.
+    fn region(&self) -> &str;
+
+    /// Get the country of this waypoint's ICAO region, if it is known.
+    fn country(&self) -> Option<&Arc<Country>>;
+
+    /// Get the kind of this waypoint.
+    fn kind(&self) -> WaypointKind;
 }
 
 impl Waypoint {
@@ -48,15 +77,56 @@ impl Waypoint {
         code: S,
         name: S,
         pos: SphericalCoordinate,
-        airport: Option<Rc<Airport>>,
+        airport: Option<Arc<Airport>>,
     ) -> Waypoint {
         return Waypoint {
             code: code.into(),
             name: name.into(),
             pos: pos,
             airport: airport,
+            region: String::new(),
+            waypoint_type: None,
+            country: None,
+            user: false,
         };
     }
+
+    /// Get the ARINC 424 waypoint type columns, if they are known.
+    pub fn arinc_type(&self) -> Option<ArincWaypointType> {
+        return self.waypoint_type.as_ref().map(|columns| ArincWaypointType::new(columns));
+    }
+}
+
+/// Classify a fix from its code, ARINC 424 type columns, whether it is in
+/// a terminal area and whether it was defined by the user.
+fn fix_kind(
+    code: &str,
+    waypoint_type: Option<&String>,
+    is_terminal: bool,
+    user: bool,
+) -> WaypointKind {
+    let arinc_type = waypoint_type.map(|columns| ArincWaypointType::new(columns));
+
+    if user {
+        return WaypointKind::User;
+    }
+
+    if is_lat_lon_code(code) || arinc_type.map(|t| t.is_lat_lon()).unwrap_or(false) {
+        return WaypointKind::LatLon;
+    }
+
+    // runway thresholds are listed as terminal fixes such as RW16L
+    let is_runway_code = code.starts_with("RW") && code.len() >= 4 &&
+        code[2..4].chars().all(|c| c.is_digit(10));
+    if arinc_type.map(|t| t.is_runway()).unwrap_or(false) || (is_terminal && is_runway_code) {
+        return WaypointKind::RunwayThreshold;
+    }
+
+    if is_terminal {
+        return WaypointKind::TerminalFix;
+    }
+
+    return WaypointKind::EnrouteFix;
 }
 
 impl WaypointInterface for Waypoint {
@@ -71,6 +141,23 @@ impl WaypointInterface for Waypoint {
     fn pos(&self) -> &SphericalCoordinate {
         return &self.pos;
     }
+
+    fn region(&self) -> &str {
+        return &self.region;
+    }
+
+    fn country(&self) -> Option<&Arc<Country>> {
+        return self.country.as_ref();
+    }
+
+    fn kind(&self) -> WaypointKind {
+        return fix_kind(
+            &self.code,
+            self.waypoint_type.as_ref(),
+            self.airport.is_some(),
+            self.user,
+        );
+    }
 }
 
 impl WaypointInterface for UnlinkedWaypoint {
@@ -85,8 +172,39 @@ impl WaypointInterface for UnlinkedWaypoint {
     fn pos(&self) -> &SphericalCoordinate {
         return &self.pos;
     }
+
+    fn region(&self) -> &str {
+        return &self.region;
+    }
+
+    fn country(&self) -> Option<&Arc<Country>> {
+        return None;
+    }
+
+    fn kind(&self) -> WaypointKind {
+        return fix_kind(
+            &self.code,
+            self.waypoint_type.as_ref(),
+            self.airport.is_some(),
+            false,
+        );
+    }
 }
 
+/// Waypoints are geohashed by their position, and a geohash decodes to the
+/// position at the centre of its cell.
+impl<W: WaypointInterface + ?Sized> Geohashable<SphericalCoordinate> for Arc<W> {
+    fn integer_decode(geohash: u64) -> Result<SphericalCoordinate, GeohashError> {
+        return SphericalCoordinate::integer_decode(geohash);
+    }
+
+    fn integer_encode(&self, precision: u8) -> Result<u64, GeohashError> {
+        return self.pos().integer_encode(precision);
+    }
+}
+
+/// A `Waypoint` as it is read from the navdata files, before it has been
+/// linked to the other objects in the `Database`.
 #[derive(Debug)]
 pub struct UnlinkedWaypoint {
     /// ICAO airport code
@@ -98,13 +216,19 @@ pub struct UnlinkedWaypoint {
     /// Position of airport
     pub pos: SphericalCoordinate,
 
-    /// `Airport` terminal area containing this `Waypoint`
+    /// Code of the `Airport` terminal area containing this `Waypoint`
     pub airport: Option<String>,
+
+    /// ICAO region code (e.g. `DT` or `K1`), empty if unknown
+    pub region: String,
+
+    /// ARINC 424 waypoint type (section 5.42) columns, if known
+    pub waypoint_type: Option<String>,
 }
 
 
 impl UnlinkedWaypoint {
-    /// Constructor for `Waypoint`.
+    /// Constructor for `UnlinkedWaypoint`.
     pub fn new<S: Into<String>>(
         code: S,
         name: S,
@@ -116,6 +240,22 @@ impl UnlinkedWaypoint {
             name: name.into(),
             pos: pos,
             airport: airport,
+            region: String::new(),
+            waypoint_type: None,
+        };
+    }
+
+    /// Convert into a `Waypoint`, linking it to its terminal area `Airport`.
+    pub fn link(self, airport: Option<Arc<Airport>>) -> Waypoint {
+        return Waypoint {
+            code: self.code,
+            name: self.name,
+            pos: self.pos,
+            airport: airport,
+            region: self.region,
+            waypoint_type: self.waypoint_type,
+            country: None,
+            user: false,
         };
     }
 }
@@ -127,14 +267,21 @@ impl fmt::Debug for Waypoint {
             None => "None",
             Some(airport) => airport.code(),
         };
+        let country_str = match self.country.as_ref() {
+            None => "None",
+            Some(country) => &country.name,
+        };
         return write!(
             f,
-            "Waypoint {{code: {}, name: {}, pos: [{},{}], country: {}}}",
+            "Waypoint {{code: {}, name: {}, pos: [{},{}], airport: {}, region: {}, \
+             country: {}}}",
             self.code,
             self.name,
             self.pos.lat(),
             self.pos.lon(),
-            airport_str
+            airport_str,
+            self.region,
+            country_str
         );
 
     }
@@ -0,0 +1,53 @@
+extern crate oldnav_lib;
+extern crate chrono;
+
+use oldnav_lib::navdata::airac::AiracCycle;
+use chrono::{UTC, TimeZone, Duration};
+
+#[test]
+fn test_cycle_dates() {
+    // effective dates of cycles from the published AIRAC calendar
+    let cycles = [
+        (1413, (2014, 12, 11)),
+        (1501, (2015, 1, 8)),
+        (1709, (2017, 8, 17)),
+        (1713, (2017, 12, 7)),
+        (1801, (2018, 1, 4)),
+        (2001, (2020, 1, 2)),
+        (2014, (2020, 12, 31)),
+        (2101, (2021, 1, 28)),
+    ];
+
+    for &(ident, (year, month, day)) in cycles.iter() {
+        let effective = UTC.ymd(year, month, day).and_hms(0, 0, 0);
+        let cycle = AiracCycle::from_ident(ident).unwrap();
+
+        assert_eq!(effective, cycle.effective());
+        assert_eq!(ident, AiracCycle::from_date(effective).ident());
+
+        let last_second = cycle.expires() - Duration::seconds(1);
+        assert_eq!(ident, AiracCycle::from_date(last_second).ident());
+    }
+}
+
+#[test]
+fn test_next_previous() {
+    let cycle = AiracCycle::from_ident(1713).unwrap();
+    assert_eq!(1801, cycle.next().ident());
+    assert_eq!(1712, cycle.previous().ident());
+    assert_eq!(cycle, cycle.next().previous());
+
+    // 2020 has 14 cycles
+    assert_eq!(2014, AiracCycle::from_ident(2013).unwrap().next().ident());
+    assert_eq!(2101, AiracCycle::from_ident(2014).unwrap().next().ident());
+    assert_eq!(None, AiracCycle::from_ident(2114));
+}
+
+#[test]
+fn test_is_current() {
+    let cycle = AiracCycle::from_ident(1709).unwrap();
+    assert!(cycle.is_current(UTC.ymd(2017, 8, 17).and_hms(0, 0, 0)));
+    assert!(cycle.is_current(UTC.ymd(2017, 9, 13).and_hms(23, 59, 59)));
+    assert!(!cycle.is_current(UTC.ymd(2017, 9, 14).and_hms(0, 0, 0)));
+    assert_eq!("1709", cycle.to_string());
+}
@@ -0,0 +1,95 @@
+//! Helpers shared by the integration tests and the benchmarks.
+
+// each test and benchmark crate uses only some of the helpers
+#![allow(dead_code)]
+
+use oldnav_lib::navdata::coord::SphericalCoordinate;
+use oldnav_lib::navdata::waypoint::Waypoint;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{env, fs, process};
+
+pub fn test_data_dir(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("data")
+        .join(name)
+}
+
+pub fn resources_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources")
+}
+
+/// Copy a directory of test navdata to a new temporary directory named
+/// after `name`, so that it is parsed rather than read from a cache, and the
+/// cache written to it stays out of the checked in test data and doesn't
+/// affect the other tests.
+pub fn copy_test_data(data: &str, name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("oldnav-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    copy_dir(&test_data_dir(data), &dir);
+    return dir;
+}
+
+/// Copy the x-plane 11 test navdata, see `copy_test_data()`.
+pub fn copy_xp11_data(name: &str) -> PathBuf {
+    return copy_test_data("xp11", name);
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let path = entry.path();
+        if path.is_dir() {
+            copy_dir(&path, &to.join(entry.file_name()));
+        } else {
+            fs::copy(&path, to.join(entry.file_name())).unwrap();
+        }
+    }
+}
+
+/// A simple linear congruential generator, so that the tests and
+/// benchmarks are repeatable.
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        return Random { state: seed };
+    }
+
+    /// The next number in `[0, 1)`.
+    pub fn next(&mut self) -> f64 {
+        self.state = self.state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        return (self.state >> 11) as f64 / (1u64 << 53) as f64;
+    }
+
+    /// A latitude and longitude in degrees, evenly spread over the whole
+    /// earth.
+    pub fn position(&mut self) -> (f64, f64) {
+        let lat = (self.next() * 2.0 - 1.0).asin().to_degrees();
+        let lon = self.next() * 360.0 - 180.0;
+        return (lat, lon);
+    }
+}
+
+pub fn waypoint(code: &str, lat: f64, lon: f64) -> Arc<Waypoint> {
+    let pos = SphericalCoordinate::from_geographic(0.0, lat, lon);
+    return Arc::new(Waypoint::new(code, code, pos, None));
+}
+
+/// `n` waypoints named `W0`, `W1`, ... spread over the whole earth.
+pub fn scattered_waypoints(n: usize) -> Vec<Arc<Waypoint>> {
+    let mut random = Random::new(12345);
+    return (0..n)
+        .map(|i| {
+            let (lat, lon) = random.position();
+            waypoint(&format!("W{}", i), lat, lon)
+        })
+        .collect();
+}
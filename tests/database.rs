@@ -0,0 +1,773 @@
+extern crate oldnav_lib;
+extern crate chrono;
+
+mod common;
+
+use oldnav_lib::navdata::database::{Database, DatabaseError, LoadMode, NavdataFormat, CycleInfo,
+                                    CalendarMismatch};
+use oldnav_lib::navdata::navaid::{Navaid, NavaidType};
+use oldnav_lib::navdata::airport::{Airport, AirportType, FrequencyType, RunwayType, Surface};
+use oldnav_lib::navdata::waypoint::{Waypoint, WaypointInterface};
+use oldnav_lib::navdata::airway::{AirwayGraph, AirwayNodeKey, AirwayNodeType, AirwaySegment,
+                                  AirwayDirection, AirwayClass};
+use oldnav_lib::navdata::route::Route;
+use oldnav_lib::navdata::coord::SphericalCoordinate;
+use oldnav_lib::navdata::procedure::{ProcedureType, PathTerminator, TurnDirection, Altitude,
+                                     AltitudeDescription, SpeedDescription};
+use oldnav_lib::navdata::cache::CACHE_FILE_NAME;
+use oldnav_lib::navdata::kind::{WaypointKind, WaypointKinds};
+use std::{fs, thread};
+use std::sync::Arc;
+use chrono::{UTC, TimeZone};
+use common::{copy_test_data, copy_xp11_data, resources_dir, test_data_dir};
+
+#[test]
+fn test_read_fixes() {
+    let db = Database::new(copy_xp11_data("read-fixes"), resources_dir()).unwrap();
+    assert_eq!(6, db.fixes.len());
+
+    let fix = db.fixes.iter().find(|fix| fix.code == "07EBA").unwrap();
+    assert_eq!("DT", fix.region);
+    assert_eq!(Some(String::from("RU ")), fix.waypoint_type);
+    assert!((fix.pos.lat() - 33.492513889).abs() < 0.000001);
+    assert!((fix.pos.lon() - 9.2174).abs() < 0.000001);
+
+    // two fixes share the same ident in different regions
+    let wendy = db.waypoint_hash.get(&String::from("WENDY")).unwrap();
+    assert_eq!(2, wendy.len());
+}
+
+#[test]
+fn test_read_navaids() {
+    let db = Database::new(copy_xp11_data("read-navaids"), resources_dir()).unwrap();
+    assert_eq!(8, db.navaids.len());
+
+    let ndb = db.navaids.iter().find(|navaid| navaid.code() == "APH").unwrap();
+    assert_eq!(NavaidType::Ndb, ndb.navaid_type);
+    assert_eq!(Some(396.0), ndb.frequency);
+    assert_eq!("A P HILL NDB", ndb.name());
+
+    let vortac = db.navaids.iter().find(|navaid| navaid.code() == "SEA").unwrap();
+    assert_eq!(NavaidType::Vortac, vortac.navaid_type);
+    assert_eq!(Some(116800.0), vortac.frequency);
+    assert_eq!(Some(19.0), vortac.slaved_variation);
+    assert_eq!("K1", vortac.region());
+
+    let vor_dme = db.navaids.iter().find(|navaid| navaid.code() == "ML").unwrap();
+    assert_eq!(NavaidType::VorDme, vor_dme.navaid_type);
+
+    let glideslope = db.navaids
+        .iter()
+        .find(|navaid| navaid.navaid_type == NavaidType::Glideslope)
+        .unwrap();
+    assert_eq!(Some(String::from("16L")), glideslope.runway);
+    assert!((glideslope.glide_path_angle.unwrap() - 3.0).abs() < 0.0001);
+    assert!((glideslope.bearing.unwrap() - 180.343).abs() < 0.0001);
+
+    let fpap = db.navaids
+        .iter()
+        .find(|navaid| navaid.navaid_type == NavaidType::Fpap)
+        .unwrap();
+    assert_eq!(Some(56000), fpap.channel);
+    assert_eq!("LPV", fpap.name());
+
+    // navaids can be looked up alongside fixes
+    let sea = db.waypoint_hash.get(&String::from("SEA")).unwrap();
+    assert_eq!(2, sea.len());
+}
+
+#[test]
+fn test_read_airways() {
+    let db = Database::new(copy_xp11_data("read-airways"), resources_dir()).unwrap();
+
+    // the segment to a fix which doesn't exist is skipped, and the segment
+    // which is part of J1 and J20 is inserted for each of them
+    assert_eq!(5, db.airway_graph.segments().len());
+
+    // H65 is made up of two unconnected airways in different regions
+    let h65 = db.airways.get(&String::from("H65")).unwrap();
+    assert_eq!(2, h65.len());
+
+    let australian_h65 = h65.iter().find(|airway| airway.segments.len() == 2).unwrap();
+    let route = australian_h65.route(&db.airway_graph);
+    let codes: Vec<&str> = route.waypoints.iter().map(|waypoint| waypoint.code()).collect();
+    assert!(codes == vec!["BOLTY", "WENDY", "ML"] || codes == vec!["ML", "WENDY", "BOLTY"]);
+
+    // J1 may only be flown from SEA to APH
+    let route = db.airway_graph.airway_route("J1", "SEA", "APH").unwrap();
+    assert_eq!(2, route.len());
+    assert_eq!("APH", route.last().unwrap().code());
+    assert!(db.airway_graph.airway_route("J1", "APH", "SEA").is_none());
+}
+
+#[test]
+fn test_build_branching_airways() {
+    // Y1 runs from A to E, with a branch from B to D
+    let mut graph = AirwayGraph::new();
+    let nodes: Vec<usize> = ["A", "B", "C", "D", "E"]
+        .iter()
+        .enumerate()
+        .map(|(i, code)| {
+            let key = AirwayNodeKey::new(*code, "ZZ", AirwayNodeType::Fix);
+            graph.add_node(key, common::waypoint(code, 0.0, i as f64))
+        })
+        .collect();
+
+    for &(from, to) in &[(0, 1), (1, 2), (1, 3), (2, 4)] {
+        graph.add_segment(AirwaySegment {
+            name: String::from("Y1"),
+            from: nodes[from],
+            to: nodes[to],
+            direction: AirwayDirection::Both,
+            class: AirwayClass::High,
+            base: 180,
+            top: 450,
+        });
+    }
+
+    let airways = graph.build_airways();
+    let mut chains: Vec<String> = airways
+        .iter()
+        .map(|airway| {
+            // each segment joins the nodes on either side of it
+            assert_eq!(airway.segments.len() + 1, airway.nodes.len());
+            for (segment, pair) in airway.segments.iter().zip(airway.nodes.windows(2)) {
+                assert!(
+                    (segment.from, segment.to) == (pair[0], pair[1]) ||
+                        (segment.to, segment.from) == (pair[0], pair[1])
+                );
+            }
+
+            let route = airway.route(&graph);
+            let mut codes: Vec<&str> = route
+                .waypoints
+                .iter()
+                .map(|waypoint| waypoint.code())
+                .collect();
+            if codes[0] > codes[codes.len() - 1] {
+                codes.reverse();
+            }
+            codes.join("-")
+        })
+        .collect();
+    chains.sort();
+
+    assert_eq!(vec!["A-B", "B-C-E", "B-D"], chains);
+}
+
+#[test]
+fn test_find_route() {
+    let db = Database::new(copy_xp11_data("find-route"), resources_dir()).unwrap();
+    let graph = &db.airway_graph;
+
+    let bolty = graph.find_nodes("BOLTY")[0];
+    let ml = graph.find_nodes("ML")[0];
+
+    let route = graph.find_route(bolty, ml, None).unwrap();
+    let codes: Vec<&str> = route.waypoints.iter().map(|waypoint| waypoint.code()).collect();
+    assert_eq!(vec!["BOLTY", "WENDY", "ML"], codes);
+
+    // H65 has a top of FL250
+    assert!(graph.find_route(bolty, ml, Some(300)).is_none());
+
+    // nodes which are not in the graph
+    let missing = graph.node_keys().len();
+    assert!(graph.find_route(missing, ml, None).is_none());
+    assert!(graph.find_route(bolty, missing, None).is_none());
+}
+
+#[test]
+fn test_waypoint_kinds() {
+    let db = Database::new(copy_xp11_data("waypoint-kinds"), resources_dir()).unwrap();
+    let kind = |code: &str| db.find_waypoints(code, WaypointKinds::all())[0].kind();
+
+    assert_eq!(WaypointKind::EnrouteFix, kind("07EBA"));
+    assert_eq!(WaypointKind::LatLon, kind("1630N"));
+    assert_eq!(WaypointKind::TerminalFix, kind("BOLTY"));
+    assert_eq!(WaypointKind::Ndb, kind("APH"));
+    assert_eq!(WaypointKind::Airport, kind("KSEA"));
+    assert_eq!(WaypointKind::Ils, kind("ISNQ"));
+
+    let bolty = db.fixes.iter().find(|fix| fix.code == "BOLTY").unwrap();
+    let arinc_type = bolty.arinc_type().unwrap();
+    assert!(arinc_type.is_rnav() && arinc_type.is_star());
+
+    // the VOR and DME elements of a VORTAC can be found separately
+    assert_eq!(2, db.find_waypoints("SEA", WaypointKinds::all()).len());
+    let dme = db.find_waypoints("SEA", WaypointKind::Dme.into());
+    assert_eq!(1, dme.len());
+    assert_eq!(WaypointKind::Dme, dme[0].kind());
+
+    // an ADF page only searches NDBs
+    assert_eq!(1, db.find_waypoints("APH", WaypointKind::Ndb.into()).len());
+    assert!(db.find_waypoints("SEA", WaypointKind::Ndb.into()).is_empty());
+    assert!(db.find_waypoints("APH", WaypointKinds::fixes()).is_empty());
+    assert!(db.find_waypoints("NOTHR", WaypointKinds::all()).is_empty());
+
+    let pos = db.airport_hash.get("KSEA").unwrap().pos().clone();
+    let vor = db.match_waypoint_dist("SEA", &pos, 10000.0, WaypointKind::Vor.into());
+    assert_eq!(WaypointKind::Vor, vor.found().unwrap().kind());
+    assert!(db.match_waypoint_dist("SEA", &pos, 10000.0, WaypointKinds::fixes()).is_none());
+
+    let australia = db.waypoints_in_country("Australia", WaypointKinds::navaids());
+    assert_eq!(1, australia.len());
+    assert_eq!("ML", australia[0].code());
+    let ils = db.waypoints_in_country("United States - Northwest", WaypointKind::Ils.into());
+    assert!(ils.iter().all(|waypoint| waypoint.kind() == WaypointKind::Ils));
+    assert_eq!(4, ils.len());
+}
+
+#[test]
+fn test_match_waypoint_dist() {
+    let db = Database::new(copy_xp11_data("match-waypoint-dist"), resources_dir()).unwrap();
+    let melbourne = SphericalCoordinate::from_geographic(0.0, -37.7, 144.8);
+    let london = SphericalCoordinate::from_geographic(0.0, 51.5, -0.1);
+
+    // the closest WENDY is chosen, whichever is first in the database
+    let result = db.match_waypoint_dist("WENDY", &melbourne, 50000.0, WaypointKinds::all());
+    assert!(result.is_found());
+    assert_eq!("YM", result.found().unwrap().region());
+    let result = db.match_waypoint_dist("WENDY", &london, 100000.0, WaypointKinds::all());
+    assert_eq!("EG", result.found().unwrap().region());
+
+    let result = db.match_waypoint_dist("WENDY", &london, 1000.0, WaypointKinds::all());
+    assert!(result.is_too_far());
+    assert!(!result.is_found());
+    assert_eq!("EG", result.too_far().unwrap().region());
+
+    let result = db.match_waypoint_dist("NOTHR", &london, 1000.0, WaypointKinds::all());
+    assert!(result.is_none());
+    assert!(!result.is_too_far());
+
+    // all the candidates, closest first
+    let candidates = db.waypoint_candidates("WENDY", &melbourne, WaypointKinds::all());
+    let regions: Vec<&str> = candidates.iter().map(|c| c.region.as_str()).collect();
+    assert_eq!(vec!["YM", "EG"], regions);
+    assert_eq!(WaypointKind::EnrouteFix, candidates[0].kind);
+    assert!(candidates[0].distance < 50000.0);
+    assert!(candidates[1].distance > 10000000.0);
+
+    // the distance to a waypoint at the position itself isn't NaN
+    let ksea = db.airport_hash.get("KSEA").unwrap();
+    let candidates = db.waypoint_candidates("KSEA", ksea.pos(), WaypointKinds::all());
+    assert!(candidates[0].distance < 1.0);
+}
+
+#[test]
+fn test_nearby_waypoints() {
+    let db = Database::new(copy_xp11_data("nearby-waypoints"), resources_dir()).unwrap();
+    let n_waypoints = db.fixes.len() + db.navaids.len() + db.airports.len();
+    assert_eq!(n_waypoints, db.spatial_index.len());
+
+    let ksea = db.airport_hash.get("KSEA").unwrap();
+    let nearest = db.spatial_index.nearest(ksea.pos(), 1, WaypointKinds::all());
+    assert_eq!("KSEA", nearest[0].waypoint.code());
+
+    let vors = db.spatial_index.within_radius_nm(ksea.pos(), 10.0, WaypointKind::Vor.into());
+    assert_eq!(1, vors.len());
+    assert_eq!("SEA", vors[0].waypoint.code());
+}
+
+#[test]
+fn test_countries() {
+    let db = Database::new(copy_xp11_data("countries"), resources_dir()).unwrap();
+
+    let fix = db.fixes.iter().find(|fix| fix.code == "07EBA").unwrap();
+    assert_eq!("Tunisia", fix.country.as_ref().unwrap().name);
+
+    let sea = db.navaids.iter().find(|navaid| navaid.code() == "SEA").unwrap();
+    assert_eq!("K1", sea.country().unwrap().code);
+    let ksea = db.airport_hash.get("KSEA").unwrap();
+    assert_eq!("United States - Northwest", ksea.country().unwrap().name);
+
+    assert_eq!(vec!["YB", "YM"], db.country_regions("Australia"));
+    let fixes = db.waypoints_in_country("Australia", WaypointKinds::fixes());
+    let mut australia: Vec<&str> = fixes.iter().map(|fix| fix.code()).collect();
+    australia.sort();
+    assert_eq!(vec!["BOLTY", "WENDY"], australia);
+    assert_eq!(1, db.waypoints_in_country("Australia", WaypointKinds::navaids()).len());
+    let airports = db.waypoints_in_country("Australia", WaypointKind::Airport.into());
+    assert_eq!("YMML", airports[0].code());
+    assert!(db.waypoints_in_country("Atlantis", WaypointKinds::all()).is_empty());
+
+    let mut route = Route::new(None);
+    for &(code, region) in &[("BOLTY", "YM"), ("WENDY", "EG"), ("1630N", "GV"), ("1730N", "GV")] {
+        let fix = db.fixes.iter().find(|fix| fix.code == code && fix.region == region).unwrap();
+        route.append_waypoint(fix.clone());
+    }
+    route.append_waypoint(db.airport_hash.get("YMML").unwrap().clone());
+
+    let countries: Vec<String> = route.countries().iter().map(|c| c.name.clone()).collect();
+    assert_eq!(vec!["Australia", "United Kingdom", "Cape Verde"], countries);
+}
+
+#[test]
+fn test_read_airports() {
+    let db = Database::new(copy_xp11_data("read-airports"), resources_dir()).unwrap();
+    assert_eq!(4, db.airports.len());
+
+    let ksea = db.airport_hash.get("KSEA").unwrap();
+    assert_eq!("Seattle-Tacoma Intl", ksea.name());
+    assert_eq!("K1", ksea.region());
+    assert_eq!(Some(String::from("SEA")), ksea.iata_code);
+    assert_eq!(Some(String::from("Seattle")), ksea.city);
+    assert_eq!(Some(18000), ksea.transition_altitude);
+    assert!((ksea.pos().lat() - 47.449888889).abs() < 0.000001);
+    assert!((ksea.pos().lon() - -122.311777778).abs() < 0.000001);
+
+    assert_eq!(2, ksea.runways.len());
+    let runway = &ksea.runways[0];
+    assert_eq!(Surface::Concrete, runway.surface);
+    assert!((runway.length - 3627.0).abs() < 5.0);
+    assert!((runway.ends[0].heading - 180.0).abs() < 1.0);
+    assert_eq!(8, runway.ends[0].approach_lighting);
+    assert_eq!(265.0, ksea.runway_end("34C").unwrap().displaced_threshold);
+
+    assert_eq!(3, ksea.frequencies.len());
+    assert_eq!(FrequencyType::Recorded, ksea.frequencies[0].frequency_type);
+    assert_eq!(118000.0, ksea.frequencies[0].frequency);
+    assert_eq!(119900.0, ksea.frequencies[1].frequency);
+
+    // airports without a datum are positioned in the middle of their runways
+    let ymml = db.airport_hash.get("YMML").unwrap();
+    assert!((ymml.pos().lat() - -37.6668).abs() < 0.001);
+
+    let seaplane_base = db.airport_hash.get("W55").unwrap();
+    assert_eq!(AirportType::Seaplane, seaplane_base.airport_type);
+    assert_eq!(RunwayType::Water, seaplane_base.runways[0].runway_type);
+
+    let heliport = db.airport_hash.get("H1").unwrap();
+    assert_eq!(RunwayType::Helipad, heliport.runways[0].runway_type);
+    assert_eq!(45.0, heliport.runways[0].ends[0].heading);
+}
+
+#[test]
+fn test_read_airports_without_trailer() {
+    // the last airport in the file is still read without the `99` row
+    let dir = copy_test_data("no_trailer", "read-airports-without-trailer");
+    for mode in &[LoadMode::Strict, LoadMode::Lenient] {
+        let db = Database::load(dir.clone(), resources_dir(), *mode).unwrap();
+        assert_eq!(4, db.airports.len());
+        assert_eq!(4, db.load_report.file("apt.dat").unwrap().loaded);
+
+        let ymml = db.airport_hash.get("YMML").unwrap();
+        assert_eq!(2, ymml.runways.len());
+        assert!((ymml.pos().lat() - -37.6668).abs() < 0.001);
+    }
+}
+
+#[test]
+fn test_link_airports() {
+    let db = Database::new(copy_xp11_data("link-airports"), resources_dir()).unwrap();
+
+    let bolty = db.fixes.iter().find(|fix| fix.code == "BOLTY").unwrap();
+    assert_eq!("YMML", bolty.airport.as_ref().unwrap().code());
+
+    let localizer = db.navaids.iter().find(|navaid| navaid.code() == "ISNQ").unwrap();
+    assert_eq!("KSEA", localizer.waypoint.airport.as_ref().unwrap().code());
+
+    // airports can be looked up alongside fixes and navaids
+    assert_eq!(1, db.waypoint_hash.get(&String::from("KSEA")).unwrap().len());
+}
+
+#[test]
+fn test_read_procedures() {
+    let db = Database::new(copy_xp11_data("read-procedures"), resources_dir()).unwrap();
+    assert!(db.read_procedures("YMML").unwrap().is_none());
+
+    let procedures = db.read_procedures("KSEA").unwrap().unwrap();
+    assert_eq!(1, procedures.sids.len());
+    assert_eq!(1, procedures.stars.len());
+    assert_eq!(1, procedures.approaches.len());
+    assert_eq!(1, procedures.runways.len());
+    assert_eq!(1, procedures.preferred_routes.len());
+
+    let sid = procedures.procedure(ProcedureType::Sid, "BANGR9").unwrap();
+    assert_eq!(2, sid.transitions.len());
+
+    let runway_transition = sid.transition("RW16L").unwrap();
+    assert_eq!(PathTerminator::CA, runway_transition.legs[0].path_terminator);
+    assert!(runway_transition.legs[0].fix.is_none());
+    assert_eq!(Some(163.0), runway_transition.legs[0].course);
+
+    let leg = &runway_transition.legs[1];
+    assert_eq!(PathTerminator::DF, leg.path_terminator);
+    assert_eq!(Some(TurnDirection::Right), leg.turn_direction);
+    assert!(leg.turn_direction_valid);
+    let speed = leg.speed.unwrap();
+    assert_eq!(SpeedDescription::AtOrBelow, speed.description);
+    assert_eq!(250, speed.speed);
+
+    let leg = &sid.transition("BANGR").unwrap().legs[1];
+    assert!(leg.fly_over);
+    assert_eq!(Some(1.0), leg.rnp);
+    let altitude = leg.altitude.unwrap();
+    assert_eq!(AltitudeDescription::Between, altitude.description);
+    assert_eq!(Some(Altitude::FlightLevel(180)), altitude.altitude1);
+    assert_eq!(Some(Altitude::Feet(10000)), altitude.altitude2);
+
+    let star = &procedures.stars[0];
+    let hold = &star.transitions[0].legs[1];
+    assert_eq!(PathTerminator::HM, hold.path_terminator);
+    assert_eq!(Some(1.0), hold.time);
+    assert_eq!(None, hold.distance);
+    assert_eq!(Some(18000), star.transitions[0].legs[0].transition_altitude);
+
+    let approach = procedures.procedure(ProcedureType::Approach, "I16L").unwrap();
+    assert_eq!(2, approach.transitions.len());
+    let leg = &approach.transition("SEA").unwrap().legs[1];
+    assert_eq!("ISNQ", leg.recommended_navaid.as_ref().unwrap().code);
+    assert_eq!(Some(159.4), leg.theta);
+    assert_eq!(Some(12.0), leg.rho);
+    assert_eq!(Some(160.0), leg.course);
+    assert!(leg.true_course);
+
+    let final_approach = approach.transition("").unwrap();
+    assert_eq!(Some(-3.0), final_approach.legs[0].vertical_angle);
+    assert_eq!(Some(3.5), final_approach.legs[1].arc_radius);
+    assert_eq!("KSEA", final_approach.legs[1].center_fix.as_ref().unwrap().code);
+
+    // procedure fixes resolve to waypoints in the database by region
+    let all = WaypointKinds::all();
+    let wendy_fix = final_approach.legs[0].fix.as_ref().unwrap();
+    let wendy = db.resolve_procedure_fix(wendy_fix, all).unwrap();
+    assert_eq!("YM", wendy.region());
+    let ksea_fix = final_approach.legs[1].center_fix.as_ref().unwrap();
+    let airport = db.resolve_procedure_fix(ksea_fix, all).unwrap();
+    assert_eq!("KSEA", airport.code());
+    assert!(db.resolve_procedure_fix(final_approach.legs[1].fix.as_ref().unwrap(), all).is_none());
+
+    // and only if they are of the kinds asked for
+    assert!(db.resolve_procedure_fix(wendy_fix, WaypointKinds::navaids()).is_none());
+    assert!(db.resolve_procedure_fix(ksea_fix, WaypointKinds::fixes()).is_none());
+    assert!(db.resolve_procedure_fix(ksea_fix, WaypointKind::Airport.into()).is_some());
+}
+
+#[test]
+fn test_detect_format() {
+    assert_eq!(
+        Some(NavdataFormat::XPlane),
+        NavdataFormat::detect(&test_data_dir("xp11"))
+    );
+    assert_eq!(
+        Some(NavdataFormat::Gns430),
+        NavdataFormat::detect(&test_data_dir("gns430"))
+    );
+    assert_eq!(None, NavdataFormat::detect(&resources_dir()));
+
+    let db = Database::new(copy_xp11_data("detect-format"), resources_dir()).unwrap();
+    assert_eq!(NavdataFormat::XPlane, db.format);
+    assert_eq!("x-plane earth_*.dat navdata, AIRAC cycle 1709", db.to_string());
+}
+
+#[test]
+fn test_read_gns430() {
+    let db = Database::new(copy_test_data("gns430", "read-gns430"), resources_dir()).unwrap();
+    assert_eq!(NavdataFormat::Gns430, db.format);
+    assert_eq!(1709, db.cycle_info.airac_cycle);
+    assert_eq!("GNS430 text navdata, AIRAC cycle 1709", db.to_string());
+
+    assert_eq!(5, db.fixes.len());
+    let fix = db.fixes.iter().find(|fix| fix.code == "07EBA").unwrap();
+    assert_eq!("DT", fix.region);
+    assert!((fix.pos.lat() - 33.492514).abs() < 0.000001);
+
+    assert_eq!(4, db.navaids.len());
+    let ndb = db.navaids.iter().find(|navaid| navaid.code() == "APH").unwrap();
+    assert_eq!(NavaidType::Ndb, ndb.navaid_type);
+    assert_eq!(Some(396.0), ndb.frequency);
+    let vor = db.navaids.iter().find(|navaid| navaid.code() == "SEA").unwrap();
+    assert_eq!(NavaidType::VorDme, vor.navaid_type);
+    assert_eq!(Some(116800.0), vor.frequency);
+    assert_eq!("K1", vor.region());
+
+    assert_eq!(2, db.airports.len());
+    let ksea = db.airport_hash.get("KSEA").unwrap();
+    assert_eq!(432.0, ksea.elevation);
+    assert_eq!(Some(18000), ksea.transition_altitude);
+    assert_eq!(2, ksea.runways.len());
+    assert_eq!(2, ksea.runways[0].ends.len());
+    assert_eq!(163.0, ksea.runway_end("16L").unwrap().heading);
+    assert!(ksea.runway_end("34R").is_some());
+    assert_eq!(1, ksea.runways[1].ends.len());
+
+    // BTG is both a fix and a navaid, the airway is matched by position to
+    // whichever is closest.
+    let j1 = db.airways.get(&String::from("J1")).unwrap();
+    assert_eq!(1, j1.len());
+    assert_eq!(3, j1[0].nodes.len());
+
+    // WENDY is matched to the fix in the YM region rather than EG.
+    let h65 = db.airways.get(&String::from("H65")).unwrap();
+    let route = h65[0].route(&db.airway_graph);
+    assert!(route.waypoints.iter().any(|waypoint| {
+        waypoint.code() == "WENDY" && waypoint.region() == "YM"
+    }));
+}
+
+#[test]
+fn test_database_errors() {
+    match Database::new(resources_dir(), resources_dir()) {
+        Err(DatabaseError::NoNavdata { .. }) => {}
+        result => panic!("expected NoNavdata, got {:?}", result.err()),
+    }
+
+    let error = Database::new(test_data_dir("bad_fix"), resources_dir()).err().unwrap();
+    match error {
+        DatabaseError::Parse {
+            ref path,
+            line,
+            column,
+            ref text,
+            ..
+        } => {
+            assert!(path.ends_with("earth_fix.dat"));
+            assert_eq!(5, line);
+            assert_eq!(Some(2), column);
+            assert_eq!(" 16.0.0000000  -30.000000000  1630N ENRT GV 2115145", text);
+        }
+        _ => panic!("expected a parse error, got {}", error),
+    }
+
+    assert!(error.to_string().contains("earth_fix.dat:5:2: Unable to parse fix"));
+}
+
+#[test]
+fn test_lenient_load() {
+    // the invalid lines are an error in strict mode
+    assert!(Database::new(test_data_dir("lenient"), resources_dir()).is_err());
+
+    let dir = copy_test_data("lenient", "lenient-load");
+    let db = Database::load(dir, resources_dir(), LoadMode::Lenient).unwrap();
+    assert_eq!(LoadMode::Lenient, db.load_mode);
+    assert_eq!(2, db.fixes.len());
+    assert_eq!(1, db.navaids.len());
+
+    let report = &db.load_report;
+
+    let fixes = report.file("earth_fix.dat").unwrap();
+    assert_eq!(2, fixes.loaded);
+    assert_eq!(2, fixes.skipped.len());
+    assert_eq!(5, fixes.skipped[0].line);
+    assert!(fixes.skipped[0].reason.starts_with("Unable to parse fix"));
+    assert_eq!(7, fixes.skipped[1].line);
+    assert_eq!("Duplicate line", fixes.skipped[1].reason);
+
+    let navaids = report.file("earth_nav.dat").unwrap();
+    assert_eq!(1, navaids.loaded);
+    assert_eq!(1, navaids.skipped.len());
+    assert_eq!("Unknown navaid type", navaids.skipped[0].reason);
+
+    let airways = report.file("earth_awy.dat").unwrap();
+    assert_eq!(1, airways.loaded);
+    assert_eq!(1, airways.skipped.len());
+    assert_eq!(6, airways.skipped[0].line);
+    assert_eq!(1, airways.unresolved.len());
+    assert_eq!("NOTHR", airways.unresolved[0].code);
+    assert_eq!(Some(String::from("DT")), airways.unresolved[0].region);
+    assert_eq!("airway UL1", airways.unresolved[0].context);
+
+    assert_eq!(4, report.total_skipped());
+    assert_eq!(1, report.total_unresolved());
+    assert!(report.to_string().contains("line 5: NOTHR (DT) not found for airway UL1"));
+}
+
+#[test]
+fn test_strict_load_report() {
+    let db = Database::new(copy_xp11_data("strict-load-report"), resources_dir()).unwrap();
+    let report = &db.load_report;
+
+    assert_eq!(6, report.file("earth_fix.dat").unwrap().loaded);
+    assert_eq!(4, report.file("apt.dat").unwrap().loaded);
+    assert_eq!(0, report.total_skipped());
+
+    // the segment to NOTHR is dropped, as there is no such fix
+    let airways = report.file("earth_awy.dat").unwrap();
+    assert_eq!(4, airways.loaded);
+    assert_eq!(1, airways.unresolved.len());
+    assert_eq!("NOTHR", airways.unresolved[0].code);
+}
+
+#[test]
+fn test_cycle_info() {
+    let db = Database::new(copy_xp11_data("cycle-info"), resources_dir()).unwrap();
+    let cycle_info = &db.cycle_info;
+
+    assert_eq!(1709, cycle_info.airac_cycle);
+    assert_eq!(UTC.ymd(2017, 8, 17).and_hms(0, 0, 0), cycle_info.valid_from);
+    assert_eq!(UTC.ymd(2017, 9, 14).and_hms(0, 0, 0), cycle_info.valid_to);
+    assert!(cycle_info.check_calendar().is_empty());
+
+    let at = UTC.ymd(2017, 9, 1).and_hms(0, 0, 0);
+    assert!(cycle_info.is_current(at));
+    assert!(!cycle_info.is_expired(at));
+    assert_eq!(13, cycle_info.days_remaining(at));
+
+    let at = UTC.ymd(2017, 9, 20).and_hms(0, 0, 0);
+    assert!(cycle_info.is_expired(at));
+    assert_eq!(-6, cycle_info.days_remaining(at));
+
+    let mut wrong = CycleInfo::new(
+        1709,
+        1,
+        UTC.ymd(2017, 8, 17).and_hms(0, 0, 0),
+        UTC.ymd(2017, 8, 17).and_hms(0, 0, 0),
+        String::new(),
+    );
+    assert_eq!(
+        vec![
+            CalendarMismatch::ValidTo {
+                expected: UTC.ymd(2017, 9, 14).and_hms(0, 0, 0),
+                found: UTC.ymd(2017, 8, 17).and_hms(0, 0, 0),
+            },
+        ],
+        wrong.check_calendar()
+    );
+
+    wrong.airac_cycle = 1799;
+    assert_eq!(vec![CalendarMismatch::UnknownCycle(1799)], wrong.check_calendar());
+}
+
+#[test]
+fn test_binary_cache() {
+    let dir = copy_xp11_data("cache");
+
+    let parsed = Database::new(dir.clone(), resources_dir()).unwrap();
+    assert!(!parsed.loaded_from_cache);
+    assert!(dir.join(CACHE_FILE_NAME).exists());
+
+    let cached = Database::new(dir.clone(), resources_dir()).unwrap();
+    assert!(cached.loaded_from_cache);
+    assert_eq!(parsed.spatial_index.len(), cached.spatial_index.len());
+    assert_eq!(parsed.fixes.len(), cached.fixes.len());
+    assert_eq!(parsed.navaids.len(), cached.navaids.len());
+    assert_eq!(parsed.airports.len(), cached.airports.len());
+    assert_eq!(parsed.countries.len(), cached.countries.len());
+    assert_eq!(parsed.airway_graph.segments().len(), cached.airway_graph.segments().len());
+    assert_eq!(2, cached.waypoint_hash.get(&String::from("WENDY")).unwrap().len());
+    assert_eq!(2, cached.airways.get(&String::from("H65")).unwrap().len());
+
+    let fix = cached.fixes.iter().find(|fix| fix.code == "07EBA").unwrap();
+    assert_eq!(Some(String::from("RU ")), fix.waypoint_type);
+    assert_eq!("Tunisia", fix.country.as_ref().unwrap().name);
+    assert!((fix.pos.lat() - 33.492513889).abs() < 0.000001);
+
+    // links to airports and waypoints are restored
+    let bolty = cached.fixes.iter().find(|fix| fix.code == "BOLTY").unwrap();
+    assert_eq!("YMML", bolty.airport.as_ref().unwrap().code());
+    let ksea = cached.airport_hash.get("KSEA").unwrap();
+    assert_eq!(265.0, ksea.runway_end("34C").unwrap().displaced_threshold);
+    let route = cached.airway_graph.airway_route("J1", "SEA", "APH").unwrap();
+    assert_eq!("APH", route.last().unwrap().code());
+
+    assert_eq!(parsed.load_report.total_loaded(), cached.load_report.total_loaded());
+    assert_eq!(1, cached.load_report.total_unresolved());
+
+    // changing a source file invalidates the cache
+    let fixes = fs::read_to_string(dir.join("earth_fix.dat")).unwrap();
+    let fixes: Vec<&str> = fixes.lines().filter(|line| !line.contains(" EG ")).collect();
+    fs::write(dir.join("earth_fix.dat"), fixes.join("\n")).unwrap();
+
+    let reparsed = Database::new(dir.clone(), resources_dir()).unwrap();
+    assert!(!reparsed.loaded_from_cache);
+    assert_eq!(5, reparsed.fixes.len());
+
+    // as does loading in a different mode
+    let lenient = Database::load(dir.clone(), resources_dir(), LoadMode::Lenient).unwrap();
+    assert!(!lenient.loaded_from_cache);
+
+    // a corrupt cache is ignored
+    fs::write(dir.join(CACHE_FILE_NAME), b"OLDNAVDB garbage").unwrap();
+    let db = Database::new(dir.clone(), resources_dir()).unwrap();
+    assert!(!db.loaded_from_cache);
+    assert_eq!(5, db.fixes.len());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_concurrent_cache_writes() {
+    let dir = copy_xp11_data("concurrent-cache");
+
+    // several loads of the same directory at once all write the cache
+    let loads: Vec<thread::JoinHandle<usize>> = (0..4)
+        .map(|_| {
+            let dir = dir.clone();
+            thread::spawn(move || {
+                let db = Database::new(dir, resources_dir()).unwrap();
+                db.fixes.len()
+            })
+        })
+        .collect();
+
+    for load in loads {
+        assert_eq!(6, load.join().unwrap());
+    }
+
+    // leaving a single complete cache, and no temporary files
+    let cached = Database::new(dir.clone(), resources_dir()).unwrap();
+    assert!(cached.loaded_from_cache);
+    assert_eq!(6, cached.fixes.len());
+    let files: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with(CACHE_FILE_NAME.split('.').next().unwrap()))
+        .collect();
+    assert_eq!(vec![CACHE_FILE_NAME.to_string()], files);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_send_sync() {
+    assert_send_sync::<Database>();
+    assert_send_sync::<Waypoint>();
+    assert_send_sync::<Navaid>();
+    assert_send_sync::<Airport>();
+    assert_send_sync::<Route>();
+    assert_send_sync::<AirwayGraph>();
+    assert_send_sync::<Arc<WaypointInterface>>();
+}
+
+#[test]
+fn test_shared_database() {
+    let db = Arc::new(Database::new(copy_xp11_data("shared-database"), resources_dir()).unwrap());
+
+    // a rendering thread, a route finding thread and the flight loop all
+    // query the same database at once
+    let render_db = db.clone();
+    let render = thread::spawn(move || {
+        let mut codes = Vec::new();
+        for _ in 0..100 {
+            codes = render_db.fixes.iter().map(|fix| fix.code.clone()).collect();
+        }
+        codes.len()
+    });
+
+    let route_db = db.clone();
+    let route = thread::spawn(move || {
+        let graph = &route_db.airway_graph;
+        let bolty = graph.find_nodes("BOLTY")[0];
+        let ml = graph.find_nodes("ML")[0];
+
+        let mut route = None;
+        for _ in 0..100 {
+            route = graph.find_route(bolty, ml, None);
+        }
+        route.unwrap()
+    });
+
+    for _ in 0..100 {
+        assert!(db.airport_hash.get("KSEA").is_some());
+        assert_eq!(2, db.waypoint_hash.get(&String::from("WENDY")).unwrap().len());
+    }
+
+    assert_eq!(6, render.join().unwrap());
+
+    // the route found on the other thread can be used on this one
+    let route = route.join().unwrap();
+    let codes: Vec<&str> = route.waypoints.iter().map(|waypoint| waypoint.code()).collect();
+    assert_eq!(vec!["BOLTY", "WENDY", "ML"], codes);
+}
@@ -0,0 +1,114 @@
+extern crate oldnav_lib;
+
+mod common;
+
+use common::Random;
+use oldnav_lib::navdata::coord::SphericalCoordinate;
+use oldnav_lib::navdata::geodesic::{EarthModel, Geodesic, WGS84_A};
+
+/// Reference solutions from GeographicLib, as lat1, lon1, azi1, lat2, lon2,
+/// azi2, s12.
+static REFERENCE: [[f64; 7]; 3] = [
+    [40.0, 0.0, 30.0, 41.79331020506, 137.84490004377, 149.09016931807, 10000000.0],
+    [-30.0, 0.0, 161.89052473633, 29.9, 179.8, 18.09073724574, 19989832.82761],
+    [40.6, -73.8, 51.198882845, 51.6, -0.5, 107.821776735, 5551759.400319],
+];
+
+#[test]
+fn test_reference_inverse() {
+    let wgs84 = Geodesic::wgs84();
+    for r in &REFERENCE {
+        let inverse = wgs84.inverse(r[0], r[1], r[3], r[4]);
+        assert!((inverse.distance - r[6]).abs() < 1e-5, "{:?} {:?}", r, inverse);
+        assert!((inverse.initial_azimuth - r[2]).abs() < 1e-9, "{:?} {:?}", r, inverse);
+        assert!((inverse.final_azimuth - r[5]).abs() < 1e-9, "{:?} {:?}", r, inverse);
+    }
+}
+
+#[test]
+fn test_reference_direct() {
+    let wgs84 = Geodesic::wgs84();
+    for r in &REFERENCE {
+        let direct = wgs84.direct(r[0], r[1], r[2], r[6]);
+        assert!((direct.lat - r[3]).abs() < 1e-9, "{:?} {:?}", r, direct);
+        assert!((direct.lon - r[4]).abs() < 1e-9, "{:?} {:?}", r, direct);
+        assert!((direct.final_azimuth - r[5]).abs() < 1e-9, "{:?} {:?}", r, direct);
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    let wgs84 = Geodesic::wgs84();
+    let mut random = Random::new(4321);
+    let positions: Vec<(f64, f64)> = (0..2000).map(|_| random.position()).collect();
+
+    for pair in positions.chunks(2) {
+        let (lat1, lon1) = pair[0];
+        let (lat2, lon2) = pair[1];
+        let inverse = wgs84.inverse(lat1, lon1, lat2, lon2);
+        let direct = wgs84.direct(lat1, lon1, inverse.initial_azimuth, inverse.distance);
+
+        // within a micrometre of the second position
+        let dlon = (direct.lon - lon2 + 540.0) % 360.0 - 180.0;
+        let error = (direct.lat - lat2).hypot(dlon * lat2.to_radians().cos());
+        assert!(error.to_radians() * WGS84_A < 1e-6, "{:?} {:?}", pair, direct);
+
+        let reverse = wgs84.inverse(lat2, lon2, lat1, lon1);
+        assert!((reverse.distance - inverse.distance).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn test_special_cases() {
+    let wgs84 = Geodesic::wgs84();
+
+    // coincident positions
+    let inverse = wgs84.inverse(-37.67, 144.84, -37.67, 144.84);
+    assert_eq!(0.0, inverse.distance);
+
+    // along the equator, and along a meridian over the pole
+    let equator = wgs84.inverse(0.0, 0.0, 0.0, 1.0);
+    assert!((equator.distance - 111319.490793).abs() < 1e-6);
+    assert!((equator.initial_azimuth - 90.0).abs() < 1e-12);
+    let meridian = wgs84.inverse(10.0, 20.0, 10.0, -160.0);
+    assert!((meridian.initial_azimuth - 0.0).abs() < 1e-12);
+    assert!((meridian.final_azimuth - 180.0).abs() < 1e-12);
+    let quarter_meridian = wgs84.inverse(0.0, 0.0, 90.0, 0.0);
+    assert!((quarter_meridian.distance - 10001965.7293127).abs() < 1e-6);
+    let pole_to_pole = wgs84.inverse(90.0, 0.0, -90.0, 0.0);
+    assert!((pole_to_pole.distance - 2.0 * quarter_meridian.distance).abs() < 1e-6);
+
+    // across the antimeridian
+    let inverse = wgs84.inverse(-16.0, 179.9, -16.0, -179.9);
+    assert!((inverse.distance - 21406.9).abs() < 0.1);
+    assert!((inverse.initial_azimuth + inverse.final_azimuth - 180.0).abs() < 1e-12);
+    let direct = wgs84.direct(-16.0, 179.9, inverse.initial_azimuth, inverse.distance);
+    assert!((direct.lon + 179.9).abs() < 1e-12);
+
+    // a few millimetres
+    let short = wgs84.inverse(51.0, 0.0, 51.0 + 1e-8, 0.0);
+    assert!((short.distance - 0.0011125).abs() < 1e-7);
+}
+
+#[test]
+fn test_earth_model() {
+    let pos1 = SphericalCoordinate::from_geographic(0.0, -37.67, 144.84);
+    let pos2 = SphericalCoordinate::from_geographic(0.0, -33.95, 151.18);
+
+    // the sphere agrees with the great circle
+    let sphere = pos1.distance_to(&pos2, EarthModel::Sphere);
+    assert!((sphere - pos1.arc_distance(&pos2)).abs() < 0.01);
+    assert!((pos1.azimuth_to(&pos2, EarthModel::Sphere) - pos1.bearing_to(&pos2)).abs() < 1e-9);
+
+    // but the ellipsoid differs by up to 0.5%
+    let wgs84 = pos1.distance_to(&pos2, EarthModel::Wgs84);
+    assert!((wgs84 - sphere).abs() > 100.0);
+    assert!((wgs84 - sphere).abs() < sphere * 0.005);
+
+    for &model in &[EarthModel::Sphere, EarthModel::Wgs84] {
+        let azimuth = pos1.azimuth_to(&pos2, model);
+        let distance = pos1.distance_to(&pos2, model);
+        let dest = pos1.destination(azimuth, distance, model);
+        assert!(dest.approx_eq(&pos2, 1e-9), "{:?} {:?}", model, dest);
+    }
+}
@@ -6,7 +6,11 @@ extern crate approx;
 #[macro_use]
 extern crate nalgebra;
 
+mod common;
+
+use common::Random;
 use oldnav_lib::navdata::geohash::*;
+use oldnav_lib::navdata::coord::SphericalCoordinate;
 use nalgebra::Vector2;
 use approx::ApproxEq;
 
@@ -81,3 +85,420 @@ fn test_spherical_rectify_in1() {
     p.spherical_rectify(&LATLON_BOUNDS);
     assert_relative_eq!(p, Vector2::new(5.0, -30.0), epsilon = 0.0001);
 }
+
+/// whether a position is in the cell of one of the hashes in a cover
+fn covered(hashes: &[u64], x: f64, y: f64) -> bool {
+    let p = Vector2::new(x, y);
+    return hashes
+        .iter()
+        .any(|hash| decode(*hash, &LATLON_BOUNDS).unwrap().contains(&p));
+}
+
+/// test covering a box with geohashes
+#[test]
+fn test_cover_bounds() {
+    let region = Bounds::new(140.0, 150.0, -40.0, -30.0);
+    let hashes = cover_bounds(&region, &LATLON_BOUNDS, 24, 64).unwrap();
+    assert!(hashes.len() <= 64);
+
+    for i in 0..11 {
+        for j in 0..11 {
+            assert!(covered(&hashes, 140.0 + i as f64, -40.0 + j as f64));
+        }
+    }
+
+    assert!(!covered(&hashes, 0.0, 0.0));
+    assert!(!covered(&hashes, 145.0, -45.0));
+
+    for hash in &hashes {
+        let cell = decode(*hash, &LATLON_BOUNDS).unwrap();
+        assert!(region.overlap(&cell) != Overlap::Outside);
+    }
+}
+
+/// test covering a box which crosses the antimeridian
+#[test]
+fn test_cover_bounds_antimeridian() {
+    let region = Bounds::new(170.0, -170.0, -10.0, 10.0);
+    let hashes = cover_bounds(&region, &LATLON_BOUNDS, 20, 32).unwrap();
+    assert!(covered(&hashes, 175.0, 0.0));
+    assert!(covered(&hashes, -175.0, 0.0));
+    assert!(covered(&hashes, 180.0, 9.0));
+    assert!(!covered(&hashes, 0.0, 0.0));
+    assert!(!covered(&hashes, 160.0, 0.0));
+}
+
+/// test covering the whole range
+#[test]
+fn test_cover_everything() {
+    let hashes = cover_bounds(&LATLON_BOUNDS, &LATLON_BOUNDS, 20, 32).unwrap();
+    assert_eq!(2, hashes.len());
+    assert!(hashes.iter().all(|hash| hash_precision(*hash) == 1));
+
+    assert!(cover_bounds(&LATLON_BOUNDS, &LATLON_BOUNDS, 0, 32).is_err());
+}
+
+/// test covering a circle around a pole and across the antimeridian
+#[test]
+fn test_cover_cap() {
+    // within 200km of a position 55km from the north pole
+    let centre = SphericalCoordinate::from_geographic(0.0, 89.5, 0.0);
+    let hashes = cover_cap(&centre, 200000.0, 24, 1024).unwrap();
+    assert!(hashes.len() <= 1024);
+    for i in 0..12 {
+        assert!(covered(&hashes, -180.0 + 30.0 * i as f64, 89.0));
+    }
+    assert!(!covered(&hashes, 0.0, 85.0));
+
+    let centre = SphericalCoordinate::from_geographic(0.0, 0.0, 179.9);
+    let hashes = cover_cap(&centre, 50000.0, 24, 64).unwrap();
+    assert!(covered(&hashes, -179.8, 0.0));
+    assert!(covered(&hashes, 179.9, 0.3));
+    assert!(!covered(&hashes, 179.9, 1.0));
+
+    // the number of hashes is limited
+    let hashes = cover_cap(&centre, 500000.0, 40, 8).unwrap();
+    assert!(hashes.len() <= 8);
+    assert!(covered(&hashes, -178.0, 2.0));
+}
+
+/// test covering a polygon
+#[test]
+fn test_cover_polygon() {
+    let triangle = Polygon::new(vec![
+        Vector2::new(0.0, 0.0),
+        Vector2::new(10.0, 0.0),
+        Vector2::new(0.0, 10.0),
+    ]);
+    let hashes = cover_polygon(&triangle, 24, 1024).unwrap();
+    assert!(covered(&hashes, 2.0, 2.0));
+    assert!(covered(&hashes, 0.1, 9.8));
+    assert!(!covered(&hashes, 8.0, 8.0));
+    assert!(!covered(&hashes, -2.0, 5.0));
+
+    // a square across the antimeridian
+    let square = Polygon::new(vec![
+        Vector2::new(170.0, -5.0),
+        Vector2::new(190.0, -5.0),
+        Vector2::new(190.0, 5.0),
+        Vector2::new(170.0, 5.0),
+    ]);
+    let hashes = cover_polygon(&square, 20, 64).unwrap();
+    assert!(covered(&hashes, 175.0, 0.0));
+    assert!(covered(&hashes, -175.0, 0.0));
+    assert!(!covered(&hashes, 0.0, 0.0));
+}
+
+/// the cell bounds of the neighbor of the cell containing `point`
+fn neighbor_bounds(point: Vector2<f64>, precision: u8, dir: (i8, i8)) -> Bounds {
+    let gh = encode(&point, precision, &LATLON_BOUNDS).unwrap();
+    return decode(neighbor(gh, dir, true).unwrap(), &LATLON_BOUNDS).unwrap();
+}
+
+#[test]
+fn test_neighbors() {
+    let point = Vector2::new(144.8, -37.7);
+    let gh = encode(&point, 20, &LATLON_BOUNDS).unwrap();
+    let bounds = decode(gh, &LATLON_BOUNDS).unwrap();
+    let neighbors = neighbors(gh, true);
+    assert_eq!(8, neighbors.len());
+
+    // each neighbor is the cell containing a point one cell over
+    let directions = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+    for (hash, dir) in neighbors.iter().zip(directions.iter()) {
+        let shifted = Vector2::new(
+            point.x + dir.0 as f64 * bounds.x_range(),
+            point.y + dir.1 as f64 * bounds.y_range(),
+        );
+        assert_eq!(encode(&shifted, 20, &LATLON_BOUNDS).unwrap(), *hash);
+        assert_eq!(*hash, neighbor(gh, *dir, false).unwrap());
+    }
+}
+
+#[test]
+fn test_neighbors_antimeridian() {
+    let east = neighbor_bounds(Vector2::new(179.99, 0.5), 20, (1, 0));
+    assert!(east.contains(&Vector2::new(-179.99, 0.5)));
+
+    let west = neighbor_bounds(Vector2::new(-179.99, 0.5), 20, (-1, 0));
+    assert!(west.contains(&Vector2::new(179.99, 0.5)));
+}
+
+#[test]
+fn test_neighbors_poles() {
+    // going north over the pole comes back down on the other side
+    let north = neighbor_bounds(Vector2::new(10.0, 89.99), 20, (0, 1));
+    assert!(north.contains(&Vector2::new(-170.0, 89.99)));
+
+    let south = neighbor_bounds(Vector2::new(-90.0, -89.99), 20, (0, -1));
+    assert!(south.contains(&Vector2::new(90.0, -89.99)));
+
+    // with two columns and one row every direction leads to the other cell
+    let gh = hash_from_string("0").unwrap();
+    assert_eq!(vec![hash_from_string("1").unwrap()], neighbors(gh, true));
+    assert_eq!(1, neighbors(gh, false).len());
+
+    // without wrapping there is nothing past the bounds
+    let corner = encode(&Vector2::new(-179.99, -89.99), 20, &LATLON_BOUNDS).unwrap();
+    assert_eq!(3, neighbors(corner, false).len());
+    assert!(neighbor(corner, (0, -1), false).is_err());
+    assert!(neighbor(corner, (-1, 0), false).is_err());
+    assert_eq!(8, neighbors(corner, true).len());
+}
+
+#[test]
+fn test_hierarchy() {
+    let gh = encode(&Vector2::new(144.8, -37.7), 30, &LATLON_BOUNDS).unwrap();
+    let up = parent(gh).unwrap();
+    assert_eq!(29, hash_precision(up));
+    assert!(children(up).unwrap().contains(&gh));
+    for child in &children(gh).unwrap() {
+        assert_eq!(gh, parent(*child).unwrap());
+        assert!(is_ancestor_of(gh, *child));
+        assert!(!is_ancestor_of(*child, gh));
+    }
+
+    assert!(parent(hash_from_string("1").unwrap()).is_err());
+    assert!(children(extend(gh, PRECISION_MAX).unwrap()).is_err());
+
+    let coarse = truncate(gh, 10).unwrap();
+    assert!(is_ancestor_of(coarse, gh));
+    assert_eq!(Some(coarse), common_prefix(coarse, gh));
+    assert_eq!(coarse, truncate(extend(coarse, 40).unwrap(), 10).unwrap());
+
+    // the cell of an extended hash is the first of the cells inside it
+    let outer = decode(coarse, &LATLON_BOUNDS).unwrap();
+    let inner = decode(extend(coarse, 40).unwrap(), &LATLON_BOUNDS).unwrap();
+    assert_relative_eq!(outer.x_min, inner.x_min);
+    assert_relative_eq!(outer.y_min, inner.y_min);
+
+    let east = encode(&Vector2::new(90.0, 0.0), 30, &LATLON_BOUNDS).unwrap();
+    let west = encode(&Vector2::new(-90.0, 0.0), 30, &LATLON_BOUNDS).unwrap();
+    assert_eq!(None, common_prefix(east, west));
+}
+
+/// published base32 geohashes, with a position inside each of them
+static BASE32_VECTORS: [(&'static str, f64, f64); 4] = [
+    ("wx4g0ec1", 39.92324, 116.3906),
+    ("ezs42", 42.6, -5.6),
+    ("u4pruydqqvj", 57.64911, 10.40744),
+    ("6gkzwgjzn82", -25.382708, -49.265506),
+];
+
+#[test]
+fn test_base32() {
+    for &(string, lat, lon) in &BASE32_VECTORS {
+        let precision = string.len() as u8 * 5;
+        let gh = encode(&Vector2::new(lon, lat), precision, &LATLON_BOUNDS).unwrap();
+        assert_eq!(string, hash_to_base32(gh).unwrap());
+        assert_eq!(gh, hash_from_base32(string).unwrap());
+        assert_eq!(gh, hash_from_base32(&string.to_uppercase()).unwrap());
+
+        let pos = SphericalCoordinate::from_geographic(0.0, lat, lon);
+        let length = string.len() as u8;
+        assert_eq!(string, pos.string_encode(length).unwrap());
+
+        let decoded = SphericalCoordinate::string_decode(string).unwrap();
+        let bounds = decode(gh, &LATLON_BOUNDS).unwrap();
+        assert!(bounds.contains(&Vector2::new(decoded.lon(), decoded.lat())));
+    }
+
+    // the binary and base32 strings hold the same bits
+    let gh = hash_from_base32("s0").unwrap();
+    assert_eq!("1100000000", hash_to_string(gh).unwrap());
+
+    assert!(hash_from_base32("").is_err());
+    assert!(hash_from_base32("u4pruydqqvjx").is_err());
+    assert!(hash_from_base32("ilo").is_err());
+    assert!(hash_to_base32(hash_from_string("1100").unwrap()).is_err());
+    assert!(SphericalCoordinate::from_geographic(0.0, 0.0, 0.0).string_encode(12).is_err());
+}
+
+/// positions spread over and just outside of `range`, including ones on the
+/// boundaries between cells.
+fn scattered_positions(range: &Bounds, n: usize) -> Vec<Vector2<f64>> {
+    let mut random = Random::new(4321);
+
+    let mut positions: Vec<Vector2<f64>> = (0..n)
+        .map(|_| {
+            let x = range.x_min + (random.next() * 1.1 - 0.05) * range.x_range();
+            let y = range.y_min + (random.next() * 1.1 - 0.05) * range.y_range();
+            Vector2::new(x, y)
+        })
+        .collect();
+
+    for bits in 0..30 {
+        let n_cells = (1u64 << bits) as f64;
+        let i = (random.next() * n_cells).floor();
+        positions.push(Vector2::new(
+            range.x_min + i * range.x_range() / n_cells,
+            range.y_min + i * range.y_range() / n_cells,
+        ));
+    }
+
+    positions.push(Vector2::new(range.x_min, range.y_max));
+    positions.push(Vector2::new(range.x_max, range.y_min));
+    return positions;
+}
+
+#[test]
+fn test_encode_matches_bisect() {
+    let ranges = [
+        LATLON_BOUNDS,
+        Bounds::new(0.0, 1.0, 0.0, 1.0),
+        Bounds::new(-10.0, 30.0, 0.0, 100.0),
+    ];
+
+    for range in &ranges {
+        for position in &scattered_positions(range, 2000) {
+            for precision in PRECISION_MIN..PRECISION_MAX + 1 {
+                let gh = encode(position, precision, range).unwrap();
+                assert_eq!(
+                    encode_bisect(position, precision, range).unwrap(),
+                    gh,
+                    "{:?} at precision {}",
+                    position,
+                    precision
+                );
+
+                let bounds = decode(gh, range).unwrap();
+                let expected = decode_precision_bisect(gh, precision, range).unwrap();
+                assert_eq!(expected.x_min, bounds.x_min);
+                assert_eq!(expected.x_max, bounds.x_max);
+                assert_eq!(expected.y_min, bounds.y_min);
+                assert_eq!(expected.y_max, bounds.y_max);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_geohash_errors() {
+    assert_eq!(
+        Err(GeohashError::PrecisionOutOfRange {
+            precision: 59,
+            min: PRECISION_MIN,
+            max: PRECISION_MAX,
+        }),
+        encode(&Vector2::new(0.0, 0.0), 59, &LATLON_BOUNDS)
+    );
+    assert_eq!(
+        Err(GeohashError::PrecisionNotMultiple {
+            precision: 4,
+            multiple: 5,
+        }),
+        hash_to_base32(hash_from_string("1100").unwrap())
+    );
+
+    match hash_from_string("1021") {
+        Err(GeohashError::InvalidString { string, .. }) => assert_eq!("1021", string),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(hash_from_base32("ezs4a").unwrap_err().to_string().contains("ezs4a"));
+
+    // garbage precision bits, and bits past the precision
+    assert!(Geohash::new(63).is_err());
+    let gh = hash_from_string("1101").unwrap();
+    assert_eq!(
+        Err(GeohashError::InvalidBits { geohash: gh | 1 << 20 }),
+        Geohash::new(gh | 1 << 20)
+    );
+    assert!(Geohash::new(extend(gh, PRECISION_MAX).unwrap() | 1 << 63).is_ok());
+
+    let corner = encode(&Vector2::new(179.9, 0.0), 10, &LATLON_BOUNDS).unwrap();
+    assert_eq!(
+        Err(GeohashError::OutOfBounds {
+            geohash: corner,
+            dir: (1, 0),
+        }),
+        neighbor(corner, (1, 0), false)
+    );
+}
+
+#[test]
+fn test_geohash_bounds() {
+    let other_bounds = Bounds::new(0.0, 100.0, 0.0, 100.0);
+    let p = Vector2::new(50.5, 20.0);
+    let latlon = Geohash::encode(&p, 20, &LATLON_BOUNDS).unwrap();
+    let other = Geohash::encode(&p, 20, &other_bounds).unwrap();
+
+    assert_eq!(Some(&other_bounds), other.bounds());
+    assert!(other.decode(&other_bounds).unwrap().contains(&p));
+    assert_eq!(
+        Err(GeohashError::BoundsMismatch {
+            expected: LATLON_BOUNDS,
+            found: other_bounds,
+        }),
+        other.decode(&LATLON_BOUNDS)
+    );
+
+    // operations between hashes check their bounds, unless they are unknown
+    assert!(latlon.is_ancestor_of(&other).is_err());
+    assert!(latlon.common_prefix(&other).is_err());
+    let unknown = Geohash::new(latlon.truncate(10).unwrap().as_u64()).unwrap();
+    assert_eq!(None, unknown.bounds());
+    assert!(unknown.is_ancestor_of(&latlon).unwrap());
+    let prefix = unknown.common_prefix(&latlon).unwrap().unwrap();
+    assert_eq!(Some(&LATLON_BOUNDS), prefix.bounds());
+
+    // derived hashes keep the bounds
+    assert_eq!(Some(&other_bounds), other.parent().unwrap().bounds());
+    assert_eq!(8, other.neighbors(true).len());
+    assert!(other.neighbors(true).iter().all(|n| n.bounds() == Some(&other_bounds)));
+
+    assert!(other.to_base32().is_err());
+    let base32 = Geohash::from_base32("wx4g0ec1").unwrap();
+    assert_eq!("wx4g0ec1", base32.to_base32().unwrap());
+    assert_eq!(40, base32.precision());
+}
+
+#[test]
+fn test_geohash_ordering() {
+    use std::collections::HashSet;
+
+    // every cell at precision 6, and each of their parents
+    let mut hashes: Vec<Geohash> = Vec::new();
+    for x in 0..8 {
+        for y in 0..8 {
+            let gh = Geohash::new(from_cell_indices(x, y, 6)).unwrap();
+            hashes.push(gh);
+            hashes.push(gh.parent().unwrap());
+        }
+    }
+    hashes.reverse();
+    hashes.sort();
+    hashes.dedup();
+    assert_eq!(64 + 32, hashes.len());
+
+    // sorted along the Z-order curve, with each cell before its children
+    for pair in hashes.windows(2) {
+        assert!(pair[0].morton_key() <= pair[1].morton_key());
+        if pair[0].precision() == 5 {
+            assert!(pair[0].is_ancestor_of(&pair[1]).unwrap());
+        }
+    }
+
+    // each quadrant is contiguous
+    let quadrants: Vec<u64> = hashes
+        .iter()
+        .map(|gh| u64::from(gh.truncate(2).unwrap()))
+        .collect();
+    let mut changes = quadrants.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    assert_eq!(3, changes);
+
+    // the same hash of different bounds is different, but sorts next to it
+    let a = Geohash::new(from_cell_indices(3, 3, 6)).unwrap();
+    let b = Geohash::with_bounds(a.as_u64(), &LATLON_BOUNDS).unwrap();
+    assert!(a != b);
+    assert!(a < b);
+    let set: HashSet<Geohash> = vec![a, b, a].into_iter().collect();
+    assert_eq!(2, set.len());
+
+    changes = 0;
+    for gh in &hashes {
+        let parsed: Geohash = gh.to_string().parse().unwrap();
+        assert_eq!(*gh, parsed);
+        changes += 1;
+    }
+    assert_eq!(hashes.len(), changes);
+}
@@ -0,0 +1,188 @@
+extern crate oldnav_lib;
+
+mod common;
+
+use common::scattered_waypoints;
+use oldnav_lib::navdata::coord::SphericalCoordinate;
+use oldnav_lib::navdata::geohash::{Bounds, GeohashError, Geohashable};
+use oldnav_lib::navdata::geohash;
+use oldnav_lib::navdata::geohash_index::{GeohashIndex, IndexCurve};
+use oldnav_lib::navdata::waypoint::Waypoint;
+use std::sync::Arc;
+
+fn position(waypoint: &Arc<Waypoint>) -> SphericalCoordinate {
+    return waypoint.pos;
+}
+
+fn sorted_codes(waypoints: &[&Arc<Waypoint>]) -> Vec<String> {
+    let mut codes: Vec<String> = waypoints.iter().map(|w| w.code.clone()).collect();
+    codes.sort();
+    return codes;
+}
+
+/// A user object with its own position, as a tuple of latitude and
+/// longitude.
+#[derive(Debug, PartialEq)]
+struct Marker {
+    name: &'static str,
+    lat: f64,
+    lon: f64,
+}
+
+impl Marker {
+    fn pos(&self) -> SphericalCoordinate {
+        return SphericalCoordinate::from_geographic(0.0, self.lat, self.lon);
+    }
+}
+
+impl Geohashable<SphericalCoordinate> for Marker {
+    fn integer_decode(geohash: u64) -> Result<SphericalCoordinate, GeohashError> {
+        return SphericalCoordinate::integer_decode(geohash);
+    }
+
+    fn integer_encode(&self, precision: u8) -> Result<u64, GeohashError> {
+        return self.pos().integer_encode(precision);
+    }
+}
+
+#[test]
+fn test_radius_matches_brute_force() {
+    let waypoints = scattered_waypoints(20000);
+    let z_order = GeohashIndex::build(waypoints.clone()).unwrap();
+    let hilbert = GeohashIndex::build_with_curve(waypoints.clone(), IndexCurve::Hilbert).unwrap();
+    assert_eq!(20000, z_order.len());
+    assert_eq!(IndexCurve::Hilbert, hilbert.curve());
+
+    let queries = [
+        (0.0, 0.0, 200000.0),
+        (-37.7, 144.8, 500000.0),
+        (51.5, -0.1, 50000.0),
+        (0.0, 179.9, 300000.0),
+        (-10.0, -179.95, 300000.0),
+        (89.9, 45.0, 400000.0),
+        (-89.5, -120.0, 1000000.0),
+        (30.0, 60.0, 5000000.0),
+    ];
+
+    for &(lat, lon, radius) in &queries {
+        let centre = SphericalCoordinate::from_geographic(0.0, lat, lon);
+        for index in &[&z_order, &hilbert] {
+            let found = index.within_radius(&centre, radius, position);
+
+            let mut expected: Vec<(f64, &Arc<Waypoint>)> = waypoints
+                .iter()
+                .map(|w| (w.pos.arc_distance(&centre), w))
+                .filter(|&(distance, _)| distance <= radius)
+                .collect();
+            expected.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+            let found_codes: Vec<&str> = found.iter().map(|f| f.1.code.as_str()).collect();
+            let expected_codes: Vec<&str> = expected.iter().map(|e| e.1.code.as_str()).collect();
+            assert_eq!(expected_codes, found_codes, "within {} of {}, {}", radius, lat, lon);
+
+            // the candidates contain every result, but not every waypoint
+            let candidates = index.candidates_within_radius(&centre, radius);
+            assert!(candidates.len() >= found.len());
+            assert!(candidates.len() < waypoints.len() / 2);
+        }
+    }
+}
+
+#[test]
+fn test_bounds_matches_brute_force() {
+    let waypoints = scattered_waypoints(20000);
+    let index = GeohashIndex::build_with_curve(waypoints.clone(), IndexCurve::Hilbert).unwrap();
+
+    let boxes = [
+        Bounds::new(140.0, 150.0, -40.0, -30.0),
+        Bounds::new(170.0, -170.0, -20.0, 20.0),
+        Bounds::new(-180.0, 180.0, 80.0, 90.0),
+    ];
+
+    for bounds in &boxes {
+        let found = index.within_bounds(bounds, position);
+        let expected: Vec<&Arc<Waypoint>> = waypoints
+            .iter()
+            .filter(|w| {
+                let in_lons = if bounds.x_min > bounds.x_max {
+                    w.pos.lon() >= bounds.x_min || w.pos.lon() <= bounds.x_max
+                } else {
+                    w.pos.lon() >= bounds.x_min && w.pos.lon() <= bounds.x_max
+                };
+                in_lons && w.pos.lat() >= bounds.y_min && w.pos.lat() <= bounds.y_max
+            })
+            .collect();
+
+        assert!(!expected.is_empty());
+        assert_eq!(sorted_codes(&expected), sorted_codes(&found), "{:?}", bounds);
+    }
+}
+
+#[test]
+fn test_insert_and_remove() {
+    let waypoints = scattered_waypoints(2000);
+    let built = GeohashIndex::build(waypoints.clone()).unwrap();
+
+    let mut index = GeohashIndex::new();
+    for waypoint in waypoints.iter().rev() {
+        index.insert(waypoint.clone()).unwrap();
+    }
+
+    let built_codes: Vec<&Arc<Waypoint>> = built.iter().collect();
+    let inserted_codes: Vec<&Arc<Waypoint>> = index.iter().collect();
+    assert_eq!(sorted_codes(&built_codes), sorted_codes(&inserted_codes));
+
+    let centre = SphericalCoordinate::from_geographic(0.0, 10.0, 10.0);
+    let (n_before, removed, kept) = {
+        let before = index.within_radius(&centre, 2000000.0, position);
+        assert!(before.len() > 2);
+        (before.len(), before[0].1.clone(), before[1].1.clone())
+    };
+
+    assert!(index.remove_by(&removed, Arc::ptr_eq).is_some());
+    assert!(index.remove_by(&removed, Arc::ptr_eq).is_none());
+    assert_eq!(1999, index.len());
+
+    let after = index.within_radius(&centre, 2000000.0, position);
+    assert_eq!(n_before - 1, after.len());
+    assert!(Arc::ptr_eq(&kept, after[0].1));
+}
+
+#[test]
+fn test_user_objects() {
+    let mut index = GeohashIndex::new();
+    index.insert(Marker { name: "home", lat: -37.81, lon: 144.96 }).unwrap();
+    index.insert(Marker { name: "work", lat: -37.82, lon: 144.95 }).unwrap();
+    index.insert(Marker { name: "away", lat: 51.5, lon: -0.12 }).unwrap();
+
+    let centre = SphericalCoordinate::from_geographic(0.0, -37.8, 145.0);
+    let found = index.within_radius(&centre, 10000.0, |marker| marker.pos());
+    let names: Vec<&str> = found.iter().map(|f| f.1.name).collect();
+    assert_eq!(vec!["home", "work"], names);
+
+    let away = Marker { name: "away", lat: 51.5, lon: -0.12 };
+    assert_eq!(Some(away), index.remove(&Marker { name: "away", lat: 51.5, lon: -0.12 }));
+    assert_eq!(2, index.len());
+    assert!(index.remove(&Marker { name: "gone", lat: 0.0, lon: 0.0 }).is_none());
+}
+
+#[test]
+fn test_key_ranges() {
+    let z_order: GeohashIndex<Arc<Waypoint>> = GeohashIndex::new();
+    let hilbert: GeohashIndex<Arc<Waypoint>> = GeohashIndex::with_curve(IndexCurve::Hilbert);
+
+    // the four cells around the centre of the bounds are in separate
+    // quadrants, so far apart along the Z-order curve, but the Hilbert curve
+    // steps straight from one of them to another.
+    let bounds = Bounds::new(-1.0, 1.0, -1.0, 1.0);
+    let cover = geohash::cover_bounds(&bounds, &geohash::LATLON_BOUNDS, 4, 16).unwrap();
+    assert_eq!(4, cover.len());
+    assert_eq!(4, z_order.key_ranges(&cover).len());
+    assert_eq!(3, hilbert.key_ranges(&cover).len());
+
+    // a cell with an odd precision is split into two square cells
+    let cell = geohash::hash_from_string("101").unwrap();
+    let ranges = hilbert.key_ranges(&[cell]);
+    let size: u64 = ranges.iter().map(|&(first, last)| last - first + 1).sum();
+    assert_eq!(1 << (geohash::PRECISION_MAX - 3), size);
+}
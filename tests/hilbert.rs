@@ -0,0 +1,87 @@
+extern crate nalgebra;
+extern crate oldnav_lib;
+
+use nalgebra::Vector2;
+use oldnav_lib::navdata::coord::SphericalCoordinate;
+use oldnav_lib::navdata::geohash::{self, Bounds, LATLON_BOUNDS, PRECISION_BITS, PRECISION_MAX};
+use oldnav_lib::navdata::hilbert::{self, HilbertHashable};
+
+/// The Hilbert hash of the `d`th cell along the curve at `precision`, which
+/// is stored with its coarsest bit lowest.
+fn nth_cell(d: u64, precision: u8) -> u64 {
+    let bits = (0..precision).fold(0, |bits, i| bits | (d >> i & 1) << (precision - 1 - i));
+    return bits << PRECISION_BITS | (precision - 1) as u64;
+}
+
+#[test]
+fn test_curve_steps_between_neighbours() {
+    for &precision in &[2, 4, 8] {
+        let n_cells = 1u64 << precision;
+        let mut visited = vec![false; n_cells as usize];
+        let mut previous: Option<(u64, u64)> = None;
+
+        for d in 0..n_cells {
+            let hash = nth_cell(d, precision);
+            let (x, y, _, _) = geohash::cell_indices(hilbert::to_geohash(hash));
+            let cell = (y << (precision / 2) | x) as usize;
+            assert!(!visited[cell]);
+            visited[cell] = true;
+
+            if let Some((px, py)) = previous {
+                let step = (x as i64 - px as i64).abs() + (y as i64 - py as i64).abs();
+                assert_eq!(1, step, "step {} at precision {}", d, precision);
+            }
+            previous = Some((x, y));
+
+            assert_eq!(hash, hilbert::from_geohash(hilbert::to_geohash(hash)).unwrap());
+        }
+    }
+}
+
+#[test]
+fn test_cells_are_contiguous() {
+    let positions = [
+        Vector2::new(144.8, -37.7),
+        Vector2::new(-0.1, 51.5),
+        Vector2::new(179.99, 0.01),
+        Vector2::new(-180.0, -90.0),
+    ];
+
+    for position in &positions {
+        let fine = hilbert::encode(position, PRECISION_MAX, &LATLON_BOUNDS).unwrap();
+        for precision in (1..PRECISION_MAX / 2 + 1).map(|i| i * 2) {
+            let hash = hilbert::encode(position, precision, &LATLON_BOUNDS).unwrap();
+            let (first, last) = hilbert::key_range(hash);
+            assert!(hilbert::key(fine) >= first && hilbert::key(fine) <= last);
+
+            // the same cell as the geohash
+            let bounds = hilbert::decode(hash, &LATLON_BOUNDS).unwrap();
+            assert!(bounds.contains(position));
+            let gh = geohash::encode(position, precision, &LATLON_BOUNDS).unwrap();
+            assert_eq!(gh, hilbert::to_geohash(hash));
+
+            // the geohash functions on the bits of a hash work on it too
+            assert_eq!(hash, geohash::truncate(fine, precision).unwrap());
+            assert!(geohash::is_ancestor_of(hash, fine));
+            assert_eq!(hilbert::key(hash), geohash::morton_key(hash));
+        }
+    }
+}
+
+#[test]
+fn test_hilbert_hashable() {
+    let pos = SphericalCoordinate::from_geographic(0.0, -37.7, 144.8);
+    let hash = pos.hilbert_encode(40).unwrap();
+    let decoded = SphericalCoordinate::hilbert_decode(hash).unwrap();
+    assert!(decoded.arc_distance(&pos) < 100.0);
+
+    assert!(pos.hilbert_encode(41).is_err());
+    assert!(pos.hilbert_encode(0).is_err());
+    assert!(pos.hilbert_encode(PRECISION_MAX + 2).is_err());
+
+    // other bounds
+    let range = Bounds::new(0.0, 100.0, 0.0, 100.0);
+    let p = Vector2::new(12.5, 80.0);
+    let hash = hilbert::encode(&p, 20, &range).unwrap();
+    assert!(hilbert::decode(hash, &range).unwrap().contains(&p));
+}
@@ -0,0 +1,84 @@
+extern crate oldnav_lib;
+
+mod common;
+
+use oldnav_lib::navdata::database::{Database, DatabaseError, LoadMode};
+use oldnav_lib::navdata::loader::{DatabaseLoader, LoadProgress};
+use common::{copy_xp11_data, resources_dir, test_data_dir};
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_load_with_progress() {
+    let dir = copy_xp11_data("progress");
+
+    let mut reports: Vec<LoadProgress> = Vec::new();
+    let db = Database::load_with_progress(
+        dir.clone(),
+        resources_dir(),
+        LoadMode::Strict,
+        &mut |progress| {
+            reports.push(progress.clone());
+            true
+        },
+    ).unwrap();
+    assert!(!db.loaded_from_cache);
+
+    // each file is reported when it is opened, and when it has been read
+    let fixes: Vec<&LoadProgress> = reports
+        .iter()
+        .filter(|progress| progress.path.ends_with("earth_fix.dat"))
+        .collect();
+    assert_eq!(2, fixes.len());
+    assert_eq!(0, fixes[0].bytes_read);
+    assert_eq!(6, fixes[1].records);
+    assert_eq!(fixes[1].total_bytes, fixes[1].bytes_read);
+    assert_eq!(1.0, fixes[1].fraction());
+
+    // loading is cancelled as soon as the callback returns false
+    fs::remove_file(dir.join("oldnav.cache")).unwrap();
+    let mut n_reports = 0;
+    let result = Database::load_with_progress(
+        dir.clone(),
+        resources_dir(),
+        LoadMode::Strict,
+        &mut |_| {
+            n_reports += 1;
+            n_reports < 3
+        },
+    );
+    match result {
+        Err(DatabaseError::Cancelled) => {}
+        other => panic!("Expected loading to be cancelled, got {:?}", other),
+    }
+    assert_eq!(3, n_reports);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_background_loader() {
+    let dir = copy_xp11_data("background");
+
+    let mut loader = DatabaseLoader::spawn(dir.clone(), resources_dir(), LoadMode::Strict);
+    let db = loop {
+        if let Some(result) = loader.poll() {
+            break result.unwrap();
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    assert!(loader.is_finished());
+    assert!(loader.progress().is_some());
+    assert!(loader.poll().is_none());
+    assert_eq!(6, db.fixes.len());
+
+    let loader = DatabaseLoader::spawn(test_data_dir("missing"), resources_dir(), LoadMode::Strict);
+    match loader.wait() {
+        Err(DatabaseError::NoNavdata { .. }) => {}
+        other => panic!("Expected no navdata to be found, got {:?}", other),
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
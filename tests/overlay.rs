@@ -0,0 +1,186 @@
+extern crate oldnav_lib;
+
+mod common;
+
+use oldnav_lib::navdata::database::{Database, DatabaseError};
+use oldnav_lib::navdata::overlay::{Overlay, WaypointSource, OverlayMatch};
+use oldnav_lib::navdata::waypoint::Waypoint;
+use oldnav_lib::navdata::kind::{WaypointKind, WaypointKinds};
+use oldnav_lib::navdata::coord::SphericalCoordinate;
+use common::{copy_xp11_data, resources_dir, test_data_dir};
+use std::sync::Arc;
+use std::{env, fs, process};
+
+fn load_overlay(name: &str) -> Overlay {
+    let db = Database::new(copy_xp11_data(name), resources_dir()).unwrap();
+    return Overlay::load_xplane(Arc::new(db), &test_data_dir("overlay")).unwrap();
+}
+
+fn find_in_region<'a>(matches: &'a [OverlayMatch], region: &str) -> &'a OverlayMatch {
+    return matches
+        .iter()
+        .find(|m| m.waypoint.region() == region)
+        .unwrap();
+}
+
+#[test]
+fn test_overlay_precedence() {
+    let overlay = load_overlay("overlay-precedence");
+
+    // Custom Data overrides the user waypoint, which overrides the default
+    let wendy = overlay.find("WENDY", WaypointKinds::all());
+    assert_eq!(2, wendy.len());
+
+    let eg = find_in_region(&wendy, "EG");
+    assert_eq!(WaypointSource::CustomData, eg.source);
+    assert!((eg.waypoint.pos().lat() - 51.6).abs() < 0.000001);
+
+    let ym = find_in_region(&wendy, "YM");
+    assert_eq!(WaypointSource::User, ym.source);
+    assert_eq!(WaypointKind::User, ym.waypoint.kind());
+    assert_eq!(WaypointKind::EnrouteFix, eg.waypoint.kind());
+    assert!((ym.waypoint.pos().lat() - -37.5).abs() < 0.000001);
+
+    // user waypoints extend the default navdata
+    let home = overlay.find("HOME", WaypointKinds::all());
+    assert_eq!(1, home.len());
+    assert_eq!("HOME FIELD", home[0].waypoint.name());
+
+    // a user navaid overrides a default navaid of the same type
+    let aph = overlay.find("APH", WaypointKinds::all());
+    assert_eq!(1, aph.len());
+    assert_eq!(WaypointSource::User, aph[0].source);
+    assert_eq!(1, overlay.find("XYZ", WaypointKinds::all()).len());
+
+    // waypoints which aren't overridden come from the database
+    let bolty = overlay.find("BOLTY", WaypointKinds::all());
+    assert_eq!(WaypointSource::Default, bolty[0].source);
+    assert_eq!(WaypointSource::Default, overlay.find("KSEA", WaypointKinds::all())[0].source);
+
+    // the database itself is unchanged
+    assert_eq!(6, overlay.base.fixes.len());
+    assert_eq!(3, overlay.load_report.files.len());
+}
+
+#[test]
+fn test_overlay_kinds() {
+    let overlay = load_overlay("overlay-kinds");
+
+    // the user waypoint and the Custom Data fix share an ident
+    let wendy = overlay.find("WENDY", WaypointKinds::from(WaypointKind::User));
+    assert_eq!(1, wendy.len());
+    assert_eq!("YM", wendy[0].waypoint.region());
+    assert_eq!(WaypointSource::User, wendy[0].source);
+
+    let wendy = overlay.find("WENDY", WaypointKinds::from(WaypointKind::EnrouteFix));
+    assert_eq!(1, wendy.len());
+    assert_eq!(WaypointSource::CustomData, wendy[0].source);
+
+    // the VOR and DME elements of a VORTAC in the database
+    let sea = overlay.find("SEA", WaypointKinds::from(WaypointKind::Dme));
+    assert_eq!(1, sea.len());
+    assert_eq!(WaypointKind::Dme, sea[0].waypoint.kind());
+
+    assert_eq!(1, overlay.find("APH", WaypointKinds::from(WaypointKind::Ndb)).len());
+    assert!(overlay.find("APH", WaypointKinds::fixes()).is_empty());
+}
+
+#[test]
+fn test_user_waypoints() {
+    let mut overlay = load_overlay("user-waypoints");
+
+    let pos = SphericalCoordinate::from_geographic(0.0, -37.8, 145.0);
+    let mut waypoint = Waypoint::new("BOLTY", "BOLTY", pos, None);
+    waypoint.region = String::from("YM");
+    overlay.add_user_waypoint(waypoint);
+
+    let bolty = overlay.find("BOLTY", WaypointKinds::all());
+    assert_eq!(1, bolty.len());
+    assert_eq!(WaypointSource::User, bolty[0].source);
+    assert_eq!(WaypointKind::User, bolty[0].waypoint.kind());
+
+    // removing the user waypoint reveals the default one again
+    assert!(overlay.remove_user_waypoint("BOLTY", "YM"));
+    assert!(!overlay.remove_user_waypoint("BOLTY", "YM"));
+    assert_eq!(WaypointSource::Default, overlay.find("BOLTY", WaypointKinds::all())[0].source);
+
+    // removing a user waypoint which overrides Custom Data changes nothing
+    assert!(overlay.remove_user_waypoint("WENDY", "EG"));
+    let wendy = overlay.find("WENDY", WaypointKinds::all());
+    assert_eq!(WaypointSource::CustomData, find_in_region(&wendy, "EG").source);
+
+    overlay.read_waypoint_list(&test_data_dir("overlay").join("waypoints.txt")).unwrap();
+    let circ1 = overlay.find("CIRC1", WaypointKinds::all());
+    assert_eq!(1, circ1.len());
+    assert_eq!("Crosswind turn", circ1[0].waypoint.name());
+    assert_eq!("CIRC2", overlay.find("CIRC2", WaypointKinds::all())[0].waypoint.name());
+}
+
+#[test]
+fn test_save_user_waypoints() {
+    let mut overlay = load_overlay("save-user-waypoints");
+    let pos = SphericalCoordinate::from_geographic(0.0, -37.9, 145.4);
+    let mut waypoint = Waypoint::new("NEWWP", "NEW WAYPOINT", pos, None);
+    waypoint.region = String::from("YM");
+    overlay.add_user_waypoint(waypoint);
+
+    // no region or type, and a name which starts with a number
+    let pos = SphericalCoordinate::from_geographic(0.0, -37.8, 145.3);
+    overlay.add_user_waypoint(Waypoint::new("MILE3", "3 MILE", pos, None));
+
+    let prefix = format!("oldnav-user_fix-{}", process::id());
+    let path = env::temp_dir().join(format!("{}.dat", prefix));
+    fs::write(&path, "I\n1101 Version - replaced\n\n99\n").unwrap();
+    overlay.save_user_waypoints(&path).unwrap();
+
+    // the existing file is replaced, and no temporary file is left behind
+    let files = fs::read_dir(env::temp_dir())
+        .unwrap()
+        .filter(|entry| {
+            entry.as_ref().unwrap().file_name().to_string_lossy().starts_with(&prefix)
+        })
+        .count();
+    assert_eq!(1, files);
+
+    // the saved waypoints are read back into a new overlay
+    let mut reloaded = Overlay::new(overlay.base.clone());
+    reloaded.read_fixes(&path, WaypointSource::User).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let saved: Vec<String> = overlay.user_waypoints().iter().map(|w| w.code.clone()).collect();
+    let loaded: Vec<String> = reloaded.user_waypoints().iter().map(|w| w.code.clone()).collect();
+    assert_eq!(vec!["HOME", "MILE3", "NEWWP", "WENDY", "WENDY"], saved);
+    assert_eq!(saved, loaded);
+
+    let new = reloaded.find("NEWWP", WaypointKinds::all());
+    assert_eq!("NEW WAYPOINT", new[0].waypoint.name());
+    assert!((new[0].waypoint.pos().lat() - -37.9).abs() < 0.000001);
+    assert!((new[0].waypoint.pos().lon() - 145.4).abs() < 0.000001);
+
+    let home = reloaded.user_waypoints().into_iter().find(|w| w.code == "HOME").unwrap();
+    assert_eq!(Some(String::from("RU ")), home.waypoint_type);
+
+    let mile3 = reloaded.user_waypoints().into_iter().find(|w| w.code == "MILE3").unwrap();
+    assert_eq!("3 MILE", mile3.name);
+    assert_eq!("", mile3.region);
+    assert_eq!(None, mile3.waypoint_type);
+    assert!(reloaded.remove_user_waypoint("MILE3", ""));
+}
+
+#[test]
+fn test_invalid_waypoint_list() {
+    let mut overlay = load_overlay("invalid-waypoint-list");
+    let path = env::temp_dir().join(format!("oldnav-waypoints-{}.txt", process::id()));
+    fs::write(&path, "-37.6, 145.2, CIRC1\nnorth, 145.2, CIRC2\n").unwrap();
+
+    let result = overlay.read_waypoint_list(&path);
+    fs::remove_file(&path).unwrap();
+
+    match result {
+        Err(DatabaseError::Parse { line, .. }) => assert_eq!(2, line),
+        other => panic!("Expected a parse error, got {:?}", other),
+    }
+
+    // nothing is added from an invalid list
+    assert!(overlay.find("CIRC1", WaypointKinds::all()).is_empty());
+}
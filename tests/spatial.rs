@@ -0,0 +1,101 @@
+extern crate oldnav_lib;
+
+mod common;
+
+use common::{scattered_waypoints, waypoint};
+use oldnav_lib::navdata::coord::SphericalCoordinate;
+use oldnav_lib::navdata::geohash::Bounds;
+use oldnav_lib::navdata::kind::{WaypointKind, WaypointKinds};
+use oldnav_lib::navdata::spatial::SpatialIndex;
+use oldnav_lib::navdata::waypoint::{Waypoint, WaypointInterface};
+use std::sync::Arc;
+
+fn sorted_codes(waypoints: &[Arc<WaypointInterface>]) -> Vec<&str> {
+    let mut codes: Vec<&str> = waypoints.iter().map(|waypoint| waypoint.code()).collect();
+    codes.sort();
+    return codes;
+}
+
+#[test]
+fn test_nearest_matches_brute_force() {
+    let waypoints = scattered_waypoints(20000);
+    let mut index = SpatialIndex::new();
+    for waypoint in &waypoints {
+        index.insert(waypoint.clone());
+    }
+    assert_eq!(20000, index.len());
+
+    let positions = [
+        (0.0, 0.0),
+        (-37.7, 144.8),
+        (51.5, -0.1),
+        (0.0, 179.9),
+        (-10.0, -179.95),
+        (89.9, 45.0),
+        (-89.5, -120.0),
+    ];
+
+    for &(lat, lon) in &positions {
+        let pos = SphericalCoordinate::from_geographic(0.0, lat, lon);
+        let nearest = index.nearest(&pos, 20, WaypointKinds::all());
+
+        let mut expected: Vec<(f64, &Arc<Waypoint>)> = waypoints
+            .iter()
+            .map(|waypoint| (waypoint.pos.arc_distance(&pos), waypoint))
+            .collect();
+        expected.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let found: Vec<&str> = nearest.iter().map(|c| c.waypoint.code()).collect();
+        let expected: Vec<&str> = expected[..20].iter().map(|e| e.1.code.as_str()).collect();
+        assert_eq!(expected, found, "nearest to {}, {}", lat, lon);
+    }
+}
+
+#[test]
+fn test_radius_across_antimeridian_and_poles() {
+    let mut index = SpatialIndex::new();
+    index.insert(waypoint("EAST", 0.0, 179.9));
+    index.insert(waypoint("WEST", 0.0, -179.9));
+    index.insert(waypoint("FAR", 0.0, 170.0));
+    index.insert(waypoint("POLE1", 89.9, 0.0));
+    index.insert(waypoint("POLE2", 89.9, 180.0));
+
+    let dateline = SphericalCoordinate::from_geographic(0.0, 0.0, -179.99);
+    let found = index.within_radius_nm(&dateline, 20.0, WaypointKinds::all());
+    let codes: Vec<&str> = found.iter().map(|c| c.waypoint.code()).collect();
+    assert_eq!(vec!["WEST", "EAST"], codes);
+    assert!(found[0].distance < found[1].distance);
+
+    // the two polar waypoints are 22km apart, across the pole
+    let north = SphericalCoordinate::from_geographic(0.0, 89.95, 90.0);
+    let found = index.within_radius(&north, 15000.0, WaypointKinds::all());
+    let found: Vec<Arc<WaypointInterface>> = found.into_iter().map(|c| c.waypoint).collect();
+    assert_eq!(vec!["POLE1", "POLE2"], sorted_codes(&found));
+
+    // the next closest is on the equator, closest to the 90th meridian
+    let nearest = index.nearest(&north, 3, WaypointKinds::all());
+    assert_eq!(3, nearest.len());
+    assert_eq!("FAR", nearest[2].waypoint.code());
+
+    // more waypoints are asked for than there are
+    assert_eq!(5, index.nearest(&north, 10, WaypointKinds::all()).len());
+    assert!(index.nearest(&north, 10, WaypointKind::Ndb.into()).is_empty());
+}
+
+#[test]
+fn test_within_bounds() {
+    let mut index = SpatialIndex::new();
+    index.insert(waypoint("EAST", 0.0, 179.9));
+    index.insert(waypoint("WEST", 5.0, -179.9));
+    index.insert(waypoint("FAR", 0.0, 170.0));
+    index.insert(waypoint("NORTH", 20.0, 179.9));
+
+    // a box crossing the antimeridian
+    let bounds = Bounds::new(175.0, -175.0, -10.0, 10.0);
+    let found = index.within_bounds(&bounds, WaypointKinds::all());
+    assert_eq!(vec!["EAST", "WEST"], sorted_codes(&found));
+
+    let bounds = Bounds::new(160.0, 180.0, -10.0, 30.0);
+    let found = index.within_bounds(&bounds, WaypointKinds::all());
+    assert_eq!(vec!["EAST", "FAR", "NORTH"], sorted_codes(&found));
+}